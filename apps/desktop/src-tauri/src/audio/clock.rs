@@ -0,0 +1,268 @@
+//! Sample-Accurate Audio Clock
+//!
+//! CPAL callbacks arrive in bursts (WASAPI can deliver 0, 1 or 3 periods per wakeup) and the
+//! device clock drifts slightly against the system clock. The pipe to FFmpeg carries raw f32le
+//! with no timestamps, so every frame written *is* the timeline: writing too many stretches audio,
+//! writing too few shrinks it.
+//!
+//! [AudioClock] sits between the callbacks and the pipe:
+//! 1. Incoming frames are queued in a ring buffer.
+//! 2. Output is paced by elapsed system time: after `t` seconds exactly `t * rate` frames have been
+//!    written (minus a small fixed latency that absorbs jitter).
+//! 3. Drift is corrected by resampling the queue slightly faster or slower depending on its fill level.
+//! 4. Silence is only inserted for true gaps (no input for longer than the gap threshold).
+
+use std::collections::VecDeque;
+use std::time::Duration;
+
+use crate::constants::{AUDIO_CLOCK_LATENCY_MS, AUDIO_GAP_THRESHOLD_MS, AUDIO_MAX_BUFFER_MS};
+
+/// Maximum resampling correction applied for drift (0.5%).
+const MAX_DRIFT_CORRECTION: f64 = 0.005;
+/// Correction per second of excess buffered audio.
+const DRIFT_GAIN: f64 = 0.25;
+/// Smoothing of the fill level so callback bursts don't modulate the pitch.
+const FILL_SMOOTHING: f64 = 0.02;
+
+pub struct AudioClock {
+    sample_rate: u32,
+    channels: usize,
+    ring: VecDeque<f32>,
+    /// Fractional read position into the front frame of `ring`.
+    frac: f64,
+    frames_out: u64,
+    latency_frames: u64,
+    gap_threshold: Duration,
+    max_buffer_frames: usize,
+    last_input_at: Option<Duration>,
+    /// Smoothed queue fill level in frames, drives drift correction.
+    fill_avg: f64,
+}
+
+impl AudioClock {
+    pub fn new(sample_rate: u32, channels: u16) -> Self {
+        Self::with_timing(
+            sample_rate,
+            channels,
+            Duration::from_millis(AUDIO_CLOCK_LATENCY_MS),
+            Duration::from_millis(AUDIO_GAP_THRESHOLD_MS),
+            Duration::from_millis(AUDIO_MAX_BUFFER_MS),
+        )
+    }
+
+    pub fn with_timing(sample_rate: u32, channels: u16, latency: Duration, gap_threshold: Duration, max_buffer: Duration) -> Self {
+        let rate = sample_rate.max(1);
+        Self {
+            sample_rate: rate,
+            channels: channels.max(1) as usize,
+            ring: VecDeque::new(),
+            frac: 0.0,
+            frames_out: 0,
+            latency_frames: (latency.as_secs_f64() * rate as f64) as u64,
+            gap_threshold,
+            max_buffer_frames: (max_buffer.as_secs_f64() * rate as f64) as usize,
+            last_input_at: None,
+            fill_avg: latency.as_secs_f64() * rate as f64,
+        }
+    }
+
+    /// Queues interleaved samples delivered by a callback at `now` (time since the clock started).
+    /// Returns the number of frames dropped because the queue overflowed.
+    pub fn push(&mut self, samples: &[f32], now: Duration) -> u64 {
+        self.ring.extend(samples);
+        self.last_input_at = Some(now);
+
+        let buffered = self.buffered_frames();
+        if buffered > self.max_buffer_frames {
+            // A stall followed by a huge burst: keep the newest audio rather than building latency.
+            let excess = buffered - self.max_buffer_frames;
+            self.ring.drain(..excess * self.channels);
+            self.frac = 0.0;
+            return excess as u64;
+        }
+        0
+    }
+
+    /// Returns the interleaved samples due at `now`, so that the total written tracks elapsed time.
+    /// Also returns how many of the emitted frames were padded silence.
+    pub fn pull(&mut self, now: Duration) -> (Vec<f32>, u64) {
+        let target = (now.as_secs_f64() * self.sample_rate as f64) as u64;
+        let due = target.saturating_sub(self.latency_frames).saturating_sub(self.frames_out) as usize;
+        if due == 0 {
+            return (Vec::new(), 0);
+        }
+
+        self.fill_avg += (self.buffered_frames() as f64 - self.fill_avg) * FILL_SMOOTHING;
+        let ratio = self.drift_ratio();
+        let mut output = Vec::with_capacity(due * self.channels);
+        let available = self.buffered_frames();
+
+        let mut produced = 0;
+        while produced < due {
+            let pos = self.frac + produced as f64 * ratio;
+            let i = pos.floor() as usize;
+            if i + 1 >= available {
+                break;
+            }
+            let t = (pos - i as f64) as f32;
+            for c in 0..self.channels {
+                let a = self.ring[i * self.channels + c];
+                let b = self.ring[(i + 1) * self.channels + c];
+                output.push(a + (b - a) * t);
+            }
+            produced += 1;
+        }
+
+        let consumed = self.frac + produced as f64 * ratio;
+        let whole = (consumed.floor() as usize).min(available);
+        self.ring.drain(..whole * self.channels);
+        self.frac = consumed - whole as f64;
+
+        let mut silence = 0;
+        if produced < due && self.in_gap(now) {
+            // True gap: pad the rest so the timeline keeps moving. A leftover partial frame is discarded.
+            self.ring.clear();
+            self.frac = 0.0;
+            silence = (due - produced) as u64;
+            output.resize(due * self.channels, 0.0);
+            produced = due;
+        }
+
+        self.frames_out += produced as u64;
+        (output, silence)
+    }
+
+    pub fn frames_out(&self) -> u64 {
+        self.frames_out
+    }
+
+    fn buffered_frames(&self) -> usize {
+        self.ring.len() / self.channels
+    }
+
+    fn in_gap(&self, now: Duration) -> bool {
+        match self.last_input_at {
+            Some(last) => now.saturating_sub(last) >= self.gap_threshold,
+            None => now >= self.gap_threshold,
+        }
+    }
+
+    /// Input frames consumed per output frame. Above 1.0 when audio is piling up (device clock
+    /// faster than the system clock), below 1.0 when the queue is running dry.
+    fn drift_ratio(&self) -> f64 {
+        let excess = self.fill_avg - self.latency_frames as f64;
+        let correction = (excess / self.sample_rate as f64) * DRIFT_GAIN;
+        1.0 + correction.clamp(-MAX_DRIFT_CORRECTION, MAX_DRIFT_CORRECTION)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const RATE: u32 = 48000;
+
+    fn clock() -> AudioClock {
+        AudioClock::with_timing(RATE, 2, Duration::from_millis(40), Duration::from_millis(100), Duration::from_millis(500))
+    }
+
+    fn expected_frames(now: Duration) -> u64 {
+        (now.as_secs_f64() * RATE as f64) as u64 - (RATE as u64 * 40 / 1000)
+    }
+
+    /// Drives the clock for `seconds` with a 1ms tick. `deliver(ms)` returns how many frames the
+    /// device hands over at that millisecond (bursty, drifting, or zero).
+    fn simulate(clock: &mut AudioClock, seconds: u64, mut deliver: impl FnMut(u64) -> usize) -> u64 {
+        let mut silence = 0;
+        for ms in 1..=seconds * 1000 {
+            let now = Duration::from_millis(ms);
+            let frames = deliver(ms);
+            if frames > 0 {
+                clock.push(&vec![0.1; frames * 2], now);
+            }
+            // Pipe task pulls every 20ms or whenever data arrives
+            if frames > 0 || ms % 20 == 0 {
+                silence += clock.pull(now).1;
+            }
+        }
+        silence
+    }
+
+    #[test]
+    fn test_bursty_callbacks_match_elapsed_time() {
+        let mut clock = clock();
+        // 10ms periods (480 frames), but the callback only wakes up on some of them and then
+        // hands over everything that piled up (bursts of 0-40ms)
+        let wakeups = [true, false, false, true, true, false, true, false, false, false, true, true];
+        let mut owed = 0usize;
+        let mut period = 0;
+        let silence = simulate(&mut clock, 60, |ms| {
+            if ms % 10 != 0 {
+                return 0;
+            }
+            owed += 480;
+            period += 1;
+            if wakeups[period % wakeups.len()] {
+                std::mem::take(&mut owed)
+            } else {
+                0
+            }
+        });
+
+        let expected = expected_frames(Duration::from_secs(60));
+        let diff = clock.frames_out() as i64 - expected as i64;
+        assert!(diff.abs() <= 960, "expected ~{} frames, got {}", expected, clock.frames_out());
+        assert_eq!(silence, 0, "bursty but continuous input must not be padded");
+    }
+
+    #[test]
+    fn test_drifting_device_stays_locked() {
+        // Device clock 0.2% fast and 0.2% slow against the system clock
+        for device_rate in [48096u64, 47904] {
+            let mut clock = clock();
+            let mut delivered = 0u64;
+            let silence = simulate(&mut clock, 120, |ms| {
+                if ms % 10 == 0 {
+                    let should = ms * device_rate / 1000;
+                    let frames = should - delivered;
+                    delivered = should;
+                    return frames as usize;
+                }
+                0
+            });
+
+            let expected = expected_frames(Duration::from_secs(120));
+            let diff = clock.frames_out() as i64 - expected as i64;
+            assert!(diff.abs() <= 960, "{}Hz device: expected ~{} frames, got {}", device_rate, expected, clock.frames_out());
+            assert_eq!(silence, 0, "{}Hz device: drift must be resampled, not padded", device_rate);
+            // Queue must not grow without bound (would mean latency creeping up)
+            assert!(clock.buffered_frames() < (RATE as usize / 10), "{}Hz device: buffer grew to {}", device_rate, clock.buffered_frames());
+        }
+    }
+
+    #[test]
+    fn test_true_gap_is_padded_with_silence() {
+        let mut clock = clock();
+        // Normal for 2s, device gone for 1s, back for 2s
+        let silence = simulate(&mut clock, 5, |ms| {
+            if ms % 10 == 0 && !(2000..3000).contains(&ms) {
+                480
+            } else {
+                0
+            }
+        });
+
+        let expected = expected_frames(Duration::from_secs(5));
+        let diff = clock.frames_out() as i64 - expected as i64;
+        assert!(diff.abs() <= 960, "expected ~{} frames, got {}", expected, clock.frames_out());
+        assert!((40000..=52000).contains(&silence), "expected ~1s of silence, got {} frames", silence);
+    }
+
+    #[test]
+    fn test_overflow_drops_oldest() {
+        let mut clock = clock();
+        let dropped = clock.push(&vec![0.0; RATE as usize * 2], Duration::from_millis(10));
+        assert_eq!(dropped, (RATE as u64) - (RATE as u64 / 2));
+        assert_eq!(clock.buffered_frames(), RATE as usize / 2);
+    }
+}
@@ -0,0 +1,154 @@
+//! Interleaved f32 format conversion (channel layout + sample rate).
+//!
+//! The FFmpeg audio process is told the pipe format once at spawn time (`-ar`/`-ac`).
+//! When the supervisor swaps to a device with a different native format, samples are
+//! converted here so the pipe keeps its original format.
+
+/// Converts interleaved f32 audio from one (rate, channels) pair to another.
+/// Keeps interpolation state between calls so block boundaries don't click.
+pub struct FormatConverter {
+    from_channels: u16,
+    to_channels: u16,
+    /// Input frames consumed per output frame.
+    step: f64,
+    /// Read position of the next output frame, relative to the start of the next block.
+    /// -1.0 refers to `last_frame`.
+    position: f64,
+    last_frame: Vec<f32>,
+}
+
+impl FormatConverter {
+    pub fn new(from_rate: u32, from_channels: u16, to_rate: u32, to_channels: u16) -> Self {
+        Self {
+            from_channels: from_channels.max(1),
+            to_channels: to_channels.max(1),
+            step: from_rate.max(1) as f64 / to_rate.max(1) as f64,
+            position: 0.0,
+            last_frame: vec![0.0; to_channels.max(1) as usize],
+        }
+    }
+
+    pub fn is_passthrough(&self) -> bool {
+        self.from_channels == self.to_channels && self.step == 1.0
+    }
+
+    pub fn process(&mut self, input: &[f32]) -> Vec<f32> {
+        if self.is_passthrough() {
+            return input.to_vec();
+        }
+
+        let mapped = remap_channels(input, self.from_channels, self.to_channels);
+        if self.step == 1.0 {
+            return mapped;
+        }
+
+        self.resample(&mapped)
+    }
+
+    fn resample(&mut self, input: &[f32]) -> Vec<f32> {
+        let ch = self.to_channels as usize;
+        let frames = input.len() / ch;
+        if frames == 0 {
+            return Vec::new();
+        }
+
+        let frame = |i: isize| -> &[f32] {
+            if i < 0 {
+                &self.last_frame
+            } else {
+                &input[i as usize * ch..(i as usize + 1) * ch]
+            }
+        };
+
+        let mut output = Vec::with_capacity(((frames as f64 / self.step) as usize + 1) * ch);
+        let mut position = self.position;
+
+        // Interpolate between frame(i) and frame(i + 1); both must be available in this block.
+        while position < (frames - 1) as f64 {
+            let i = position.floor() as isize;
+            let frac = (position - i as f64) as f32;
+            let (a, b) = (frame(i), frame(i + 1));
+            for c in 0..ch {
+                output.push(a[c] + (b[c] - a[c]) * frac);
+            }
+            position += self.step;
+        }
+
+        self.position = position - frames as f64;
+        self.last_frame = input[(frames - 1) * ch..].to_vec();
+        output
+    }
+}
+
+/// Maps interleaved samples between channel counts.
+/// Mono is duplicated when upmixing; everything is averaged when downmixing to mono.
+/// Other layouts keep the first channels (FL/FR for surround to stereo).
+pub fn remap_channels(input: &[f32], from: u16, to: u16) -> Vec<f32> {
+    if from == to {
+        return input.to_vec();
+    }
+
+    let (from, to) = (from as usize, to as usize);
+    let frames = input.len() / from;
+    let mut output = Vec::with_capacity(frames * to);
+
+    for frame in input.chunks_exact(from) {
+        if to == 1 {
+            output.push(frame.iter().sum::<f32>() / from as f32);
+        } else if from == 1 {
+            output.extend(std::iter::repeat(frame[0]).take(to));
+        } else {
+            for c in 0..to {
+                output.push(frame[c % from]);
+            }
+        }
+    }
+
+    output
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_remap_channels() {
+        assert_eq!(remap_channels(&[0.5, -0.5], 1, 2), vec![0.5, 0.5, -0.5, -0.5]);
+        assert_eq!(remap_channels(&[0.2, 0.4, 1.0, 0.0], 2, 1), vec![0.3, 0.5]);
+        // 5.1 -> Stereo keeps front left/right
+        assert_eq!(remap_channels(&[1.0, 2.0, 3.0, 4.0, 5.0, 6.0], 6, 2), vec![1.0, 2.0]);
+    }
+
+    #[test]
+    fn test_passthrough() {
+        let mut converter = FormatConverter::new(48000, 2, 48000, 2);
+        assert!(converter.is_passthrough());
+        assert_eq!(converter.process(&[0.1, 0.2]), vec![0.1, 0.2]);
+    }
+
+    #[test]
+    fn test_resample_frame_count_across_blocks() {
+        // 44.1kHz mono -> 48kHz stereo, fed in uneven blocks like a real callback
+        let mut converter = FormatConverter::new(44100, 1, 48000, 2);
+        let mut output_frames = 0;
+        let mut input_frames = 0;
+
+        for block in [441, 512, 97, 1024, 2336].iter().cycle().take(50) {
+            let input = vec![0.25f32; *block];
+            input_frames += block;
+            output_frames += converter.process(&input).len() / 2;
+        }
+
+        let expected = input_frames as f64 * 48000.0 / 44100.0;
+        assert!((output_frames as f64 - expected).abs() <= 2.0, "expected ~{}, got {}", expected, output_frames);
+    }
+
+    #[test]
+    fn test_resample_preserves_dc_level() {
+        let mut converter = FormatConverter::new(48000, 2, 44100, 2);
+        // Prime with the same level so the first interpolation doesn't start from silence
+        converter.process(&[0.5; 64]);
+        let output = converter.process(&[0.5; 960]);
+        assert!(output.iter().all(|s| (s - 0.5).abs() < 1e-6));
+    }
+}
@@ -0,0 +1,313 @@
+//! Audio Device Enumeration
+//!
+//! CPAL only exposes friendly names, which collide for identical headsets and change when
+//! Windows renumbers an endpoint ("Headset (2- HyperX)" -> "Headset (HyperX)").
+//! Devices are therefore given a derived ID: host API, direction, the name with the Windows
+//! instance prefix stripped, and an ordinal among devices sharing that name.
+//!
+//! Devices are resolved by ID first, then exact name, then normalized name. A name shared by
+//! several devices matches none of them, as does the ID of one of several identical devices once
+//! it's gone: the ordinals of the rest shift, so falling back to the name would pick another one.
+
+use cpal::traits::DeviceTrait;
+use serde::{Deserialize, Serialize};
+
+use super::DeviceKind;
+use crate::config::RecordingConfig;
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct AudioDeviceInfo {
+    pub id: String,
+    pub name: String,
+    /// "input" (microphones) or "output" (captured via loopback)
+    pub kind: String,
+    pub is_default: bool,
+    pub host_api: String,
+    pub default_format: Option<AudioFormat>,
+    pub supported_formats: Vec<AudioFormatRange>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct AudioFormat {
+    pub sample_rate: u32,
+    pub channels: u16,
+    pub sample_format: String,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct AudioFormatRange {
+    pub channels: u16,
+    pub min_sample_rate: u32,
+    pub max_sample_rate: u32,
+    pub sample_format: String,
+}
+
+/// What the config asks for: a stable ID, a display name, or both.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct DeviceSelector {
+    pub id: Option<String>,
+    pub name: Option<String>,
+}
+
+impl DeviceSelector {
+    pub fn new(id: Option<String>, name: Option<String>) -> Option<Self> {
+        if id.is_none() && name.is_none() {
+            None
+        } else {
+            Some(Self { id, name })
+        }
+    }
+
+    pub fn describe(&self) -> String {
+        self.name.clone().or_else(|| self.id.clone()).unwrap_or_default()
+    }
+}
+
+impl DeviceKind {
+    fn direction(&self) -> &'static str {
+        match self {
+            DeviceKind::Microphone => "input",
+            DeviceKind::System => "output",
+        }
+    }
+}
+
+/// Strips the Windows endpoint instance prefix: "Microphone (2- USB Audio)" -> "Microphone (USB Audio)".
+pub fn normalize_device_name(name: &str) -> String {
+    if let Some(open) = name.find('(') {
+        let inner = &name[open + 1..];
+        let digits = inner.chars().take_while(|c| c.is_ascii_digit()).count();
+        if digits > 0 && inner[digits..].starts_with("- ") {
+            return format!("{}({}", &name[..open], &inner[digits + 2..]);
+        }
+    }
+    name.to_string()
+}
+
+/// Assigns IDs to an enumeration-ordered list of names. Identical names get increasing ordinals.
+pub fn assign_device_ids(host_api: &str, kind: DeviceKind, names: &[String]) -> Vec<String> {
+    let mut seen: Vec<String> = Vec::new();
+    names.iter().map(|name| {
+        let normalized = normalize_device_name(name);
+        let ordinal = seen.iter().filter(|n| **n == normalized).count();
+        seen.push(normalized.clone());
+        format!("{}:{}:{}#{}", host_api.to_lowercase(), kind.direction(), normalized, ordinal)
+    }).collect()
+}
+
+/// Picks the index of the device matching `selector` from parallel (id, name) lists.
+pub fn match_device(ids: &[String], names: &[String], selector: &DeviceSelector) -> Option<usize> {
+    if let Some(id) = &selector.id {
+        if let Some(i) = ids.iter().position(|d| d == id) {
+            return Some(i);
+        }
+        let ordinal = id.rsplit_once('#').and_then(|(_, n)| n.parse::<usize>().ok());
+        if ordinal.is_some_and(|n| n > 0) {
+            log::warn!("Device {} is gone and has identical twins, not picking one by name", id);
+            return None;
+        }
+    }
+    if let Some(name) = &selector.name {
+        let normalized = normalize_device_name(name);
+        let exact: Vec<usize> = (0..names.len()).filter(|&i| names[i] == *name).collect();
+        let similar: Vec<usize> = (0..names.len()).filter(|&i| normalize_device_name(&names[i]) == normalized).collect();
+        for candidates in [exact, similar] {
+            match candidates[..] {
+                [] => continue,
+                [i] => return Some(i),
+                _ => {
+                    log::warn!("{} devices are named {:?}, not picking one", candidates.len(), name);
+                    return None;
+                }
+            }
+        }
+    }
+    None
+}
+
+/// Whether two handles are the same endpoint. WASAPI compares endpoint IDs, which tell identical
+/// devices apart where names don't.
+fn same_device(a: &cpal::Device, b: &cpal::Device) -> bool {
+    let (cpal::platform::DeviceInner::Wasapi(a), cpal::platform::DeviceInner::Wasapi(b)) = (a.as_inner(), b.as_inner());
+    a == b
+}
+
+/// Enumerates devices of one kind with their derived IDs, in host order.
+fn enumerate(host: &cpal::Host, kind: DeviceKind) -> Result<Vec<(String, String, cpal::Device)>, String> {
+    let devices = kind.devices(host)?;
+    let names: Vec<String> = devices.iter().map(|d| d.name().unwrap_or_default()).collect();
+    let ids = assign_device_ids(host.id().name(), kind, &names);
+    Ok(ids.into_iter().zip(names).zip(devices).map(|((id, name), d)| (id, name, d)).collect())
+}
+
+/// Finds the device for `selector`. Returns the device and its current name.
+pub fn resolve_device(host: &cpal::Host, kind: DeviceKind, selector: &DeviceSelector) -> Option<(cpal::Device, String)> {
+    let mut entries = enumerate(host, kind).ok()?;
+    let ids: Vec<String> = entries.iter().map(|e| e.0.clone()).collect();
+    let names: Vec<String> = entries.iter().map(|e| e.1.clone()).collect();
+    let index = match_device(&ids, &names, selector)?;
+    let (_, name, device) = entries.swap_remove(index);
+    Some((device, name))
+}
+
+/// Current ID of the device named `name`, if it is connected. Matches like [match_device].
+pub fn device_id_for_name(kind: DeviceKind, name: &str) -> Option<String> {
+    let host = cpal::default_host();
+    let entries = enumerate(&host, kind).ok()?;
+    let ids: Vec<String> = entries.iter().map(|e| e.0.clone()).collect();
+    let names: Vec<String> = entries.iter().map(|e| e.1.clone()).collect();
+    let selector = DeviceSelector { id: None, name: Some(name.to_string()) };
+    match_device(&ids, &names, &selector).map(|i| ids[i].clone())
+}
+
+/// Fills in device IDs the config doesn't have from the device names, e.g. for configs written
+/// before IDs existed. IDs the UI picked are kept: a name can't tell identical headsets apart.
+pub fn fill_missing_ids(recording: &mut RecordingConfig) {
+    fill_missing_id(&recording.audio_source, &mut recording.audio_source_id, |name| device_id_for_name(DeviceKind::Microphone, name));
+    fill_missing_id(&recording.system_audio_device, &mut recording.system_audio_device_id, |name| device_id_for_name(DeviceKind::System, name));
+}
+
+fn fill_missing_id(name: &Option<String>, id: &mut Option<String>, lookup: impl FnOnce(&str) -> Option<String>) {
+    match name {
+        None => *id = None,
+        Some(name) if id.is_none() => *id = lookup(name),
+        Some(_) => {}
+    }
+}
+
+pub fn list_devices(kind: DeviceKind) -> Result<Vec<AudioDeviceInfo>, String> {
+    let host = cpal::default_host();
+    let default_device = kind.default_device(&host);
+    let host_api = host.id().name().to_string();
+
+    Ok(enumerate(&host, kind)?.into_iter().map(|(id, name, device)| {
+        let default_format = kind.default_config(&device).ok().map(|c| AudioFormat {
+            sample_rate: c.sample_rate().0,
+            channels: c.channels(),
+            sample_format: format!("{:?}", c.sample_format()),
+        });
+
+        let ranges = match kind {
+            DeviceKind::Microphone => device.supported_input_configs().map(|r| r.collect::<Vec<_>>()),
+            DeviceKind::System => device.supported_output_configs().map(|r| r.collect::<Vec<_>>()),
+        };
+        let supported_formats = ranges.unwrap_or_default().into_iter().map(|r| AudioFormatRange {
+            channels: r.channels(),
+            min_sample_rate: r.min_sample_rate().0,
+            max_sample_rate: r.max_sample_rate().0,
+            sample_format: format!("{:?}", r.sample_format()),
+        }).collect();
+
+        AudioDeviceInfo {
+            is_default: default_device.as_ref().is_some_and(|d| same_device(d, &device)),
+            id,
+            name,
+            kind: kind.direction().to_string(),
+            host_api: host_api.clone(),
+            default_format,
+            supported_formats,
+        }
+    }).collect())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn names(list: &[&str]) -> Vec<String> {
+        list.iter().map(|s| s.to_string()).collect()
+    }
+
+    #[test]
+    fn test_normalize_device_name() {
+        assert_eq!(normalize_device_name("Headset Microphone (2- HyperX Cloud II)"), "Headset Microphone (HyperX Cloud II)");
+        assert_eq!(normalize_device_name("Speakers (Realtek(R) Audio)"), "Speakers (Realtek(R) Audio)");
+        assert_eq!(normalize_device_name("Line 1 (Virtual Audio Cable)"), "Line 1 (Virtual Audio Cable)");
+    }
+
+    #[test]
+    fn test_identical_names_get_distinct_ids() {
+        let ids = assign_device_ids("WASAPI", DeviceKind::Microphone, &names(&["Headset (USB)", "Headset (2- USB)", "Mic"]));
+        assert_eq!(ids[0], "wasapi:input:Headset (USB)#0");
+        assert_eq!(ids[1], "wasapi:input:Headset (USB)#1");
+        assert_eq!(ids[2], "wasapi:input:Mic#0");
+    }
+
+    #[test]
+    fn test_match_prefers_id_then_name() {
+        let list = names(&["Headset (USB)", "Headset (2- USB)"]);
+        let ids = assign_device_ids("WASAPI", DeviceKind::Microphone, &list);
+
+        // ID wins over a stale name
+        let selector = DeviceSelector { id: Some(ids[1].clone()), name: Some("Headset (USB)".into()) };
+        assert_eq!(match_device(&ids, &list, &selector), Some(1));
+
+        // Unknown ID falls back to exact name
+        let selector = DeviceSelector { id: Some("wasapi:input:Gone#0".into()), name: Some("Headset (2- USB)".into()) };
+        assert_eq!(match_device(&ids, &list, &selector), Some(1));
+    }
+
+    #[test]
+    fn test_reconnected_device_with_changed_name_is_found() {
+        // Saved while the device was enumerated as "(3- HyperX)"; now it's back without the prefix
+        let saved = DeviceSelector { id: None, name: Some("Microphone (3- HyperX)".into()) };
+        let list = names(&["Microphone (Realtek)", "Microphone (HyperX)"]);
+        let ids = assign_device_ids("WASAPI", DeviceKind::Microphone, &list);
+        assert_eq!(match_device(&ids, &list, &saved), Some(1));
+
+        // And the ID survives the renumbering too
+        let old_ids = assign_device_ids("WASAPI", DeviceKind::Microphone, &names(&["Microphone (3- HyperX)"]));
+        let by_id = DeviceSelector { id: Some(old_ids[0].clone()), name: None };
+        assert_eq!(match_device(&ids, &list, &by_id), Some(1));
+    }
+
+    #[test]
+    fn test_chosen_id_is_kept() {
+        let list = names(&["Headset (USB)", "Headset (2- USB)"]);
+        let ids = assign_device_ids("WASAPI", DeviceKind::Microphone, &list);
+        let lookup = |name: &str| {
+            let selector = DeviceSelector { id: None, name: Some(name.to_string()) };
+            match_device(&ids, &list, &selector).map(|i| ids[i].clone())
+        };
+
+        // The second of two identical headsets stays the second
+        let mut id = Some(ids[1].clone());
+        fill_missing_id(&Some("Headset (USB)".into()), &mut id, lookup);
+        assert_eq!(id.as_deref(), Some("wasapi:input:Headset (USB)#1"));
+
+        // Configs without an ID get one from the name
+        let mut id = None;
+        fill_missing_id(&Some("Headset (2- USB)".into()), &mut id, lookup);
+        assert_eq!(id, Some(ids[1].clone()));
+
+        // No device, no ID
+        let mut id = Some(ids[0].clone());
+        fill_missing_id(&None, &mut id, lookup);
+        assert_eq!(id, None);
+    }
+
+    #[test]
+    fn test_ambiguous_names_match_nothing() {
+        let list = names(&["Headset (USB)", "Headset (USB)", "Headset (2- USB)"]);
+        let ids = assign_device_ids("WASAPI", DeviceKind::Microphone, &list);
+        let by_name = |name: &str| DeviceSelector { id: None, name: Some(name.into()) };
+        assert_eq!(match_device(&ids, &list, &by_name("Headset (USB)")), None);
+        assert_eq!(match_device(&ids, &list, &by_name("Headset (3- USB)")), None);
+        // A unique exact name still wins over normalized twins
+        assert_eq!(match_device(&ids, &list, &by_name("Headset (2- USB)")), Some(2));
+
+        // The second of two headsets was unplugged: the first now holds #0, so #1 matches nothing
+        let list = names(&["Headset (USB)"]);
+        let ids = assign_device_ids("WASAPI", DeviceKind::Microphone, &list);
+        let selector = DeviceSelector { id: Some("wasapi:input:Headset (USB)#1".into()), name: Some("Headset (USB)".into()) };
+        assert_eq!(match_device(&ids, &list, &selector), None);
+    }
+
+    #[test]
+    fn test_missing_device() {
+        let list = names(&["Speakers"]);
+        let ids = assign_device_ids("WASAPI", DeviceKind::System, &list);
+        let selector = DeviceSelector { id: None, name: Some("Headphones".into()) };
+        assert_eq!(match_device(&ids, &list, &selector), None);
+    }
+}
@@ -0,0 +1,226 @@
+//! Audio Capture Module
+//!
+//! Captures microphone and system (loopback) audio with CPAL and feeds it to FFmpeg over named pipes.
+//!
+//! # Architecture
+//!
+//! * `supervisor`: Owns the CPAL stream for one pipe and rebuilds it when the device fails, disappears or the default changes.
+//! * `convert`: Channel/sample-rate conversion so a replacement device can feed a pipe opened with a different format.
+//! * `devices`: Enumeration with stable IDs, default flags and supported formats; resolves configured devices.
+//! * `clock`: Paces pipe output by sample count against the system clock (drift correction, gap padding).
+
+pub mod clock;
+pub mod convert;
+pub mod devices;
+pub mod supervisor;
+
+use cpal::traits::{DeviceTrait, HostTrait, StreamTrait};
+use crate::constants::{SYSTEM_AUDIO_PIPE_NAME, MIC_AUDIO_PIPE_NAME, ERROR_NO_DATA, AUDIO_SILENCE_TIMEOUT_MS};
+use tokio::net::windows::named_pipe::ServerOptions;
+use tokio::io::AsyncWriteExt;
+use std::sync::Arc;
+use std::sync::atomic::{AtomicBool, Ordering};
+use tauri::AppHandle;
+
+use clock::AudioClock;
+use convert::FormatConverter;
+use devices::DeviceSelector;
+use supervisor::DeviceSupervisor;
+
+pub struct AudioCapture {
+    pub mic_stream: Option<cpal::Stream>,
+    pub system_stream: Option<cpal::Stream>,
+    pub mic_pipe_task: Option<tokio::task::JoinHandle<()>>,
+    pub system_pipe_task: Option<tokio::task::JoinHandle<()>>,
+    pub system_sample_rate: u32,
+}
+
+/// Which side of the audio graph a capture belongs to.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum DeviceKind {
+    Microphone,
+    System,
+}
+
+impl DeviceKind {
+    pub fn pipe_name(&self) -> &'static str {
+        match self {
+            DeviceKind::Microphone => MIC_AUDIO_PIPE_NAME,
+            DeviceKind::System => SYSTEM_AUDIO_PIPE_NAME,
+        }
+    }
+
+    pub fn label(&self) -> &'static str {
+        match self {
+            DeviceKind::Microphone => "microphone",
+            DeviceKind::System => "system",
+        }
+    }
+
+    fn devices(&self, host: &cpal::Host) -> Result<Vec<cpal::Device>, String> {
+        match self {
+            DeviceKind::Microphone => host.input_devices().map(|d| d.collect()),
+            DeviceKind::System => host.output_devices().map(|d| d.collect()),
+        }.map_err(|e| e.to_string())
+    }
+
+    fn default_device(&self, host: &cpal::Host) -> Option<cpal::Device> {
+        match self {
+            DeviceKind::Microphone => host.default_input_device(),
+            DeviceKind::System => host.default_output_device(),
+        }
+    }
+
+    fn default_config(&self, device: &cpal::Device) -> Result<cpal::SupportedStreamConfig, String> {
+        match self {
+            DeviceKind::Microphone => device.default_input_config(),
+            DeviceKind::System => device.default_output_config(),
+        }.map_err(|e| e.to_string())
+    }
+}
+
+pub fn start_mic_capture(app: AppHandle, device: Option<DeviceSelector>) -> Result<(u32, u16, DeviceSupervisor), String> {
+    DeviceSupervisor::spawn(app, DeviceKind::Microphone, device)
+}
+
+pub fn start_system_capture(app: AppHandle, device: Option<DeviceSelector>) -> Result<(u32, u16, DeviceSupervisor), String> {
+    DeviceSupervisor::spawn(app, DeviceKind::System, device)
+}
+
+/// Shared destination of every stream built for one pipe.
+/// Streams come and go as devices change, but the sink (and therefore the pipe) stays the same.
+#[derive(Clone)]
+pub(crate) struct StreamSink {
+    pub tx: tokio::sync::mpsc::UnboundedSender<Vec<f32>>,
+    pub is_recording: Arc<AtomicBool>,
+    /// Set by the CPAL error callback so the supervisor knows to rebuild.
+    pub failed: Arc<AtomicBool>,
+}
+
+/// Builds and starts a CPAL stream on `device`.
+///
+/// `target` is the (rate, channels) the pipe was opened with. When it differs from the
+/// device's native format the samples are converted before being sent to the sink.
+/// Returns the device's native (rate, channels) and the running stream.
+pub(crate) fn build_stream(device: &cpal::Device, kind: DeviceKind, target: Option<(u32, u16)>, sink: StreamSink) -> Result<(u32, u16, cpal::Stream), String> {
+    let pipe_name = kind.pipe_name();
+    let supported_config = kind.default_config(device)?;
+
+    let sample_format = supported_config.sample_format();
+    let sample_rate = supported_config.sample_rate().0;
+    let channels = supported_config.channels();
+    log::info!("Audio Format for {}: {}Hz, {} channels, {:?}", pipe_name, sample_rate, channels, sample_format);
+
+    let (target_rate, target_channels) = target.unwrap_or((sample_rate, channels));
+    let converter = FormatConverter::new(sample_rate, channels, target_rate, target_channels);
+    if !converter.is_passthrough() {
+        log::info!("Converting {} from {}Hz/{}ch to {}Hz/{}ch", pipe_name, sample_rate, channels, target_rate, target_channels);
+    }
+
+    let config: cpal::StreamConfig = supported_config.into();
+
+    let stream = match sample_format {
+        cpal::SampleFormat::F32 => forward_samples(device, &config, sink, converter, pipe_name, |s: f32| s),
+        cpal::SampleFormat::I16 => forward_samples(device, &config, sink, converter, pipe_name, |s: i16| s as f32 / 32768.0),
+        cpal::SampleFormat::U16 => forward_samples(device, &config, sink, converter, pipe_name, |s: u16| (s as f32 - 32768.0) / 32768.0),
+        _ => return Err(format!("Unsupported sample format: {:?}", sample_format)),
+    }.map_err(|e| e.to_string())?;
+
+    stream.play().map_err(|e| e.to_string())?;
+
+    Ok((sample_rate, channels, stream))
+}
+
+fn forward_samples<T: cpal::SizedSample + 'static>(
+    device: &cpal::Device,
+    config: &cpal::StreamConfig,
+    sink: StreamSink,
+    mut converter: FormatConverter,
+    pipe_name: &'static str,
+    to_f32: fn(T) -> f32,
+) -> Result<cpal::Stream, cpal::BuildStreamError> {
+    let failed = sink.failed.clone();
+    let err_fn = move |err| {
+        log::error!("Error on stream {}: {}", pipe_name, err);
+        failed.store(true, Ordering::Relaxed);
+    };
+
+    device.build_input_stream(
+        config,
+        move |data: &[T], _: &_| {
+            if sink.is_recording.load(Ordering::Relaxed) {
+                let samples: Vec<f32> = data.iter().map(|&s| to_f32(s)).collect();
+                let _ = sink.tx.send(converter.process(&samples));
+            }
+        },
+        err_fn,
+        None
+    )
+}
+
+pub(crate) fn spawn_pipe_task(pipe_name: &'static str, mut rx: tokio::sync::mpsc::UnboundedReceiver<Vec<f32>>, is_recording: Arc<AtomicBool>, sample_rate: u32, channels: u16) {
+    tauri::async_runtime::spawn(async move {
+        let mut server = match ServerOptions::new()
+            .first_pipe_instance(true)
+            .create(pipe_name) {
+                Ok(s) => s,
+                Err(e) => {
+                    log::error!("Failed to create named pipe {}: {}", pipe_name, e);
+                    return;
+                }
+            };
+
+        log::info!("Waiting for FFmpeg to connect to {}...", pipe_name);
+        if let Err(e) = server.connect().await {
+            log::error!("Failed to connect named pipe {}: {}", pipe_name, e);
+            return;
+        }
+        log::info!("FFmpeg connected to {}!", pipe_name);
+
+        is_recording.store(true, Ordering::Relaxed);
+
+        // The pipe has no timestamps: the clock decides exactly how many frames are written
+        // so the stream length always matches elapsed time since FFmpeg connected.
+        let mut clock = AudioClock::new(sample_rate, channels);
+        let start = std::time::Instant::now();
+        let tick = tokio::time::Duration::from_millis(AUDIO_SILENCE_TIMEOUT_MS);
+        let mut silence_total = 0u64;
+        let mut dropped_total = 0u64;
+        let mut last_report = std::time::Instant::now();
+
+        loop {
+            // Wait for data or the next tick
+            match tokio::time::timeout(tick, rx.recv()).await {
+                Ok(Some(samples)) => {
+                    dropped_total += clock.push(&samples, start.elapsed());
+                }
+                Ok(None) => {
+                    log::info!("Audio channel closed for {}", pipe_name);
+                    return;
+                }
+                Err(_) => {}
+            }
+
+            let (samples, silence) = clock.pull(start.elapsed());
+            silence_total += silence;
+            if samples.is_empty() {
+                continue;
+            }
+
+            if let Err(e) = server.write_all(bytemuck::cast_slice(&samples)).await {
+                if e.kind() == std::io::ErrorKind::BrokenPipe || e.raw_os_error() == Some(ERROR_NO_DATA) {
+                    log::info!("Pipe {} closed by client.", pipe_name);
+                } else {
+                    log::error!("Pipe {} write error: {}", pipe_name, e);
+                }
+                return;
+            }
+
+            if last_report.elapsed() >= std::time::Duration::from_secs(60) {
+                log::info!("Audio clock {}: {} frames written, {} silence padded, {} dropped",
+                    pipe_name, clock.frames_out(), silence_total, dropped_total);
+                last_report = std::time::Instant::now();
+            }
+        }
+    });
+}
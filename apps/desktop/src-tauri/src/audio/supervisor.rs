@@ -0,0 +1,199 @@
+//! Audio Device Supervisor
+//!
+//! Owns the CPAL stream feeding one named pipe. The pipe (and the FFmpeg input reading it)
+//! lives for the whole recording session, while the stream underneath is rebuilt when:
+//! 1. The stream reports an error (device unplugged, driver reset).
+//! 2. The configured device disappears (fall back to the default) or re-appears (switch back).
+//! 3. No device is configured and Windows changes the default device.
+//!
+//! While no stream is running, the pipe task keeps padding silence so the timeline stays continuous.
+
+use std::sync::atomic::AtomicBool;
+use std::sync::mpsc::{self, RecvTimeoutError, Sender};
+use std::sync::Arc;
+use std::thread::{self, JoinHandle};
+use std::time::Duration;
+
+use cpal::traits::DeviceTrait;
+use serde::Serialize;
+use tauri::{AppHandle, Emitter};
+
+use super::devices::{resolve_device, DeviceSelector};
+use super::{build_stream, spawn_pipe_task, DeviceKind, StreamSink};
+use crate::constants::AUDIO_DEVICE_POLL_MS;
+
+pub const AUDIO_DEVICE_CHANGED_EVENT: &str = "audio-device-changed";
+
+#[derive(Debug, Clone, Serialize)]
+pub struct AudioDeviceChangedEvent {
+    /// "microphone" or "system"
+    pub source: &'static str,
+    /// Device now feeding the pipe, or None if capture is paused (silence).
+    pub device: Option<String>,
+    /// True if the configured device is missing and the default is used instead.
+    pub fallback: bool,
+    pub reason: String,
+}
+
+/// Handle to a supervisor thread. Dropping it stops the stream and closes the pipe feed.
+pub struct DeviceSupervisor {
+    stop_tx: Sender<()>,
+    handle: Option<JoinHandle<()>>,
+}
+
+impl DeviceSupervisor {
+    /// Starts capture on the selected device (or the default device) and returns the pipe format.
+    /// Fails if the initial device can't be opened, matching the previous one-shot behavior.
+    pub fn spawn(app: AppHandle, kind: DeviceKind, selector: Option<DeviceSelector>) -> Result<(u32, u16, Self), String> {
+        let (stop_tx, stop_rx) = mpsc::channel::<()>();
+        let (ready_tx, ready_rx) = mpsc::channel::<Result<(u32, u16), String>>();
+
+        // cpal::Stream is !Send, so the stream is created, swapped and dropped on this thread only.
+        let handle = thread::spawn(move || {
+            let host = cpal::default_host();
+            let (tx, rx) = tokio::sync::mpsc::unbounded_channel::<Vec<f32>>();
+            let sink = StreamSink {
+                tx,
+                is_recording: Arc::new(AtomicBool::new(false)),
+                failed: Arc::new(AtomicBool::new(false)),
+            };
+
+            let initial = match &selector {
+                Some(selector) => resolve_device(&host, kind, selector)
+                    .ok_or(format!("{} device '{}' not found", kind.label(), selector.describe())),
+                None => kind.default_device(&host)
+                    .map(|d| { let name = d.name().unwrap_or_default(); (d, name) })
+                    .ok_or(format!("No default {} device available", kind.label())),
+            };
+
+            let (device, active_name) = match initial {
+                Ok(d) => d,
+                Err(e) => { let _ = ready_tx.send(Err(e)); return; }
+            };
+
+            log::info!("{} Audio Device: {}", kind.label(), active_name);
+
+            let (rate, channels, stream) = match build_stream(&device, kind, None, sink.clone()) {
+                Ok(res) => res,
+                Err(e) => { let _ = ready_tx.send(Err(e)); return; }
+            };
+
+            spawn_pipe_task(kind.pipe_name(), rx, sink.is_recording.clone(), rate, channels);
+            let _ = ready_tx.send(Ok((rate, channels)));
+
+            supervise(&app, &host, kind, selector, sink, (rate, channels), stream, active_name, stop_rx);
+            log::info!("Audio supervisor for {} exiting", kind.label());
+        });
+
+        match ready_rx.recv() {
+            Ok(Ok((rate, channels))) => Ok((rate, channels, Self { stop_tx, handle: Some(handle) })),
+            Ok(Err(e)) => {
+                let _ = handle.join();
+                Err(e)
+            }
+            Err(_) => Err(format!("Audio supervisor for {} exited during startup", kind.label())),
+        }
+    }
+}
+
+impl Drop for DeviceSupervisor {
+    fn drop(&mut self) {
+        let _ = self.stop_tx.send(());
+        if let Some(handle) = self.handle.take() {
+            let _ = handle.join();
+        }
+    }
+}
+
+#[allow(clippy::too_many_arguments)]
+fn supervise(
+    app: &AppHandle,
+    host: &cpal::Host,
+    kind: DeviceKind,
+    requested: Option<DeviceSelector>,
+    sink: StreamSink,
+    pipe_format: (u32, u16),
+    stream: cpal::Stream,
+    active_name: String,
+    stop_rx: mpsc::Receiver<()>,
+) {
+    let mut stream = Some(stream);
+    let mut active_name = Some(active_name);
+
+    loop {
+        match stop_rx.recv_timeout(Duration::from_millis(AUDIO_DEVICE_POLL_MS)) {
+            Ok(()) | Err(RecvTimeoutError::Disconnected) => break,
+            Err(RecvTimeoutError::Timeout) => {}
+        }
+
+        let stream_failed = sink.failed.swap(false, std::sync::atomic::Ordering::Relaxed);
+        let (desired, fallback) = desired_device(host, kind, requested.as_ref());
+        let desired_name = desired.as_ref().map(|(_, name)| name.clone());
+
+        if !stream_failed && desired_name == active_name {
+            continue;
+        }
+
+        let reason = if stream_failed {
+            "stream error".to_string()
+        } else {
+            match (&active_name, &desired_name) {
+                (None, Some(_)) => "device available".to_string(),
+                (Some(_), None) => "device removed".to_string(),
+                _ => "device changed".to_string(),
+            }
+        };
+
+        // Release the old device before opening the new one (some drivers only allow one client).
+        drop(stream.take());
+
+        let Some((device, name)) = desired else {
+            if active_name.take().is_some() {
+                log::warn!("No {} device available ({}). Padding silence until one appears.", kind.label(), reason);
+                emit_change(app, kind, None, requested.is_some(), reason);
+            }
+            continue;
+        };
+
+        match build_stream(&device, kind, Some(pipe_format), sink.clone()) {
+            Ok((_, _, new_stream)) => {
+                log::info!("Switched {} capture to '{}' ({}{})", kind.label(), name, reason, if fallback { ", fallback to default" } else { "" });
+                stream = Some(new_stream);
+                active_name = Some(name.clone());
+                emit_change(app, kind, Some(name), fallback, reason);
+            }
+            Err(e) => {
+                // Leave active_name empty so the next poll retries.
+                log::warn!("Failed to open {} device '{}': {}. Retrying.", kind.label(), name, e);
+                active_name = None;
+            }
+        }
+    }
+
+    drop(stream);
+}
+
+/// The configured device (and its current name) if it's present, otherwise the current default.
+/// The flag is true when a configured device is missing and the default stands in.
+fn desired_device(host: &cpal::Host, kind: DeviceKind, requested: Option<&DeviceSelector>) -> (Option<(cpal::Device, String)>, bool) {
+    if let Some(found) = requested.and_then(|selector| resolve_device(host, kind, selector)) {
+        return (Some(found), false);
+    }
+    let default = kind.default_device(host).map(|d| {
+        let name = d.name().unwrap_or_default();
+        (d, name)
+    });
+    (default, requested.is_some())
+}
+
+fn emit_change(app: &AppHandle, kind: DeviceKind, device: Option<String>, fallback: bool, reason: String) {
+    let event = AudioDeviceChangedEvent {
+        source: kind.label(),
+        device,
+        fallback,
+        reason,
+    };
+    if let Err(e) = app.emit(AUDIO_DEVICE_CHANGED_EVENT, event) {
+        log::error!("Failed to emit {}: {}", AUDIO_DEVICE_CHANGED_EVENT, e);
+    }
+}
@@ -0,0 +1,65 @@
+use tauri::{command, AppHandle, Emitter, State};
+use crate::state::RecordingState;
+use crate::ffmpeg::benchmark::{self, BenchmarkReport, BENCHMARK_PROGRESS_EVENT};
+use crate::commands::profiles::update_profiles;
+use std::sync::atomic::{AtomicBool, Ordering};
+
+/// Clears [RecordingState::benchmarking] however the benchmark ends.
+struct BenchmarkFlag<'a>(&'a AtomicBool);
+
+impl Drop for BenchmarkFlag<'_> {
+    fn drop(&mut self) {
+        self.0.store(false, Ordering::SeqCst);
+    }
+}
+
+/// Benchmarks the available encoders (emitting each run as `benchmark-progress`) and recommends
+/// the best profile the machine sustains. With `apply` it's saved and made the active profile.
+#[command]
+pub async fn benchmark_encoders(app: AppHandle, state: State<'_, RecordingState>, apply: bool) -> Result<BenchmarkReport, String> {
+    // The buffer would compete for the encoder and skew every number. The flag keeps the game
+    // watcher from starting it until the benchmark is done.
+    let _benchmarking = {
+        let tx = state.tx.lock().map_err(|e| e.to_string())?;
+        if tx.is_some() || state.replay_starts.load(Ordering::SeqCst) > 0 {
+            return Err("Stop the replay buffer before benchmarking".to_string());
+        }
+        if state.benchmarking.swap(true, Ordering::SeqCst) {
+            return Err("A benchmark is already running".to_string());
+        }
+        BenchmarkFlag(&state.benchmarking)
+    };
+    let codec = state.config.lock().map_err(|e| e.to_string())?.recording.video_codec.unwrap_or_default();
+
+    let app_clone = app.clone();
+    let results = tauri::async_runtime::spawn_blocking(move || {
+        benchmark::run(&app_clone, codec, |result| {
+            if let Err(e) = app_clone.emit(BENCHMARK_PROGRESS_EVENT, result) {
+                log::error!("Failed to emit {}: {}", BENCHMARK_PROGRESS_EVENT, e);
+            }
+        })
+    })
+    .await
+    .map_err(|e| format!("Benchmark task failed: {}", e))?;
+
+    let recommended = benchmark::recommend(&results).map(|r| r.to_profile());
+    let mut applied = false;
+    match &recommended {
+        Some(profile) => {
+            log::info!("Benchmark recommends {:?}", profile);
+            if apply {
+                let profile = profile.clone();
+                update_profiles(&app, &state, move |config| {
+                    config.active_profile = Some(profile.name.clone());
+                    config.upsert_profile(profile);
+                    Ok(())
+                })
+                .await?;
+                applied = true;
+            }
+        }
+        None => log::warn!("No encoder sustained even the lowest benchmark tier"),
+    }
+
+    Ok(BenchmarkReport { results, recommended, applied })
+}
@@ -0,0 +1,17 @@
+use tauri::{command, State};
+use crate::ntp::clock::ClockHealth;
+use crate::ntp::source::TimeSyncExchange;
+use crate::state::RecordingState;
+
+/// How far the NTP-synced clock can currently be trusted.
+#[command]
+pub fn get_clock_health(state: State<'_, RecordingState>) -> Result<ClockHealth, String> {
+    state.ntp_manager.health()
+}
+
+/// Feeds one `TIME_SYNC_REQUEST`/`TIME_SYNC_RESPONSE` round trip with the room server into
+/// clock sync.
+#[command]
+pub fn submit_time_sync(state: State<'_, RecordingState>, exchange: TimeSyncExchange) -> Result<(), String> {
+    state.ntp_manager.add_signaling_sample(&exchange)
+}
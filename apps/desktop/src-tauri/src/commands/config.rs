@@ -1,7 +1,10 @@
-use tauri::{command, AppHandle, State};
-use crate::state::{RecordingState, RecordingMessage};
-use crate::config::AppConfig;
+use tauri::{command, AppHandle, Manager, State};
+use crate::state::{RecordingState, RecordingMessage, StartGuard};
+use crate::config::{AppConfig, ChangeKind, ConfigDiff, FieldError, RecordingConfig};
+use crate::ffmpeg::session::AudioSettings;
 use crate::ffmpeg::process::start_recording_process;
+use crate::ffmpeg::capture::CaptureTarget;
+use crate::commands::monitors::monitor_identity_at;
 
 #[command]
 pub fn get_config(state: State<'_, RecordingState>) -> Result<AppConfig, String> {
@@ -9,26 +12,114 @@ pub fn get_config(state: State<'_, RecordingState>) -> Result<AppConfig, String>
     Ok(config.clone())
 }
 
+/// Field-level problems with `config`, empty if it can be saved.
 #[command]
-pub async fn update_config(app: AppHandle, state: State<'_, RecordingState>, new_config: AppConfig) -> Result<(), String> {
-    let mut was_recording = false;
-    let mut handle_to_join = None;
+pub fn validate_config(config: AppConfig) -> Vec<FieldError> {
+    config.validate().err().unwrap_or_default()
+}
+
+#[command]
+pub async fn update_config(app: AppHandle, state: State<'_, RecordingState>, mut new_config: AppConfig) -> Result<(), String> {
+    if let Err(errors) = new_config.validate() {
+        let fields: Vec<String> = errors.iter().map(|e| format!("{}: {}", e.field, e.message)).collect();
+        return Err(format!("Invalid settings: {}", fields.join("; ")));
+    }
 
-    // 1. Check if recording is active and stop it
+    // 1. Normalize the new config and work out what it changes
+    crate::audio::devices::fill_missing_ids(&mut new_config.recording);
+    crate::plugins::ensure_token(&mut new_config.plugin_api);
+    crate::gsi::ensure_token(&mut new_config.gsi);
+    let diff;
+    let effective;
+    let hotkeys_changed;
+    let plugin_api_changed;
+    let gsi_changed;
     {
-        let mut tx_guard = state.tx.lock().map_err(|e| e.to_string())?;
-        if let Some(tx) = tx_guard.take() {
-            was_recording = true;
-            log::info!("Settings changed while recording. Stopping to apply changes...");
-            let _ = tx.send(RecordingMessage::Stop);
-            
-            // Take handle to join
-            let mut handle_guard = state.join_handle.lock().map_err(|e| e.to_string())?;
-            handle_to_join = handle_guard.take();
+        let mut config = state.config.lock().map_err(|e| e.to_string())?;
+
+        // The UI still picks monitors by list index; pin a new choice to the monitor's identity.
+        let monitor_changed = new_config.recording.monitor_index != config.recording.monitor_index;
+        let targets_monitor = matches!(new_config.recording.capture_target, None | Some(CaptureTarget::Monitor { .. }));
+        if monitor_changed && targets_monitor {
+            let identity = new_config.recording.monitor_index.and_then(|idx| monitor_identity_at(&app, idx));
+            new_config.recording.capture_target = identity.map(|m| CaptureTarget::Monitor { monitor: Some(m) });
+        }
+
+        // Compared as recorded, with the profile in use applied
+        let game = state.active_game.lock().map_err(|e| e.to_string())?.clone();
+        effective = new_config.effective(game.as_deref());
+        diff = ConfigDiff::new(&config.effective(game.as_deref()), &effective);
+        hotkeys_changed = new_config.hotkeys != config.hotkeys;
+        plugin_api_changed = new_config.plugin_api != config.plugin_api;
+        gsi_changed = new_config.gsi != config.gsi;
+
+        // 2. Update Config. Hot fields (output path, retention, uploads, ...) are read from here.
+        *config = new_config.clone();
+        config.save(&app)?;
+    }
+
+    // 2b. Re-register hotkeys; failures are reported through the hotkeys-status event
+    if hotkeys_changed {
+        crate::hotkeys::apply(&app, &new_config.hotkeys);
+    }
+    if plugin_api_changed {
+        if let Err(e) = crate::plugins::restart(&app, &new_config.plugin_api).await {
+            log::error!("Failed to restart plugin API: {}", e);
         }
     }
+    if gsi_changed {
+        if let Err(e) = crate::gsi::restart(&app, &new_config.gsi).await {
+            log::error!("Failed to restart game state receiver: {}", e);
+        }
+    }
+
+    // 3. Restart only what the changes need, if recording
+    apply_recording_changes(&app, &diff, &effective.recording).await
+}
+
+/// Applies a change of the recording settings (as recorded, see [AppConfig::effective]) to a
+/// running session, restarting as little as `diff` allows.
+pub(crate) async fn apply_recording_changes(app: &AppHandle, diff: &ConfigDiff, recording: &RecordingConfig) -> Result<(), String> {
+    for (field, kind) in &diff.changes {
+        log::debug!("Setting {} changed ({:?})", field, kind);
+    }
 
-    // 1b. Wait for cleanup if needed
+    let state = app.state::<RecordingState>();
+    match diff.kind() {
+        Some(ChangeKind::AudioRestart) => {
+            let tx_guard = state.tx.lock().map_err(|e| e.to_string())?;
+            if let Some(tx) = tx_guard.as_ref() {
+                log::info!("Audio settings changed while recording. Restarting audio only...");
+                let _ = tx.send(RecordingMessage::RestartAudio(AudioSettings::from_config(recording)));
+            }
+        }
+        Some(ChangeKind::FullRestart) => restart_recording(app, &state, diff.keeps_segments()).await?,
+        Some(ChangeKind::Hot) | None => {}
+    }
+
+    Ok(())
+}
+
+/// Stops the recording thread and starts it again with the saved config. Nothing to do if it
+/// isn't recording.
+async fn restart_recording(app: &AppHandle, state: &State<'_, RecordingState>, keep_segments: bool) -> Result<(), String> {
+    let handle_to_join;
+    // Counted as a start while `tx` is None, so a benchmark doesn't begin in between
+    let _starting = {
+        let mut tx_guard = state.tx.lock().map_err(|e| e.to_string())?;
+        let Some(tx) = tx_guard.take() else {
+            return Ok(());
+        };
+        log::info!("Settings changed while recording. Stopping to apply changes...");
+        let _ = tx.send(RecordingMessage::Stop);
+
+        // Take handle to join
+        let mut handle_guard = state.join_handle.lock().map_err(|e| e.to_string())?;
+        handle_to_join = handle_guard.take();
+        StartGuard::new(&state.replay_starts)
+    };
+
+    // Wait for cleanup
     if let Some(handle) = handle_to_join {
         log::info!("Waiting for previous recording to cleanup...");
         if handle.join().is_err() {
@@ -37,34 +128,21 @@ pub async fn update_config(app: AppHandle, state: State<'_, RecordingState>, new
         log::info!("Previous recording cleaned up.");
     }
 
-    // 2. Update Config
-    {
-        let mut config = state.config.lock().map_err(|e| e.to_string())?;
-        *config = new_config.clone();
-        config.save(&app)?;
-    }
+    log::info!("Restarting recording with new settings (keeping buffered segments: {})...", keep_segments);
+    match start_recording_process(app, keep_segments).await {
+        Ok((tx, handle)) => {
+            let mut tx_guard = state.tx.lock().map_err(|e| e.to_string())?;
+            *tx_guard = Some(tx);
 
-    // 3. Restart if it was recording
-    if was_recording {
-        log::info!("Restarting recording with new settings...");
-        // Small delay to ensure previous process cleanup if needed, though not strictly required if we spawn new one.
-        // But let's just spawn.
-        match start_recording_process(&app).await {
-            Ok((tx, handle)) => {
-                let mut tx_guard = state.tx.lock().map_err(|e| e.to_string())?;
-                *tx_guard = Some(tx);
-                
-                let mut handle_guard = state.join_handle.lock().map_err(|e| e.to_string())?;
-                *handle_guard = Some(handle);
-
-                log::info!("Recording restarted successfully.");
-            }
-            Err(e) => {
-                log::error!("Failed to restart recording: {}", e);
-                return Err(format!("Settings saved, but failed to restart recording: {}", e));
-            }
+            let mut handle_guard = state.join_handle.lock().map_err(|e| e.to_string())?;
+            *handle_guard = Some(handle);
+
+            log::info!("Recording restarted successfully.");
+            Ok(())
+        }
+        Err(e) => {
+            log::error!("Failed to restart recording: {}", e);
+            Err(format!("Settings saved, but failed to restart recording: {}", e))
         }
     }
-    
-    Ok(())
 }
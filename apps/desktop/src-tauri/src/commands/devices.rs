@@ -1,28 +1,21 @@
 use tauri::{command, AppHandle};
-use cpal::traits::{DeviceTrait, HostTrait};
+use crate::audio::DeviceKind;
+use crate::audio::devices::{list_devices, AudioDeviceInfo};
 
 #[command]
 pub async fn get_audio_devices(_app: AppHandle) -> Result<Vec<String>, String> {
-    let host = cpal::default_host();
-    let devices = host.input_devices().map_err(|e| e.to_string())?;
-    let mut device_names = Vec::new();
-    for device in devices {
-        if let Ok(name) = device.name() {
-            device_names.push(name);
-        }
-    }
-    Ok(device_names)
+    Ok(list_devices(DeviceKind::Microphone)?.into_iter().map(|d| d.name).collect())
 }
 
 #[command]
 pub async fn get_system_audio_devices(_app: AppHandle) -> Result<Vec<String>, String> {
-    let host = cpal::default_host();
-    let devices = host.output_devices().map_err(|e| e.to_string())?;
-    let mut device_names = Vec::new();
-    for device in devices {
-        if let Ok(name) = device.name() {
-            device_names.push(name);
-        }
-    }
-    Ok(device_names)
+    Ok(list_devices(DeviceKind::System)?.into_iter().map(|d| d.name).collect())
+}
+
+/// Microphones and loopback-capable outputs with IDs, default flags and supported formats.
+#[command]
+pub async fn list_audio_devices(_app: AppHandle) -> Result<Vec<AudioDeviceInfo>, String> {
+    let mut devices = list_devices(DeviceKind::Microphone)?;
+    devices.extend(list_devices(DeviceKind::System)?);
+    Ok(devices)
 }
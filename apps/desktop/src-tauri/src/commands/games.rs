@@ -0,0 +1,8 @@
+use tauri::{command, State};
+use crate::state::RecordingState;
+
+/// The game currently detected by the process watcher, if any.
+#[command]
+pub fn get_active_game(state: State<'_, RecordingState>) -> Result<Option<String>, String> {
+    Ok(state.active_game.lock().map_err(|e| e.to_string())?.clone())
+}
@@ -0,0 +1,12 @@
+use tauri::{command, State};
+use crate::state::RecordingState;
+
+/// The CS2 `gamestate_integration_squadsync.cfg` matching the current `[gsi]` settings.
+#[command]
+pub fn get_cs2_gsi_config(state: State<'_, RecordingState>) -> Result<String, String> {
+    let config = state.config.lock().map_err(|e| e.to_string())?;
+    if !config.gsi.enabled {
+        return Err("Game state integration is disabled".to_string());
+    }
+    Ok(crate::gsi::cs2_config_file(&config.gsi))
+}
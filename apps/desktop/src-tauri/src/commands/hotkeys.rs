@@ -0,0 +1,16 @@
+use tauri::{command, State};
+use crate::hotkeys::accelerator::Accelerator;
+use crate::hotkeys::HotkeyStatus;
+use crate::state::RecordingState;
+
+/// Registration result of every bound hotkey, including conflicts and OS refusals.
+#[command]
+pub fn get_hotkey_status(state: State<'_, RecordingState>) -> Result<Vec<HotkeyStatus>, String> {
+    Ok(state.hotkeys.lock().map_err(|e| e.to_string())?.status.clone())
+}
+
+/// Checks an accelerator typed in the UI and returns its canonical form.
+#[command]
+pub fn validate_hotkey(accelerator: String) -> Result<String, String> {
+    accelerator.parse::<Accelerator>().map(|a| a.to_string())
+}
@@ -0,0 +1,31 @@
+use tauri::{command, AppHandle, Emitter, Manager};
+use std::path::PathBuf;
+use crate::markers::{Marker, MARKER_ADDED_EVENT};
+use crate::state::RecordingState;
+
+/// Drops a marker into the replay buffer. `timestamp` is NTP-corrected Unix ms (default: now).
+#[command]
+pub fn add_marker(app: AppHandle, label: Option<String>, timestamp: Option<u64>) -> Result<Marker, String> {
+    add_marker_impl(&app, label, timestamp)
+}
+
+pub fn add_marker_impl(app: &AppHandle, label: Option<String>, timestamp: Option<u64>) -> Result<Marker, String> {
+    let state = app.state::<RecordingState>();
+    let (buffer_dir, retention_ms) = {
+        let config = state.config.lock().map_err(|e| e.to_string())?;
+        (PathBuf::from(&config.recording.temp_path), config.recording.buffer_retention_seconds as u64 * 1000)
+    };
+
+    let now_ms = state.ntp_manager.get_ntp_time_ms();
+    let label = label.map(|l| l.trim().to_string()).filter(|l| !l.is_empty())
+        .unwrap_or_else(|| crate::constants::DEFAULT_MARKER_LABEL.to_string());
+    let marker = Marker { label, timestamp_ms: timestamp.unwrap_or(now_ms) };
+
+    state.markers.lock().map_err(|e| e.to_string())?.add(&buffer_dir, marker.clone(), now_ms, retention_ms)?;
+    log::info!("Marker '{}' added at {}", marker.label, marker.timestamp_ms);
+
+    if let Err(e) = app.emit(MARKER_ADDED_EVENT, &marker) {
+        log::error!("Failed to emit {}: {}", MARKER_ADDED_EVENT, e);
+    }
+    Ok(marker)
+}
@@ -2,7 +2,16 @@ pub mod recording;
 pub mod replay;
 pub mod system;
 pub mod config;
+pub mod profiles;
+pub mod benchmark;
 pub mod devices;
 pub mod monitors;
 pub mod playback;
 pub mod upload;
+pub mod squad;
+pub mod hotkeys;
+pub mod markers;
+pub mod plugins;
+pub mod gsi;
+pub mod games;
+pub mod clock;
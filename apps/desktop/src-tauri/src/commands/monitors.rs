@@ -1,13 +1,35 @@
 use tauri::{command, AppHandle, Manager};
 use serde::Serialize;
+use crate::ffmpeg::capture::MonitorIdentity;
 
 #[derive(Debug, Serialize)]
 pub struct MonitorInfo {
-    pub id: u32, // We'll use index as ID for simplicity with ddagrab
+    pub id: u32, // List index, kept for the legacy `monitor_index` setting
     pub name: String,
     pub width: u32,
     pub height: u32,
     pub is_primary: bool,
+    /// Stable identity to store in a `CaptureTarget`; survives monitors being re-ordered.
+    pub identity: MonitorIdentity,
+}
+
+pub(crate) fn monitor_identity(monitor: &tauri::Monitor) -> MonitorIdentity {
+    let size = monitor.size();
+    let position = monitor.position();
+    MonitorIdentity {
+        name: monitor.name().cloned().unwrap_or_default(),
+        x: position.x,
+        y: position.y,
+        width: size.width,
+        height: size.height,
+    }
+}
+
+/// Identity of the monitor at `index` in the current list.
+pub(crate) fn monitor_identity_at(app: &AppHandle, index: u32) -> Option<MonitorIdentity> {
+    let window = app.get_webview_window("main")?;
+    let monitors = window.available_monitors().ok()?;
+    monitors.get(index as usize).map(monitor_identity)
 }
 
 #[command]
@@ -32,6 +54,7 @@ pub fn get_monitors(app: AppHandle) -> Result<Vec<MonitorInfo>, String> {
             width: size.width,
             height: size.height,
             is_primary,
+            identity: monitor_identity(monitor),
         });
     }
 
@@ -2,7 +2,10 @@ use tauri::{AppHandle, Manager, Runtime};
 use std::path::PathBuf;
 use serde::{Serialize, Deserialize};
 use std::fs;
+use crate::config::AppConfig;
 use crate::state::RecordingState;
+use crate::library::ClipMetadata;
+use crate::markers::ClipMarker;
 use std::time::{SystemTime, UNIX_EPOCH};
 
 #[derive(Debug, Serialize, Deserialize)]
@@ -13,17 +16,26 @@ pub struct Recording {
     pub size: u64,
     pub created_at: u64,
     pub duration: Option<f64>, // Duration in seconds, optional for now
+    /// Event markers saved with the clip, for the playback scrubber
+    pub markers: Vec<ClipMarker>,
+    /// Game the clip was recorded in, if one was detected
+    pub game: Option<String>,
+}
+
+/// Where saved replays go: the configured path, or `Videos/SquadSync`.
+pub(crate) fn recordings_dir<R: Runtime>(app: &AppHandle<R>, config: &AppConfig) -> Result<PathBuf, String> {
+    if !config.recording.path.is_empty() {
+        Ok(PathBuf::from(&config.recording.path))
+    } else {
+        Ok(app.path().video_dir().map_err(|e| e.to_string())?.join("SquadSync"))
+    }
 }
 
 #[tauri::command]
 pub async fn get_recordings(app: AppHandle) -> Result<Vec<Recording>, String> {
     let state = app.state::<RecordingState>();
     let config = state.config.lock().map_err(|e| e.to_string())?;
-    let output_path = if !config.recording.path.is_empty() {
-        PathBuf::from(&config.recording.path)
-    } else {
-        app.path().video_dir().map_err(|e| e.to_string())?.join("SquadSync")
-    };
+    let output_path = recordings_dir(&app, &config)?;
 
     log::info!("Scanning for recordings in: {:?}", output_path);
 
@@ -56,6 +68,7 @@ pub async fn get_recordings(app: AppHandle) -> Result<Vec<Recording>, String> {
                             None
                         };
 
+                        let meta = ClipMetadata::load(&path);
                         recordings.push(Recording {
                             name: path.file_name().unwrap_or(std::ffi::OsStr::new("")).to_string_lossy().into_owned(),
                             path: path.to_string_lossy().to_string(),
@@ -63,6 +76,8 @@ pub async fn get_recordings(app: AppHandle) -> Result<Vec<Recording>, String> {
                             size: metadata.len(),
                             created_at,
                             duration: None, // TODO: Extract duration if needed
+                            markers: meta.markers,
+                            game: meta.game,
                         });
                     }
                 }
@@ -71,6 +86,7 @@ pub async fn get_recordings(app: AppHandle) -> Result<Vec<Recording>, String> {
 
 
     // Sort by created_at desc
+    #[allow(clippy::unnecessary_sort_by)]
     recordings.sort_by(|a, b| b.created_at.cmp(&a.created_at));
 
     Ok(recordings)
@@ -80,7 +96,8 @@ pub async fn get_recordings(app: AppHandle) -> Result<Vec<Recording>, String> {
 pub async fn delete_recording(path: String) -> Result<(), String> {
     let path_buf = PathBuf::from(&path);
     if path_buf.exists() {
-        fs::remove_file(path_buf).map_err(|e| e.to_string())?;
+        fs::remove_file(&path_buf).map_err(|e| e.to_string())?;
+        let _ = fs::remove_file(ClipMetadata::path(&path_buf));
     }
     Ok(())
 }
@@ -103,7 +120,11 @@ pub async fn rename_recording(path: String, new_name: String) -> Result<(), Stri
     }
 
     let new_path = parent.join(new_filename);
-    fs::rename(old_path, new_path).map_err(|e| e.to_string())?;
+    fs::rename(&old_path, &new_path).map_err(|e| e.to_string())?;
+    let old_meta = ClipMetadata::path(&old_path);
+    if old_meta.exists() {
+        let _ = fs::rename(old_meta, ClipMetadata::path(&new_path));
+    }
     Ok(())
 }
 
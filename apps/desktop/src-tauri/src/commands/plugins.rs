@@ -0,0 +1,32 @@
+use tauri::{command, AppHandle, State};
+use serde::Serialize;
+use crate::state::RecordingState;
+
+#[derive(Debug, Serialize)]
+pub struct PluginApiStatus {
+    pub enabled: bool,
+    /// Base URL of the running server; None when disabled or it failed to start
+    pub url: Option<String>,
+}
+
+#[command]
+pub fn get_plugin_api_status(state: State<'_, RecordingState>) -> Result<PluginApiStatus, String> {
+    let enabled = state.config.lock().map_err(|e| e.to_string())?.plugin_api.enabled;
+    let url = state.plugin_server.lock().map_err(|e| e.to_string())?
+        .as_ref()
+        .map(|server| format!("http://{}", server.addr()));
+    Ok(PluginApiStatus { enabled, url })
+}
+
+/// Replaces the plugin API token, invalidating the one plugins have. Returns the new token.
+#[command]
+pub async fn regenerate_plugin_token(app: AppHandle, state: State<'_, RecordingState>) -> Result<String, String> {
+    let plugin_api = {
+        let mut config = state.config.lock().map_err(|e| e.to_string())?;
+        config.plugin_api.token = crate::plugins::generate_token();
+        config.save(&app)?;
+        config.plugin_api.clone()
+    };
+    crate::plugins::restart(&app, &plugin_api).await?;
+    Ok(plugin_api.token)
+}
@@ -0,0 +1,74 @@
+use tauri::{command, AppHandle, State};
+use crate::state::RecordingState;
+use crate::config::{AppConfig, ConfigDiff, RecordingProfile};
+use crate::commands::config::apply_recording_changes;
+
+/// Switches the active recording profile (None records with `[recording]` as is) and applies
+/// it to a running buffer. A profile set for the detected game still takes precedence.
+#[command]
+pub async fn set_active_profile(app: AppHandle, state: State<'_, RecordingState>, name: Option<String>) -> Result<(), String> {
+    update_profiles(&app, &state, |config| {
+        if let Some(name) = &name {
+            if config.profile(name).is_none() {
+                return Err(format!("No profile is called '{}'", name));
+            }
+        }
+        config.active_profile = name;
+        Ok(())
+    })
+    .await
+}
+
+/// Writes a profile to a TOML file that can be shared and imported elsewhere.
+#[command]
+pub fn export_profile(state: State<'_, RecordingState>, name: String, path: String) -> Result<(), String> {
+    let content = {
+        let config = state.config.lock().map_err(|e| e.to_string())?;
+        let profile = config.profile(&name).ok_or_else(|| format!("No profile is called '{}'", name))?;
+        profile.to_toml()?
+    };
+    std::fs::write(&path, content).map_err(|e| format!("Failed to write {}: {}", path, e))?;
+    log::info!("Exported profile '{}' to {}", name, path);
+    Ok(())
+}
+
+/// Adds the profile in a TOML file, replacing a profile with the same name.
+#[command]
+pub async fn import_profile(app: AppHandle, state: State<'_, RecordingState>, path: String) -> Result<RecordingProfile, String> {
+    let content = std::fs::read_to_string(&path).map_err(|e| format!("Failed to read {}: {}", path, e))?;
+    let profile = RecordingProfile::from_toml(&content)?;
+    let imported = profile.clone();
+    update_profiles(&app, &state, move |config| {
+        config.upsert_profile(profile);
+        Ok(())
+    })
+    .await?;
+    log::info!("Imported profile '{}' from {}", imported.name, path);
+    Ok(imported)
+}
+
+/// Changes the config with `change`, validates and saves it, then applies the change to a
+/// running buffer if the profile in use is affected.
+pub(crate) async fn update_profiles(
+    app: &AppHandle,
+    state: &State<'_, RecordingState>,
+    change: impl FnOnce(&mut AppConfig) -> Result<(), String>,
+) -> Result<(), String> {
+    let game = state.active_game.lock().map_err(|e| e.to_string())?.clone();
+    let (diff, effective) = {
+        let mut config = state.config.lock().map_err(|e| e.to_string())?;
+        let mut updated = config.clone();
+        change(&mut updated)?;
+        if let Err(errors) = updated.validate() {
+            let fields: Vec<String> = errors.iter().map(|e| format!("{}: {}", e.field, e.message)).collect();
+            return Err(format!("Invalid profile: {}", fields.join("; ")));
+        }
+
+        let effective = updated.effective(game.as_deref());
+        let diff = ConfigDiff::new(&config.effective(game.as_deref()), &effective);
+        *config = updated;
+        config.save(app)?;
+        (diff, effective)
+    };
+    apply_recording_changes(app, &diff, &effective.recording).await
+}
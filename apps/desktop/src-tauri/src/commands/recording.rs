@@ -1,22 +1,28 @@
 use tauri::{command, AppHandle, Manager};
-use crate::state::{RecordingState, RecordingMessage};
+use crate::state::{RecordingState, RecordingMessage, StartGuard};
 use crate::ffmpeg::process::start_recording_process;
+use std::sync::atomic::Ordering;
 
 #[command]
 pub async fn enable_replay(app: AppHandle) -> Result<(), String> {
     log::info!("Enable Replay command received");
     
-    // Release the lock BEFORE awaiting start_recording_process
-    {
-        let state = app.state::<RecordingState>();
+    // Release the lock BEFORE awaiting start_recording_process. The start is counted under it
+    // so a benchmark can't begin before `tx` is set.
+    let _starting = {
+        let state = app.state::<RecordingState>().inner();
         let tx_guard = state.tx.lock().map_err(|e| e.to_string())?;
         if tx_guard.is_some() {
             return Err("Replay Buffer already active".to_string());
         }
-    } // Lock released here
+        if state.benchmarking.load(Ordering::SeqCst) {
+            return Err("Encoder benchmark running, not starting the replay buffer".to_string());
+        }
+        StartGuard::new(&state.replay_starts)
+    }; // Lock released here
 
     // Now await the process start
-    match start_recording_process(&app).await {
+    match start_recording_process(&app, false).await {
         Ok((tx, handle)) => {
             let state = app.state::<RecordingState>();
             
@@ -5,11 +5,13 @@ use std::path::{Path, PathBuf};
 use std::process::Command;
 #[cfg(target_os = "windows")]
 use std::os::windows::process::CommandExt;
-use regex::Regex;
-use chrono::{DateTime, Local, TimeZone, Duration};
-use std::sync::OnceLock;
-
-static RE_VIDEO_AUDIO: OnceLock<Regex> = OnceLock::new();
+use chrono::{DateTime, Local, Utc, Duration};
+use crate::markers::{self, ClipMarker};
+use crate::library::ClipMetadata;
+use crate::ntp::clock::ClockHealth;
+use crate::ffmpeg::segment_index::{SegmentEntry, SegmentIndex};
+use crate::constants::REPLAY_AUDIO_SYNC_THRESHOLD_SEC;
+use std::collections::HashMap;
 
 #[derive(serde::Serialize)]
 pub struct SavedReplay {
@@ -17,14 +19,45 @@ pub struct SavedReplay {
     pub duration_ms: u64,
     pub start_time_utc_ms: Option<u64>,
     pub version: u32,
+    /// Buffer markers inside the clip, also written into the MP4 as chapters
+    pub markers: Vec<ClipMarker>,
+    /// Game running when the clip was saved
+    pub game: Option<String>,
+    /// Clock state at save time. `clock.synced == false` means `start_time_utc_ms` may be off.
+    pub clock: ClockHealth,
+    /// Audio start minus video start in the buffer, corrected in the clip. None without audio
+    /// or when either start is unknown.
+    pub av_skew_ms: Option<i64>,
+}
+
+/// How the stitched audio is cut to line up with the clip's video.
+#[derive(Debug, Clone, Copy, PartialEq)]
+struct AudioCut {
+    /// Seconds skipped at the start of the stitched audio
+    trim_sec: f64,
+    /// Silence before the audio, when it starts after the clip does
+    delay_ms: u64,
+    skew_ms: i64,
+}
+
+impl AudioCut {
+    fn align(clip_start_ms: u64, audio_start_ms: u64, video_start_ms: u64) -> Self {
+        let lead_ms = clip_start_ms as i64 - audio_start_ms as i64;
+        Self {
+            trim_sec: lead_ms.max(0) as f64 / 1000.0,
+            delay_ms: (-lead_ms).max(0) as u64,
+            skew_ms: audio_start_ms as i64 - video_start_ms as i64,
+        }
+    }
 }
 
 #[command]
-pub async fn save_replay(app: AppHandle, trigger_timestamp: Option<u64>) -> Result<SavedReplay, String> {
-    save_replay_impl(&app, trigger_timestamp).await
+pub async fn save_replay(app: AppHandle, trigger_timestamp: Option<u64>, duration_secs: Option<u32>) -> Result<SavedReplay, String> {
+    save_replay_impl(&app, trigger_timestamp, duration_secs).await
 }
 
-pub async fn save_replay_impl(app: &AppHandle, trigger_timestamp: Option<u64>) -> Result<SavedReplay, String> {
+/// Saves the last `duration_secs` (default: the configured buffer duration) before the trigger.
+pub async fn save_replay_impl(app: &AppHandle, trigger_timestamp: Option<u64>, duration_secs: Option<u32>) -> Result<SavedReplay, String> {
     log::info!("Save Replay triggered (Time-Based)");
 
     // Wait for FFmpeg to flush recent packets
@@ -47,8 +80,12 @@ pub async fn save_replay_impl(app: &AppHandle, trigger_timestamp: Option<u64>) -
     };
     
     let ntp_offset = state.ntp_manager.get_offset();
+    let clock = state.ntp_manager.health()?;
+    if !clock.synced {
+        log::warn!("Saving replay with an unsynced clock (uncertainty: {:?} ms)", clock.uncertainty_ms);
+    }
     
-    // Convert back to Local System Time for file searching
+    // Convert back to System Time for file searching
     // TriggerTime_Local = TriggerTime_NTP - Offset
     // (Because FileTime = SystemTime)
     let trigger_time_ms = if ntp_offset >= 0 {
@@ -58,15 +95,16 @@ pub async fn save_replay_impl(app: &AppHandle, trigger_timestamp: Option<u64>) -
     };
 
     let trigger_time = std::time::UNIX_EPOCH + std::time::Duration::from_millis(trigger_time_ms);
-    let trigger_datetime: DateTime<Local> = trigger_time.into();
+    let trigger_datetime: DateTime<Utc> = trigger_time.into();
     
     log::info!("Trigger Time: {} (NTP: {}, Offset: {}, Remote: {})", trigger_datetime, ntp_time_ms, ntp_offset, is_remote);
 
     // 3. Define Time Range
-    // Add 15 seconds of padding to the duration to account for trigger latency/skew
+    // Search 15 seconds further back to account for trigger latency/skew
     // This ensures we capture enough historical video data even if the trigger is late.
-    let duration_sec = config.recording.buffer_duration as i64 + 15;
-    let start_time = trigger_datetime - Duration::seconds(duration_sec);
+    // The clip itself stays the requested length.
+    let duration_sec = duration_secs.unwrap_or(config.recording.buffer_duration) as i64;
+    let start_time = trigger_datetime - Duration::seconds(duration_sec + 15);
     let end_time = trigger_datetime;
 
     log::info!("Searching for segments between {} and {}", start_time, end_time);
@@ -96,51 +134,27 @@ pub async fn save_replay_impl(app: &AppHandle, trigger_timestamp: Option<u64>) -
     let stitch_temp_dir = buffer_dir.join(format!("stitch_{}", timestamp_str));
     fs::create_dir_all(&stitch_temp_dir).map_err(|e| e.to_string())?;
 
-    // 6. Probe Start Time (Precision)
-    // We need the EXACT start time of the first video segment to calculate trim
+    // 6. First Segment Starts (NTP)
+    // The segment index has them stamped with NTP time as FFmpeg opened each segment. Older
+    // segments fall back to the file name / probe, which are system time.
+    let segment_index = SegmentIndex::new(&buffer_dir).load();
+    let video_segments = latest_run(video_segments, &segment_index);
+    let audio_segments = latest_run(audio_segments, &segment_index);
+    let first_video_start_ms_ntp = segment_start_ms_ntp(app, &video_segments[0], &segment_index, ntp_offset);
+    let first_audio_start_ms_ntp = audio_segments.first().and_then(|path| segment_start_ms_ntp(app, path, &segment_index, ntp_offset));
 
-    let first_video_path = &video_segments[0];
-    let first_video_start_ms_local_opt = probe_start_time(app, first_video_path);
-    let first_video_start_ms_local = first_video_start_ms_local_opt.unwrap_or(0);
-    
-    // Calculate Trim Start (Local Time)
+    // Calculate Trim Start (NTP Time)
     // Target Start = Trigger - Duration
     // Actual Start = First Segment Start
     // Trim = Target Start - Actual Start
-    
-    let target_start_ms = trigger_time_ms - (duration_sec as u64 * 1000);
-    let trim_start_sec = if first_video_start_ms_local > 0 && target_start_ms > first_video_start_ms_local {
-        (target_start_ms - first_video_start_ms_local) as f64 / 1000.0
-    } else {
-        0.0
+    let target_start_ms = ntp_time_ms.saturating_sub(duration_sec as u64 * 1000);
+    let trim_start_sec = match first_video_start_ms_ntp {
+        Some(first_ms) if target_start_ms > first_ms => (target_start_ms - first_ms) as f64 / 1000.0,
+        _ => 0.0,
     };
 
-    // Calculate Effective Start Time (Local -> NTP)
-    // This is the timestamp where the generated video file actually starts.
-    // If we trimmed, it starts at target_start_ms.
-    // If we didn't trim, it starts at first_video_start_ms_local.
-    let effective_start_ms_local = if trim_start_sec > 0.0 {
-        target_start_ms
-    } else {
-        first_video_start_ms_local
-    };
-
-    // Convert Effective Start to NTP
-    let effective_start_ms_ntp = if ntp_offset >= 0 {
-        effective_start_ms_local.saturating_sub(ntp_offset as u64)
-    } else {
-        effective_start_ms_local + ((-ntp_offset) as u64)
-    };
-    
-    // Fallback if local probe failed completely (shouldn't happen with valid segments)
-    let final_start_time_utc_ms = if first_video_start_ms_local > 0 {
-        Some(effective_start_ms_ntp)
-    } else {
-        None 
-    };
-
-    log::info!("Precision Trim: Target(Local)={}, Actual(Local)={}, Trim={:.3}s, FinalStart(NTP)={:?}", 
-        target_start_ms, first_video_start_ms_local, trim_start_sec, final_start_time_utc_ms);
+    log::info!("Precision Trim: Target(NTP)={}, Actual(NTP)={:?}, Trim={:.3}s",
+        target_start_ms, first_video_start_ms_ntp, trim_start_sec);
 
     // 7. Stitch Video
     let temp_video_path = stitch_temp_dir.join("temp_video.mp4");
@@ -169,29 +183,28 @@ pub async fn save_replay_impl(app: &AppHandle, trigger_timestamp: Option<u64>) -
         }
     }
 
-    // Recalculate Effective Start Time based on Actual Trim (Keyframe)
-    // Start_Local = First_Segment_Start + Actual_Trim_Offset
-    let effective_start_ms_local = first_video_start_ms_local + (actual_trim_start_sec * 1000.0) as u64;
-
-    let effective_start_ms_ntp = if ntp_offset >= 0 {
-        effective_start_ms_local.saturating_sub(ntp_offset as u64)
-    } else {
-        effective_start_ms_local + ((-ntp_offset) as u64)
-    };
-    
-    let final_start_time_utc_ms = if first_video_start_ms_local > 0 {
-        Some(effective_start_ms_ntp)
-    } else {
-        None 
+    // Effective Start Time based on Actual Trim (Keyframe)
+    // Start = First_Segment_Start + Actual_Trim_Offset
+    let final_start_time_utc_ms = first_video_start_ms_ntp.map(|first_ms| first_ms + (actual_trim_start_sec * 1000.0) as u64);
+
+    // 8c. A/V Alignment
+    // The audio process starts (and cuts segments) independently of the video one, so the
+    // stitched audio gets its own trim from its own first segment start.
+    let audio_cut = match (final_start_time_utc_ms, first_audio_start_ms_ntp) {
+        (Some(clip_start_ms), Some(audio_start_ms)) if has_audio => {
+            let cut = AudioCut::align(clip_start_ms, audio_start_ms, first_video_start_ms_ntp.unwrap_or(clip_start_ms));
+            if cut.skew_ms.unsigned_abs() as f64 > REPLAY_AUDIO_SYNC_THRESHOLD_SEC * 1000.0 {
+                log::warn!("Audio starts {} ms off video, correcting", cut.skew_ms);
+            }
+            log::info!("A/V Sync: Skew={} ms, Audio Trim={:.3}s, Audio Delay={} ms", cut.skew_ms, cut.trim_sec, cut.delay_ms);
+            Some(cut)
+        }
+        _ => None,
     };
 
     // 9. Merge & Trim
     let output_filename = format!("Replay_{}.mp4", timestamp_str);
-    let output_dir = if !config.recording.path.is_empty() {
-        PathBuf::from(&config.recording.path)
-    } else {
-        app.path().video_dir().map_err(|e| e.to_string())?.join("SquadSync")
-    };
+    let output_dir = crate::commands::playback::recordings_dir(app, &config)?;
 
     if !output_dir.exists() {
         fs::create_dir_all(&output_dir).map_err(|e| e.to_string())?;
@@ -211,15 +224,38 @@ pub async fn save_replay_impl(app: &AppHandle, trigger_timestamp: Option<u64>) -
     cmd.arg("-i").arg(&temp_video_path);
 
     if has_audio {
-        // Audio doesn't have keyframes, but should align with video start
-        cmd.arg("-ss").arg(actual_trim_start_sec.to_string());
+        // Without both start times, assume audio and video started together
+        let audio_trim_sec = audio_cut.map_or(actual_trim_start_sec, |cut| cut.trim_sec);
+        cmd.arg("-ss").arg(audio_trim_sec.to_string());
         cmd.arg("-i").arg(&temp_audio_path);
     }
 
+    // Markers inside the clip become chapters
+    let clip_duration_ms = (duration_sec * 1000) as u64;
+    let clip_markers = match final_start_time_utc_ms {
+        Some(start_ms) => {
+            let buffered = state.markers.lock().map_err(|e| e.to_string())?
+                .between(&buffer_dir, start_ms, start_ms + clip_duration_ms);
+            markers::clip_markers(&buffered, start_ms, clip_duration_ms)
+        }
+        None => Vec::new(),
+    };
+    if !clip_markers.is_empty() {
+        let chapters_path = stitch_temp_dir.join("chapters.txt");
+        fs::write(&chapters_path, markers::ffmetadata(&clip_markers, clip_duration_ms)).map_err(|e| e.to_string())?;
+        let chapters_input = if has_audio { 2 } else { 1 };
+        cmd.arg("-i").arg(&chapters_path);
+        cmd.arg("-map_chapters").arg(chapters_input.to_string());
+        log::info!("Writing {} marker(s) as chapters", clip_markers.len());
+    }
+
     // Map & Encode
     cmd.arg("-map").arg("0:v");
     if has_audio {
         cmd.arg("-map").arg("1:a");
+        if let Some(cut) = audio_cut.filter(|cut| cut.delay_ms > 0) {
+            cmd.arg("-af").arg(format!("adelay={}:all=1", cut.delay_ms));
+        }
         cmd.arg("-c:a").arg("aac");
         cmd.arg("-b:a").arg("192k");
     }
@@ -235,11 +271,24 @@ pub async fn save_replay_impl(app: &AppHandle, trigger_timestamp: Option<u64>) -
     let _ = fs::remove_dir_all(&stitch_temp_dir);
 
     if status.success() {
+        let meta = ClipMetadata {
+            markers: clip_markers,
+            game: state.active_game.lock().map_err(|e| e.to_string())?.clone(),
+        };
+        if !meta.is_empty() {
+            if let Err(e) = meta.save(&output_path) {
+                log::warn!("{}", e);
+            }
+        }
         Ok(SavedReplay {
             file_path: output_path.to_string_lossy().to_string(),
-            duration_ms: (duration_sec * 1000) as u64,
+            duration_ms: clip_duration_ms,
             start_time_utc_ms: final_start_time_utc_ms,
             version: 1,
+            markers: meta.markers,
+            game: meta.game,
+            clock,
+            av_skew_ms: audio_cut.map(|cut| cut.skew_ms),
         })
     } else {
         Err("FFmpeg merge process failed".to_string())
@@ -294,8 +343,8 @@ fn find_nearest_keyframe(app: &AppHandle, path: &Path, target_sec: f64) -> Resul
 fn find_segments_by_time(
     dir: &PathBuf, 
     prefix: &str, 
-    start: DateTime<Local>, 
-    end: DateTime<Local>
+    start: DateTime<Utc>, 
+    end: DateTime<Utc>
 ) -> Result<Vec<PathBuf>, String> {
     let mut segments = Vec::new();
 
@@ -311,9 +360,8 @@ fn find_segments_by_time(
         if let Some(fname) = path.file_name().and_then(|n| n.to_str()) {
             if fname.starts_with(prefix) && fname.ends_with(".mkv") {
                  if let Ok(epoch_ms) = crate::ffmpeg::utils::parse_segment_filename_to_epoch_ms(fname) {
-                     // Convert Epoch MS back to Local DateTime for comparison
                      let ts = std::time::UNIX_EPOCH + std::time::Duration::from_millis(epoch_ms);
-                     let ts_dt: DateTime<Local> = ts.into();
+                     let ts_dt: DateTime<Utc> = ts.into();
 
                      // Check overlap
                      // Segment covers [ts, ts + 15s] roughly
@@ -338,6 +386,33 @@ fn find_segments_by_time(
     Ok(segments.into_iter().map(|(p, _)| p).collect())
 }
 
+/// The segments written by the same FFmpeg run as the last one. A restart (of the audio alone,
+/// or of both processes keeping the buffer) leaves a gap that stitching would close, so
+/// segments from before it are dropped: the clip starts at the restart, and audio that starts
+/// after the video is padded with silence (see [AudioCut]). Segments missing from the index
+/// are kept.
+fn latest_run(segments: Vec<PathBuf>, index: &HashMap<String, SegmentEntry>) -> Vec<PathBuf> {
+    let run_of = |path: &PathBuf| path.file_name().and_then(|n| n.to_str()).and_then(|n| index.get(n)).map(|e| e.run_start_us);
+    let Some(latest) = segments.iter().rev().find_map(run_of) else {
+        return segments;
+    };
+    let total = segments.len();
+    let kept: Vec<PathBuf> = segments.into_iter().filter(|path| run_of(path).map_or(true, |run| run == latest)).collect();
+    if kept.len() < total {
+        log::info!("Skipping {} segment(s) from before a restart", total - kept.len());
+    }
+    kept
+}
+
+/// NTP start of a segment: from the segment index, else its file name shifted by the current offset.
+fn segment_start_ms_ntp(app: &AppHandle, path: &Path, index: &HashMap<String, SegmentEntry>, ntp_offset: i64) -> Option<u64> {
+    if let Some(entry) = path.file_name().and_then(|n| n.to_str()).and_then(|n| index.get(n)) {
+        return Some(entry.start_ms());
+    }
+    log::warn!("{:?} is not in the segment index, using its file name", path);
+    probe_start_time(app, path).map(|local_ms| (local_ms as i64 + ntp_offset).max(0) as u64)
+}
+
 fn probe_start_time(app: &AppHandle, path: &Path) -> Option<u64> {
     // 1. Parse Filename for approximate Epoch (Fallback & Validation)
     // This now supports high-precision via utils
@@ -447,33 +522,26 @@ fn stitch_segments(app: &AppHandle, segments: &[PathBuf], temp_dir: &Path, outpu
 pub fn cleanup_buffer(buffer_dir: &PathBuf, retention_seconds: u32) -> std::io::Result<()> {
     if !buffer_dir.exists() { return Ok(()); }
     
-    let now = Local::now();
-    let retention = Duration::seconds(retention_seconds as i64);
-    
-    // Regex for new pattern
-    // Update: allow optional 3-digit millisecond suffix
-    // r"(video|audio)_(\d{14})(\d{3})?\.mkv"
-    let re = RE_VIDEO_AUDIO.get_or_init(|| Regex::new(r"(video|audio)_(\d{14})(\d{3})?\.mkv").expect("Invalid Regex Pattern"));
+    let now_ms = Utc::now().timestamp_millis().max(0) as u64;
+    let retention_ms = retention_seconds as u64 * 1000;
 
     for entry in fs::read_dir(buffer_dir)? {
         let entry = entry?;
         let path = entry.path();
         if let Some(fname) = path.file_name().and_then(|n| n.to_str()) {
-            if let Some(caps) = re.captures(fname) {
-                // Determine which group is the timestamp (could be 2 or 3 depending on implementation, but regex above is clear)
-                // Group 2 is base timestamp. Group 3 is optional MS (we ignore MS for retention as seconds prec is enough)
-                if let Some(ts_str) = caps.get(2) {
-                    if let Ok(naive) = chrono::NaiveDateTime::parse_from_str(ts_str.as_str(), "%Y%m%d%H%M%S") {
-                        if let Some(ts) = Local.from_local_datetime(&naive).latest() {
-                             if now - ts > retention {
-                                 let _ = fs::remove_file(path);
-                             }
-                        }
-                    }
+            if !(fname.starts_with("video_") || fname.starts_with("audio_")) || !fname.ends_with(".mkv") {
+                continue;
+            }
+            if let Ok(ts_ms) = crate::ffmpeg::utils::parse_segment_filename_to_epoch_ms(fname) {
+                if now_ms.saturating_sub(ts_ms) > retention_ms {
+                    let _ = fs::remove_file(path);
                 }
             }
         }
     }
+    if let Err(e) = SegmentIndex::new(buffer_dir).prune() {
+        log::warn!("{}", e);
+    }
     Ok(())
 }
 
@@ -534,6 +602,38 @@ mod tests {
     use super::*;
     use std::fs::File;
 
+    #[test]
+    fn test_audio_cut_align() {
+        // Audio started 180 ms before video: skip more of it
+        let cut = AudioCut::align(1_000_010_000, 1_000_000_000, 1_000_000_180);
+        assert_eq!((cut.trim_sec, cut.delay_ms, cut.skew_ms), (10.0, 0, -180));
+
+        // Audio only starts 250 ms into the clip: pad with silence
+        let cut = AudioCut::align(1_000_000_000, 1_000_000_250, 1_000_000_000);
+        assert_eq!((cut.trim_sec, cut.delay_ms, cut.skew_ms), (0.0, 250, 250));
+    }
+
+    #[test]
+    fn test_latest_run() {
+        let entry = |file: &str, run_start_us| SegmentEntry { file: file.into(), start_us: 0, offset_us: 0, uncertainty_ms: None, drift_ppm: 0.0, run_start_us };
+        let index: HashMap<String, SegmentEntry> = [
+            entry("audio_20240101T100000Z.mkv", 1),
+            entry("audio_20240101T100002Z.mkv", 1),
+            entry("audio_20240101T100005Z.mkv", 2),
+            entry("audio_20240101T100007Z.mkv", 2),
+        ].into_iter().map(|e| (e.file.clone(), e)).collect();
+        let paths = |names: &[&str]| names.iter().map(|n| PathBuf::from("buffer").join(n)).collect::<Vec<_>>();
+
+        let segments = paths(&["audio_20240101T100000Z.mkv", "audio_20240101T100002Z.mkv", "audio_20240101T100005Z.mkv", "audio_20240101T100007Z.mkv"]);
+        assert_eq!(latest_run(segments, &index), paths(&["audio_20240101T100005Z.mkv", "audio_20240101T100007Z.mkv"]));
+
+        // One run, or no index at all: everything is kept
+        let segments = paths(&["audio_20240101T100000Z.mkv", "audio_20240101T100002Z.mkv"]);
+        assert_eq!(latest_run(segments.clone(), &index), segments);
+        let segments = paths(&["audio_20240101T095958Z.mkv", "audio_20240101T100000Z.mkv"]);
+        assert_eq!(latest_run(segments.clone(), &HashMap::new()), segments);
+    }
+
     #[test]
     fn test_find_segments_by_time() {
         let temp_dir = std::env::temp_dir().join("squad_sync_test_time");
@@ -544,21 +644,18 @@ mod tests {
             File::create(temp_dir.join(name)).unwrap();
         };
 
-        create_file("video_20240101100000.mkv");
-        create_file("video_20240101100002.mkv");
-        create_file("video_20240101100004.mkv");
+        create_file("video_20240101T100000Z.mkv");
+        create_file("video_20240101T100002Z.mkv");
+        create_file("video_20240101T100004Z.mkv");
 
-        let start_naive = chrono::NaiveDateTime::parse_from_str("20240101100001", "%Y%m%d%H%M%S").unwrap();
-        let end_naive = chrono::NaiveDateTime::parse_from_str("20240101100003", "%Y%m%d%H%M%S").unwrap();
-        
-        let start = Local.from_local_datetime(&start_naive).latest().unwrap();
-        let end = Local.from_local_datetime(&end_naive).latest().unwrap();
+        let start: DateTime<Utc> = "2024-01-01T10:00:01Z".parse().unwrap();
+        let end: DateTime<Utc> = "2024-01-01T10:00:03Z".parse().unwrap();
 
         let segments = find_segments_by_time(&temp_dir, "video_", start, end).unwrap();
         
         assert_eq!(segments.len(), 2);
-        assert!(segments[0].to_string_lossy().contains("20240101100000"));
-        assert!(segments[1].to_string_lossy().contains("20240101100002"));
+        assert!(segments[0].to_string_lossy().contains("20240101T100000Z"));
+        assert!(segments[1].to_string_lossy().contains("20240101T100002Z"));
 
         let _ = fs::remove_dir_all(&temp_dir);
     }
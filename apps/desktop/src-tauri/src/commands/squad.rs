@@ -0,0 +1,42 @@
+use tauri::{command, AppHandle, Emitter, State};
+use std::sync::Arc;
+use crate::commands::playback::recordings_dir;
+use crate::state::RecordingState;
+use crate::upload::download::{moment_dir, view_file_name, MomentView, SquadMoment, View, DOWNLOAD_PROGRESS_EVENT};
+use crate::upload::progress::ProgressCallback;
+use crate::upload::{UploadManager, UploadOptions};
+
+/// Downloads a squadmate's view of `clip_id` into the clip's local squad moment bundle and
+/// records its metadata. Returns the path of the downloaded file.
+#[command]
+pub async fn download_clip(app: AppHandle, state: State<'_, RecordingState>, clip_id: String, view: View, own_clip_path: Option<String>) -> Result<String, String> {
+    let config = state.config.lock().map_err(|e| e.to_string())?.clone();
+    let dir = moment_dir(&recordings_dir(&app, &config)?, &clip_id);
+    let file = view_file_name(&view.author);
+    let dest = dir.join(&file);
+    log::info!("Downloading {}'s view of clip {} to {:?}", view.author, clip_id, dest);
+
+    let emitter = app.clone();
+    let on_progress: ProgressCallback = Arc::new(move |progress| {
+        if let Err(e) = emitter.emit(DOWNLOAD_PROGRESS_EVENT, progress) {
+            log::error!("Failed to emit {}: {}", DOWNLOAD_PROGRESS_EVENT, e);
+        }
+    });
+
+    let options = UploadOptions { max_retries: config.upload.max_retries, ..Default::default() };
+    let manager = UploadManager::new(options)?;
+    manager.download(&view.url, &dest, view.sha256.as_deref(), on_progress).await?;
+
+    let mut moment = SquadMoment::load(&dir, &clip_id);
+    if own_clip_path.is_some() {
+        moment.own_clip = own_clip_path;
+    }
+    moment.upsert(MomentView {
+        view,
+        file,
+        downloaded_at_ms: chrono::Utc::now().timestamp_millis() as u64,
+    });
+    moment.save(&dir)?;
+
+    Ok(dest.to_string_lossy().to_string())
+}
@@ -1,38 +1,135 @@
-use tauri::command;
+use tauri::{command, AppHandle, Emitter, State};
 use std::path::PathBuf;
-use tokio::fs::File;
-use tokio_util::io::ReaderStream;
+use std::sync::Arc;
+use crate::state::RecordingState;
+use crate::upload::{MultipartPlan, UploadManager, UploadOptions, UPLOAD_PROGRESS_EVENT};
+use crate::upload::progress::ProgressCallback;
+use crate::config::{AppConfig, StorageBackend};
+use crate::upload::integrity::ClipHashes;
+use crate::upload::storage::{clip_key, S3Storage, Storage};
+use crate::upload::queue::{QueueEvent, QueueListener, UploadJob, UploadQueue, UPLOAD_STATUS_EVENT, UPLOAD_URL_EXPIRED_EVENT};
+use crate::upload::throttle::RateLimiter;
 
-#[command]
-pub async fn upload_clip_to_url(file_path: String, upload_url: String) -> Result<(), String> {
-    log::info!("Starting upload for {} to {}", file_path, upload_url);
+/// Upload options from the config. The limiter is shared so the cap covers all uploads together.
+fn upload_options(state: &RecordingState) -> Result<UploadOptions, String> {
+    let upload_config = state.config.lock().map_err(|e| e.to_string())?.upload.clone();
 
-    let path = PathBuf::from(&file_path);
-    if !path.exists() {
-        return Err(format!("File not found: {}", file_path));
-    }
+    let limiter = match upload_config.bandwidth_limit_kbps.filter(|kbps| *kbps > 0) {
+        Some(kbps) => {
+            let mut cached = state.upload_limiter.lock().map_err(|e| e.to_string())?;
+            match cached.as_ref() {
+                Some((cached_kbps, limiter)) if *cached_kbps == kbps => Some(limiter.clone()),
+                _ => {
+                    let limiter = RateLimiter::from_kbps(kbps);
+                    *cached = Some((kbps, limiter.clone()));
+                    Some(limiter)
+                }
+            }
+        }
+        None => None,
+    };
+
+    Ok(UploadOptions {
+        max_retries: upload_config.max_retries,
+        limiter,
+        ..Default::default()
+    })
+}
+
+fn progress_emitter(app: AppHandle) -> ProgressCallback {
+    Arc::new(move |progress| {
+        if let Err(e) = app.emit(UPLOAD_PROGRESS_EVENT, progress) {
+            log::error!("Failed to emit {}: {}", UPLOAD_PROGRESS_EVENT, e);
+        }
+    })
+}
+
+/// Forwards queue events to the frontend.
+pub(crate) fn queue_listener(app: AppHandle) -> QueueListener {
+    Arc::new(move |event| {
+        let result = match event {
+            QueueEvent::Status(job) => app.emit(UPLOAD_STATUS_EVENT, job),
+            QueueEvent::UrlExpired(job) => app.emit(UPLOAD_URL_EXPIRED_EVENT, job),
+            QueueEvent::Progress(progress) => app.emit(UPLOAD_PROGRESS_EVENT, progress),
+        };
+        if let Err(e) = result {
+            log::error!("Failed to emit upload event: {}", e);
+        }
+    })
+}
 
-    let file = File::open(&path).await.map_err(|e| format!("Failed to open file: {}", e))?;
-    let metadata = file.metadata().await.map_err(|e| format!("Failed to get file metadata: {}", e))?;
-    let file_size = metadata.len();
-    let stream = ReaderStream::new(file);
-    let body = reqwest::Body::wrap_stream(stream);
-
-    let client = reqwest::Client::new();
-    let response = client
-        .put(&upload_url)
-        .header("Content-Type", "video/mp4")
-        .header("Content-Length", file_size)
-        .body(body)
-        .send()
-        .await
-        .map_err(|e| format!("Upload request failed: {}", e))?;
-
-    if !response.status().is_success() {
-        return Err(format!("Upload failed with status: {}", response.status()));
+/// The direct S3 backend, if it is the configured one.
+fn direct_storage(config: &AppConfig) -> Result<Option<S3Storage>, String> {
+    match config.storage.backend {
+        StorageBackend::S3 => S3Storage::new(config.storage.s3.clone()).map(Some),
+        StorageBackend::Presigned => Ok(None),
     }
+}
+
+/// Applies the current upload config to the queue and starts whatever it allows.
+pub(crate) fn pump_queue(queue: &Arc<UploadQueue>, state: &RecordingState) -> Result<(), String> {
+    let config = state.config.lock().map_err(|e| e.to_string())?.clone();
+    let signer = direct_storage(&config).unwrap_or_else(|e| {
+        log::error!("S3 storage is misconfigured: {}", e);
+        None
+    });
+    queue.configure(upload_options(state)?, config.upload.max_concurrent);
+    queue.set_signer(signer.map(|s| Arc::new(s) as Arc<dyn Storage>));
+    queue.pump();
+    Ok(())
+}
+
+/// Queues the upload and waits for it. The upload continues (and resumes after a restart)
+/// even if the caller goes away.
+#[command]
+pub async fn upload_clip_to_url(state: State<'_, RecordingState>, queue: State<'_, Arc<UploadQueue>>, file_path: String, upload_url: String, clip_id: Option<String>) -> Result<Option<ClipHashes>, String> {
+    log::info!("Queueing upload for {}", file_path);
+
+    let id = queue.enqueue(&file_path, &upload_url, clip_id, None);
+    pump_queue(&queue, &state)?;
+    let job = queue.wait(&id).await?;
+
+    log::info!("Upload successful for {}", file_path);
+    Ok(job.hashes)
+}
+
+/// Uploads straight to the configured S3-compatible bucket (self-hosted storage).
+#[command]
+pub async fn upload_clip_to_storage(state: State<'_, RecordingState>, queue: State<'_, Arc<UploadQueue>>, file_path: String) -> Result<String, String> {
+    let config = state.config.lock().map_err(|e| e.to_string())?.clone();
+    let storage = direct_storage(&config)?.ok_or("Direct storage is not enabled (storage.backend = \"s3\")")?;
+
+    let key = clip_key(config.user.user_id.as_deref(), &PathBuf::from(&file_path));
+    log::info!("Queueing {} upload of {} as {}", storage.name(), file_path, key);
+    let id = queue.enqueue_signed(&file_path, &key, storage.put_url(&key)?);
+    pump_queue(&queue, &state)?;
+    queue.wait(&id).await?;
 
     log::info!("Upload successful for {}", file_path);
+    Ok(key)
+}
+
+#[command]
+pub fn list_uploads(queue: State<'_, Arc<UploadQueue>>) -> Vec<UploadJob> {
+    queue.list()
+}
+
+/// Supplies a new presigned URL for an upload whose URL expired.
+#[command]
+pub async fn refresh_upload_url(state: State<'_, RecordingState>, queue: State<'_, Arc<UploadQueue>>, id: String, upload_url: String) -> Result<(), String> {
+    queue.refresh_url(&id, &upload_url, None)?;
+    pump_queue(&queue, &state)
+}
+
+/// Multipart upload with per-part retry. Calling it again with the same plan resumes.
+#[command]
+pub async fn upload_clip_multipart(app: AppHandle, state: State<'_, RecordingState>, file_path: String, plan: MultipartPlan) -> Result<(), String> {
+    log::info!("Starting multipart upload {} for {} ({} parts)", plan.upload_id, file_path, plan.part_urls.len());
+
+    let manager = UploadManager::new(upload_options(&state)?)?;
+    manager.upload_multipart(&PathBuf::from(&file_path), &plan, progress_emitter(app)).await?;
+
+    log::info!("Multipart upload successful for {}", file_path);
     Ok(())
 }
 
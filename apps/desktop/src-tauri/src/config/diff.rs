@@ -0,0 +1,184 @@
+//! What a settings change affects, so `update_config` restarts only the parts of the recorder
+//! that read the changed fields at startup.
+
+use serde_json::Value;
+
+use super::{AppConfig, Resolution};
+
+/// How much of the recorder a change needs restarted, least to most.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+pub enum ChangeKind {
+    /// Read where it's used, or restarted by its own subsystem. Recording carries on.
+    Hot,
+    /// Audio captures and the audio FFmpeg restart, video keeps recording
+    AudioRestart,
+    /// Both FFmpeg processes restart
+    FullRestart,
+}
+
+/// Recording fields read live: the output folder on save, retention and buffer length by cleanup.
+const HOT_FIELDS: &[&str] = &["path", "buffer_retention_seconds", "buffer_duration"];
+
+/// Recording fields only the audio pipeline reads.
+const AUDIO_FIELDS: &[&str] = &[
+    "audio_source", "system_audio_device", "audio_source_id", "system_audio_device_id",
+    "audio_codec", "audio_bitrate", "audio_backend",
+];
+
+/// Recording fields that change the format of the video segments. Segments that differ in
+/// frame rate or encoder settings can't be stream-copied into one clip either.
+const VIDEO_FORMAT_FIELDS: &[&str] = &[
+    "resolution", "framerate", "encoder", "video_codec", "video_preset", "video_tune", "video_profile",
+];
+
+/// With `Resolution::Native` the output size follows the capture, so these change it too.
+const CAPTURE_FIELDS: &[&str] = &["capture_target", "monitor_index"];
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct ConfigDiff {
+    /// Dotted path of every changed field, with what it needs
+    pub changes: Vec<(String, ChangeKind)>,
+    keeps_segments: bool,
+}
+
+impl ConfigDiff {
+    pub fn new(old: &AppConfig, new: &AppConfig) -> Self {
+        let (old_value, new_value) = (to_object(old), to_object(new));
+        let mut changes = Vec::new();
+        for (section, field) in changed_fields(&old_value, &new_value) {
+            let kind = match (section.as_str(), field.as_deref()) {
+                ("version", _) => continue,
+                ("recording", Some(f)) if HOT_FIELDS.contains(&f) => ChangeKind::Hot,
+                ("recording", Some(f)) if AUDIO_FIELDS.contains(&f) => ChangeKind::AudioRestart,
+                ("recording", _) => ChangeKind::FullRestart,
+                // Hotkeys, plugin API and GSI are restarted by `update_config`, the rest is read on use
+                _ => ChangeKind::Hot,
+            };
+            let path = match field {
+                Some(field) => format!("{}.{}", section, field),
+                None => section,
+            };
+            changes.push((path, kind));
+        }
+
+        let changes_size = |fields: &[&str]| fields.iter().any(|f| changes.iter().any(|(path, _)| path == &format!("recording.{}", f)));
+        let native = old.recording.resolution == Resolution::Native || new.recording.resolution == Resolution::Native;
+        let keeps_segments = old.recording.temp_path == new.recording.temp_path
+            && !changes_size(VIDEO_FORMAT_FIELDS)
+            && !(native && changes_size(CAPTURE_FIELDS));
+
+        Self { changes, keeps_segments }
+    }
+
+    /// The most any change needs, None if nothing changed.
+    pub fn kind(&self) -> Option<ChangeKind> {
+        self.changes.iter().map(|(_, kind)| *kind).max()
+    }
+
+    /// Whether a full restart can keep the buffered video segments, which it can when the new
+    /// ones will have the same resolution, frame rate, codec and encoder settings.
+    pub fn keeps_segments(&self) -> bool {
+        self.keeps_segments
+    }
+}
+
+fn to_object(config: &AppConfig) -> serde_json::Map<String, Value> {
+    match serde_json::to_value(config) {
+        Ok(Value::Object(map)) => map,
+        _ => serde_json::Map::new(),
+    }
+}
+
+/// (section, field) pairs that differ. Recording is compared field by field, other sections whole.
+fn changed_fields(old: &serde_json::Map<String, Value>, new: &serde_json::Map<String, Value>) -> Vec<(String, Option<String>)> {
+    let mut changed = Vec::new();
+    let sections = old.keys().chain(new.keys().filter(|k| !old.contains_key(*k)));
+    for section in sections {
+        let (a, b) = (old.get(section), new.get(section));
+        if a == b {
+            continue;
+        }
+        match (section.as_str(), a, b) {
+            ("recording", Some(Value::Object(a)), Some(Value::Object(b))) => {
+                let fields = a.keys().chain(b.keys().filter(|k| !a.contains_key(*k)));
+                for field in fields {
+                    if a.get(field) != b.get(field) {
+                        changed.push((section.clone(), Some(field.clone())));
+                    }
+                }
+            }
+            _ => changed.push((section.clone(), None)),
+        }
+    }
+    changed
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::config::{Bitrate, EncoderChoice};
+    use crate::ffmpeg::encoder::{VideoCodec, VideoEncoder};
+    use crate::hotkeys::HotkeyAction;
+
+    fn diff(change: impl FnOnce(&mut AppConfig)) -> ConfigDiff {
+        let old = AppConfig::default();
+        let mut new = old.clone();
+        change(&mut new);
+        ConfigDiff::new(&old, &new)
+    }
+
+    #[test]
+    fn test_classifies_changes() {
+        assert_eq!(diff(|_| {}).kind(), None);
+
+        let hot = diff(|c| {
+            c.recording.path = "D:\\Clips".into();
+            c.recording.buffer_retention_seconds += 60;
+            c.hotkeys.insert(HotkeyAction::SaveReplay, "F9".into());
+            c.upload.max_concurrent += 1;
+        });
+        assert_eq!(hot.kind(), Some(ChangeKind::Hot));
+        let mut paths: Vec<&str> = hot.changes.iter().map(|(p, _)| p.as_str()).collect();
+        paths.sort();
+        assert_eq!(paths, vec!["hotkeys", "recording.buffer_retention_seconds", "recording.path", "upload"]);
+
+        let audio = diff(|c| {
+            c.recording.path = "D:\\Clips".into();
+            c.recording.audio_bitrate = Some(Bitrate(256_000));
+            c.recording.system_audio_device = Some("Speakers (Realtek)".into());
+        });
+        assert_eq!(audio.kind(), Some(ChangeKind::AudioRestart));
+
+        let full = diff(|c| {
+            c.recording.audio_bitrate = Some(Bitrate(256_000));
+            c.recording.bitrate = Some(Bitrate(20_000_000));
+        });
+        assert_eq!(full.kind(), Some(ChangeKind::FullRestart));
+        assert!(full.keeps_segments());
+    }
+
+    #[test]
+    fn test_keeps_segments_only_for_same_format() {
+        assert!(!diff(|c| c.recording.encoder = EncoderChoice::Fixed(VideoEncoder::Nvenc, VideoCodec::Hevc)).keeps_segments());
+        assert!(!diff(|c| c.recording.resolution = Resolution::Fixed { width: 1280, height: 720 }).keeps_segments());
+        assert!(!diff(|c| c.recording.framerate = 30).keeps_segments());
+        assert!(!diff(|c| c.recording.video_preset = Some("quality".into())).keeps_segments());
+        assert!(!diff(|c| c.recording.video_profile = Some("high".into())).keeps_segments());
+        assert!(!diff(|c| c.recording.temp_path = "D:\\Buffer".into()).keeps_segments());
+
+        // The default is a fixed size, so the monitor doesn't matter...
+        let other_monitor = diff(|c| {
+            c.recording.monitor_index = Some(1);
+            c.recording.bitrate = Some(Bitrate(20_000_000));
+        });
+        assert_eq!(other_monitor.kind(), Some(ChangeKind::FullRestart));
+        assert!(other_monitor.keeps_segments());
+
+        // ...but a native resolution follows it
+        let mut old = AppConfig::default();
+        old.recording.resolution = Resolution::Native;
+        let mut new = old.clone();
+        new.recording.monitor_index = Some(1);
+        assert!(!ConfigDiff::new(&old, &new).keeps_segments());
+    }
+}
@@ -0,0 +1,222 @@
+//! Upgrading config files written by older versions, and keeping what's valid in broken ones.
+//!
+//! A file is read as a plain TOML table first. `MIGRATIONS` brings it up to `CONFIG_VERSION`
+//! one version at a time, then it's deserialized. If some fields still don't deserialize, every
+//! field that does is kept and the rest fall back to their defaults.
+
+use toml::{Table, Value};
+
+use super::AppConfig;
+use crate::constants::CONFIG_VERSION;
+
+/// Upgrades a config table by one version.
+type Migration = fn(&mut Table);
+
+/// `MIGRATIONS[n]` upgrades version `n` to `n + 1`. Files without a `version` are version 0.
+const MIGRATIONS: &[Migration] = &[v0_to_v1];
+
+/// Version 1 only adds the `version` field itself.
+fn v0_to_v1(_: &mut Table) {}
+
+/// The result of reading a config file.
+pub struct Loaded {
+    pub config: AppConfig,
+    /// Version the file was written with. None if it isn't TOML at all.
+    pub from_version: Option<u32>,
+    /// Dotted paths of the fields that were invalid and replaced by defaults
+    pub dropped: Vec<String>,
+}
+
+impl Loaded {
+    /// Whether saving `config` would change what the file says (beyond formatting). Files from
+    /// a newer version are never rewritten, since that would drop the sections this version
+    /// doesn't know about.
+    pub fn needs_rewrite(&self) -> bool {
+        match self.from_version {
+            Some(version) if version > CONFIG_VERSION => false,
+            Some(version) => version != CONFIG_VERSION || !self.dropped.is_empty(),
+            None => true,
+        }
+    }
+}
+
+pub fn parse(content: &str, defaults: AppConfig) -> Loaded {
+    let mut table = match toml::from_str::<Table>(content) {
+        Ok(table) => table,
+        Err(e) => {
+            log::error!("Config file is not valid TOML, using defaults: {}", e);
+            return Loaded { config: defaults, from_version: None, dropped: Vec::new() };
+        }
+    };
+
+    let from_version = migrate(&mut table);
+    let (mut config, mut dropped) = parse_lenient(&table, defaults);
+    config.version = CONFIG_VERSION;
+    dropped.sort();
+    for path in &dropped {
+        log::warn!("Invalid config value `{}`, using the default", path);
+    }
+    Loaded { config, from_version: Some(from_version), dropped }
+}
+
+/// Version a config file was written with. None if it isn't TOML at all.
+pub fn file_version(content: &str) -> Option<u32> {
+    toml::from_str::<Table>(content).ok().map(|table| version_of(&table))
+}
+
+fn version_of(table: &Table) -> u32 {
+    match table.get("version") {
+        None => 0,
+        Some(value) => value.as_integer().and_then(|v| u32::try_from(v).ok()).unwrap_or(CONFIG_VERSION),
+    }
+}
+
+/// Runs the migrations from the table's version to `CONFIG_VERSION`. Returns the version the
+/// table had. Tables from a newer version are left as they are.
+fn migrate(table: &mut Table) -> u32 {
+    let version = version_of(table);
+    if version > CONFIG_VERSION {
+        log::warn!("Config file is from a newer version ({} > {}), reading what is understood", version, CONFIG_VERSION);
+        return version;
+    }
+    for (from, migration) in MIGRATIONS.iter().enumerate().skip(version as usize) {
+        log::info!("Migrating config from version {} to {}", from, from + 1);
+        migration(table);
+    }
+    table.insert("version".to_string(), Value::Integer(CONFIG_VERSION as i64));
+    version
+}
+
+/// Deserializes `table`. On failure, starts from `defaults` and copies each of the table's
+/// fields over as long as the result still deserializes, going into nested tables for the
+/// ones that don't.
+fn parse_lenient(table: &Table, defaults: AppConfig) -> (AppConfig, Vec<String>) {
+    if let Ok(config) = Value::Table(table.clone()).try_into::<AppConfig>() {
+        return (config, Vec::new());
+    }
+
+    let everything = || table.keys().cloned().collect();
+    let mut merged = match Value::try_from(&defaults) {
+        Ok(Value::Table(merged)) => merged,
+        _ => return (defaults, everything()),
+    };
+    let mut dropped = Vec::new();
+    merge_valid(&mut merged, &mut Vec::new(), table, &mut dropped);
+    match Value::Table(merged).try_into::<AppConfig>() {
+        Ok(config) => (config, dropped),
+        Err(_) => (defaults, everything()),
+    }
+}
+
+fn merge_valid(root: &mut Table, path: &mut Vec<String>, user: &Table, dropped: &mut Vec<String>) {
+    for (key, value) in user {
+        path.push(key.clone());
+        let previous = get_path(root, path).cloned();
+        set_path(root, path, Some(value.clone()));
+        if Value::Table(root.clone()).try_into::<AppConfig>().is_err() {
+            set_path(root, path, previous.clone());
+            match (value, previous) {
+                (Value::Table(inner), Some(Value::Table(_))) => merge_valid(root, path, inner, dropped),
+                _ => dropped.push(path.join(".")),
+            }
+        }
+        path.pop();
+    }
+}
+
+fn get_path<'a>(root: &'a Table, path: &[String]) -> Option<&'a Value> {
+    let (last, parents) = path.split_last()?;
+    let mut table = root;
+    for key in parents {
+        table = table.get(key)?.as_table()?;
+    }
+    table.get(last)
+}
+
+/// Sets (or with `None` removes) the value at `path`. The parent tables must exist.
+fn set_path(root: &mut Table, path: &[String], value: Option<Value>) {
+    let Some((last, parents)) = path.split_last() else {
+        return;
+    };
+    let mut table = root;
+    for key in parents {
+        match table.get_mut(key).and_then(Value::as_table_mut) {
+            Some(inner) => table = inner,
+            None => return,
+        }
+    }
+    match value {
+        Some(value) => table.insert(last.clone(), value),
+        None => table.remove(last),
+    };
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::config::Bitrate;
+    use crate::hotkeys::HotkeyAction;
+
+    #[test]
+    fn test_upgrades_unversioned_file() {
+        let loaded = parse(include_str!("fixtures/v0_baseline.toml"), AppConfig::default());
+        assert_eq!(loaded.from_version, Some(0));
+        assert!(loaded.dropped.is_empty());
+        assert!(loaded.needs_rewrite());
+
+        let config = loaded.config;
+        assert_eq!(config.version, CONFIG_VERSION);
+        assert_eq!(config.recording.path, "D:\\Clips");
+        assert_eq!(config.recording.framerate, 144);
+        assert_eq!(config.recording.bitrate, Some(Bitrate(20_000_000)));
+        assert_eq!(config.recording.audio_source.as_deref(), Some("Microphone (USB Audio)"));
+        assert_eq!(config.user.display_name.as_deref(), Some("ace"));
+        // Sections that didn't exist yet get their defaults
+        assert_eq!(config.clock, Default::default());
+        assert_eq!(config.hotkeys[&HotkeyAction::SaveReplay], "Alt+F10");
+    }
+
+    #[test]
+    fn test_keeps_valid_fields_of_broken_file() {
+        let loaded = parse(include_str!("fixtures/v1_invalid_fields.toml"), AppConfig::default());
+        assert_eq!(loaded.from_version, Some(1));
+        assert_eq!(loaded.dropped, vec!["gsi.port", "hotkeys.save_forever", "recording.framerate"]);
+
+        let config = loaded.config;
+        assert_eq!(config.recording.framerate, AppConfig::default().recording.framerate);
+        assert_eq!(config.recording.path, "D:\\Clips");
+        assert_eq!(config.recording.buffer_duration, 120);
+        assert_eq!(config.hotkeys[&HotkeyAction::SaveReplay], "Ctrl+Shift+S");
+        assert_eq!(config.hotkeys[&HotkeyAction::AddMarker], "F8");
+        assert!(config.gsi.enabled);
+        assert_eq!(config.gsi.port, crate::constants::DEFAULT_GSI_PORT);
+    }
+
+    #[test]
+    fn test_unreadable_and_newer_files() {
+        let loaded = parse("[recording\npath = ", AppConfig::default());
+        assert_eq!(loaded.from_version, None);
+        assert!(loaded.needs_rewrite());
+
+        let mut table: Table = toml::from_str("version = 7\nfuture_section = { a = 1 }").unwrap();
+        assert_eq!(migrate(&mut table), 7);
+        assert_eq!(table["version"].as_integer(), Some(7));
+
+        let newer = "version = 7\nfuture_section = { a = 1 }\n[recording]\npath = 'D:/Clips'\nframerate = 'fast'";
+        let loaded = parse(newer, AppConfig::default());
+        assert_eq!(loaded.from_version, Some(7));
+        assert_eq!(loaded.dropped, vec!["recording.framerate"]);
+        assert_eq!(loaded.config.recording.path, "D:/Clips");
+        assert!(!loaded.needs_rewrite());
+
+        let current = "version = 1\n[recording]\npath = 'D:/Clips'\nframerate = 30\nencoder = 'auto'";
+        let loaded = parse(current, AppConfig::default());
+        assert_eq!(loaded.from_version, Some(1));
+        assert!(!loaded.needs_rewrite());
+
+        assert_eq!(file_version(newer), Some(7));
+        assert_eq!(file_version(current), Some(1));
+        assert_eq!(file_version("[recording]\npath = 'D:/Clips'"), Some(0));
+        assert_eq!(file_version("[recording\npath = "), None);
+    }
+}
@@ -0,0 +1,531 @@
+use serde::{Deserialize, Serialize};
+use std::fs;
+use std::path::{Path, PathBuf};
+use tauri::AppHandle;
+use tauri::Manager;
+use cpal::traits::{DeviceTrait, HostTrait};
+use crate::ffmpeg::capture::CaptureTarget;
+use crate::hotkeys::{default_hotkeys, HotkeyMap};
+use crate::gsi::rules::{default_rules, GsiRule};
+use crate::games::detect::{default_games, GameEntry};
+
+pub mod diff;
+pub mod migrate;
+pub mod profiles;
+pub mod validate;
+pub mod values;
+
+pub use diff::{ChangeKind, ConfigDiff};
+pub use profiles::{default_profiles, RecordingProfile};
+pub use validate::FieldError;
+pub use values::{AudioBackend, Bitrate, EncoderChoice, Resolution};
+use crate::ffmpeg::encoder::VideoCodec;
+
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct AppConfig {
+    /// Schema version, see [migrate]. Files without one predate versioning (version 0).
+    #[serde(default = "default_config_version")]
+    pub version: u32,
+    pub recording: RecordingConfig,
+    /// Named overrides of `recording`, see [profiles]
+    #[serde(default = "default_profiles")]
+    pub profiles: Vec<RecordingProfile>,
+    /// Profile used when the detected game doesn't name one. None records with `recording` as is.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub active_profile: Option<String>,
+    #[serde(default)]
+    pub user: UserConfig,
+    #[serde(default)]
+    pub upload: UploadConfig,
+    #[serde(default)]
+    pub storage: StorageConfig,
+    #[serde(default = "default_hotkeys")]
+    pub hotkeys: HotkeyMap,
+    #[serde(default)]
+    pub plugin_api: PluginApiConfig,
+    #[serde(default)]
+    pub gsi: GsiConfig,
+    #[serde(default)]
+    pub game_detection: GameDetectionConfig,
+    #[serde(default)]
+    pub clock: ClockConfig,
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct RecordingConfig {
+    pub path: String,
+    #[serde(default = "default_temp_path")]
+    pub temp_path: String,
+    #[serde(default)]
+    pub resolution: Resolution,
+    pub framerate: u32,
+    /// None picks one from resolution and framerate
+    pub bitrate: Option<Bitrate>,
+    #[serde(default = "default_buffer_duration")]
+    pub buffer_duration: u32,
+    #[serde(default = "default_segment_time")]
+    pub segment_time: u32,
+    pub monitor_index: Option<u32>,
+    /// What to record. Takes precedence over `monitor_index` when set.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub capture_target: Option<CaptureTarget>,
+    pub encoder: EncoderChoice,
+    /// Codec family used when `encoder` is "auto": "h264" (default), "hevc" or "av1".
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub video_codec: Option<VideoCodec>,
+    pub audio_source: Option<String>,
+    pub system_audio_device: Option<String>,
+    /// Stable device IDs (see [crate::audio::devices]). The names above remain the fallback.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub audio_source_id: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub system_audio_device_id: Option<String>,
+    pub audio_codec: Option<String>,
+    
+    // Advanced Overrides (Hidden from default config)
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub video_preset: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub video_tune: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub video_profile: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub audio_bitrate: Option<Bitrate>,
+    #[serde(default = "default_buffer_retention_seconds")]
+    pub buffer_retention_seconds: u32,
+    #[serde(default)]
+    pub audio_backend: AudioBackend,
+}
+
+fn default_config_version() -> u32 {
+    crate::constants::CONFIG_VERSION
+}
+
+fn default_temp_path() -> String {
+    if let Some(mut path) = dirs::data_local_dir() {
+        path.push("SquadSync");
+        path.push("Buffer");
+        return path.to_string_lossy().to_string();
+    }
+    // Fallback if dirs fails (unlikely on Windows)
+    "C:\\SquadSync_Buffer".to_string()
+}
+
+fn default_buffer_duration() -> u32 {
+    60
+}
+
+fn default_segment_time() -> u32 {
+    15
+}
+
+fn default_buffer_retention_seconds() -> u32 {
+    480 // 8 minutes
+}
+
+impl Default for AppConfig {
+    fn default() -> Self {
+        // Auto-detect default microphone
+        let audio_source = cpal::default_host()
+            .default_input_device()
+            .map(|d| d.name().unwrap_or_default())
+            .filter(|n| !n.is_empty());
+
+        // Auto-detect default speaker
+        let system_audio_device = cpal::default_host()
+            .default_output_device()
+            .map(|d| d.name().unwrap_or_default())
+            .filter(|n| !n.is_empty());
+
+        Self {
+            version: default_config_version(),
+            recording: RecordingConfig {
+                path: String::new(),
+                temp_path: default_temp_path(),
+                resolution: Resolution::Fixed { width: 1920, height: 1080 },
+                framerate: 60,
+                bitrate: None,
+                buffer_duration: 60, // 1 minute default buffer
+                segment_time: 15,     // 15 second segments
+                monitor_index: None,
+                capture_target: None,
+                encoder: EncoderChoice::Auto,
+                video_codec: None,
+                audio_source,
+                system_audio_device,
+                audio_source_id: None,
+                system_audio_device_id: None,
+                audio_codec: None,
+                video_preset: None,
+                video_tune: None,
+                video_profile: None,
+                audio_bitrate: None,
+                buffer_retention_seconds: 300,
+                audio_backend: AudioBackend::Cpal,
+            },
+            profiles: default_profiles(),
+            active_profile: None,
+            user: UserConfig::default(),
+            upload: UploadConfig::default(),
+            storage: StorageConfig::default(),
+            hotkeys: default_hotkeys(),
+            plugin_api: PluginApiConfig::default(),
+            gsi: GsiConfig::default(),
+            game_detection: GameDetectionConfig::default(),
+            clock: ClockConfig::default(),
+        }
+    }
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone, Default)]
+pub struct UserConfig {
+    #[serde(default)]
+    pub display_name: Option<String>,
+    #[serde(default)]
+    pub user_id: Option<String>,
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct UploadConfig {
+    /// Upload bandwidth cap in kilobits per second. None = unlimited.
+    #[serde(default)]
+    pub bandwidth_limit_kbps: Option<u32>,
+    #[serde(default = "default_upload_max_retries")]
+    pub max_retries: u32,
+    /// Clips uploaded at the same time; further uploads wait in the queue.
+    #[serde(default = "default_upload_max_concurrent")]
+    pub max_concurrent: usize,
+}
+
+fn default_upload_max_retries() -> u32 {
+    5
+}
+
+fn default_upload_max_concurrent() -> usize {
+    crate::constants::DEFAULT_UPLOAD_MAX_CONCURRENT
+}
+
+impl Default for UploadConfig {
+    fn default() -> Self {
+        Self {
+            bandwidth_limit_kbps: None,
+            max_retries: default_upload_max_retries(),
+            max_concurrent: default_upload_max_concurrent(),
+        }
+    }
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone, Copy, Default, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum StorageBackend {
+    /// Upload to presigned URLs handed out by the signaling server.
+    #[default]
+    Presigned,
+    /// Upload straight to a self-hosted S3-compatible bucket (MinIO, R2, Garage).
+    S3,
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone, Default)]
+pub struct StorageConfig {
+    #[serde(default)]
+    pub backend: StorageBackend,
+    #[serde(default)]
+    pub s3: S3Config,
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct S3Config {
+    /// e.g. "http://localhost:9000" or "https://<account>.r2.cloudflarestorage.com"
+    #[serde(default)]
+    pub endpoint: String,
+    #[serde(default)]
+    pub bucket: String,
+    #[serde(default = "default_s3_region")]
+    pub region: String,
+    #[serde(default)]
+    pub access_key_id: String,
+    #[serde(default)]
+    pub secret_access_key: String,
+    /// `endpoint/bucket/key` instead of `bucket.endpoint/key`. MinIO and Garage need this.
+    #[serde(default = "default_true")]
+    pub path_style: bool,
+    /// Prepended to object keys, e.g. "squadsync/"
+    #[serde(default)]
+    pub prefix: String,
+}
+
+fn default_s3_region() -> String {
+    "us-east-1".to_string()
+}
+
+fn default_true() -> bool {
+    true
+}
+
+impl Default for S3Config {
+    fn default() -> Self {
+        Self {
+            endpoint: String::new(),
+            bucket: String::new(),
+            region: default_s3_region(),
+            access_key_id: String::new(),
+            secret_access_key: String::new(),
+            path_style: true,
+            prefix: String::new(),
+        }
+    }
+}
+
+/// Local HTTP/WebSocket API for auto-clip plugins (see `crate::plugins`). Off by default.
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq)]
+pub struct PluginApiConfig {
+    #[serde(default)]
+    pub enabled: bool,
+    #[serde(default = "default_plugin_api_port")]
+    pub port: u16,
+    /// Bearer token plugins must send. Generated when the API is first enabled.
+    #[serde(default)]
+    pub token: String,
+    /// Clip triggers accepted per plugin per minute
+    #[serde(default = "default_plugin_triggers_per_minute")]
+    pub triggers_per_minute: u32,
+    /// Events (markers) accepted per plugin per minute
+    #[serde(default = "default_plugin_events_per_minute")]
+    pub events_per_minute: u32,
+}
+
+fn default_plugin_api_port() -> u16 {
+    crate::constants::DEFAULT_PLUGIN_API_PORT
+}
+
+fn default_plugin_triggers_per_minute() -> u32 {
+    crate::constants::DEFAULT_PLUGIN_TRIGGERS_PER_MINUTE
+}
+
+fn default_plugin_events_per_minute() -> u32 {
+    crate::constants::DEFAULT_PLUGIN_EVENTS_PER_MINUTE
+}
+
+impl Default for PluginApiConfig {
+    fn default() -> Self {
+        Self {
+            enabled: false,
+            port: default_plugin_api_port(),
+            token: String::new(),
+            triggers_per_minute: default_plugin_triggers_per_minute(),
+            events_per_minute: default_plugin_events_per_minute(),
+        }
+    }
+}
+
+/// Receiver for games that push events over HTTP (CS2 GSI). Off by default.
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq)]
+pub struct GsiConfig {
+    #[serde(default)]
+    pub enabled: bool,
+    #[serde(default = "default_gsi_port")]
+    pub port: u16,
+    /// Must match `auth.token` in the game's cfg file. Generated when first enabled.
+    #[serde(default)]
+    pub token: String,
+    #[serde(default = "default_rules")]
+    pub rules: Vec<GsiRule>,
+}
+
+fn default_gsi_port() -> u16 {
+    crate::constants::DEFAULT_GSI_PORT
+}
+
+impl Default for GsiConfig {
+    fn default() -> Self {
+        Self {
+            enabled: false,
+            port: default_gsi_port(),
+            token: String::new(),
+            rules: default_rules(),
+        }
+    }
+}
+
+/// Auto-start the replay buffer while a listed game runs (see `crate::games`). Off by default.
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq)]
+pub struct GameDetectionConfig {
+    #[serde(default)]
+    pub enabled: bool,
+    /// Seconds the buffer keeps running after the game exits
+    #[serde(default = "default_game_grace_secs")]
+    pub grace_secs: u32,
+    #[serde(default = "default_games")]
+    pub games: Vec<GameEntry>,
+}
+
+fn default_game_grace_secs() -> u32 {
+    crate::constants::DEFAULT_GAME_GRACE_SECS
+}
+
+impl Default for GameDetectionConfig {
+    fn default() -> Self {
+        Self {
+            enabled: false,
+            grace_secs: default_game_grace_secs(),
+            games: default_games(),
+        }
+    }
+}
+
+/// NTP sync settings (see `crate::ntp`). Applied from the next sync on.
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq)]
+pub struct ClockConfig {
+    /// Host names or addresses, optionally with a port. Several servers let a wrong one be outvoted.
+    #[serde(default = "default_ntp_servers")]
+    pub ntp_servers: Vec<String>,
+    #[serde(default = "default_ntp_samples_per_server")]
+    pub samples_per_server: u32,
+    #[serde(default = "default_ntp_sync_interval_secs")]
+    pub sync_interval_secs: u32,
+}
+
+fn default_ntp_servers() -> Vec<String> {
+    crate::constants::DEFAULT_NTP_SERVERS.iter().map(|s| s.to_string()).collect()
+}
+
+fn default_ntp_samples_per_server() -> u32 {
+    crate::constants::DEFAULT_NTP_SAMPLES_PER_SERVER
+}
+
+fn default_ntp_sync_interval_secs() -> u32 {
+    crate::constants::DEFAULT_NTP_SYNC_INTERVAL_SECS
+}
+
+impl Default for ClockConfig {
+    fn default() -> Self {
+        Self {
+            ntp_servers: default_ntp_servers(),
+            samples_per_server: default_ntp_samples_per_server(),
+            sync_interval_secs: default_ntp_sync_interval_secs(),
+        }
+    }
+}
+
+impl AppConfig {
+    /// Loads the config file, migrating it from older versions. Invalid fields fall back to
+    /// their defaults; the original file is backed up before it's rewritten. Files written by a
+    /// newer version are read but left as they are (until a save, see `save_to_path`).
+    pub fn load(app: &AppHandle) -> Self {
+        let Some(path) = get_config_path(app) else {
+            return Self::default();
+        };
+
+        if !path.exists() {
+            // Save the default config so the user has a file to edit
+            let default_config = Self::default();
+            if let Err(e) = default_config.save_to_path(&path) {
+                log::error!("Failed to write default config: {}", e);
+            }
+            return default_config;
+        }
+
+        let content = match fs::read_to_string(&path) {
+            Ok(content) => content,
+            Err(e) => {
+                // Not overwritten: the file may be fine once it can be read again
+                log::error!("Failed to read config file: {}", e);
+                return Self::default();
+            }
+        };
+
+        let loaded = migrate::parse(&content, Self::default());
+        if loaded.needs_rewrite() {
+            match backup(&path) {
+                Ok(backup_path) => {
+                    log::info!("Backed up config file to {:?}", backup_path);
+                    if let Err(e) = loaded.config.save_to_path(&path) {
+                        log::error!("Failed to save migrated config: {}", e);
+                    }
+                }
+                Err(e) => log::error!("Not rewriting the config file: {}", e),
+            }
+        }
+        loaded.config
+    }
+
+    pub fn save(&self, app: &AppHandle) -> Result<(), String> {
+        let config_path = get_config_path(app).ok_or("Could not resolve config path")?;
+        self.save_to_path(&config_path)
+    }
+
+    /// Writes the config to `path`. A file written by a newer version is backed up first, as
+    /// this version drops the sections it doesn't know.
+    fn save_to_path(&self, path: &PathBuf) -> Result<(), String> {
+        let content = toml::to_string_pretty(self).map_err(|e| e.to_string())?;
+
+        let on_disk = fs::read_to_string(path).ok().and_then(|c| migrate::file_version(&c));
+        if let Some(version) = on_disk.filter(|v| *v > crate::constants::CONFIG_VERSION) {
+            let backup_path = backup(path)?;
+            log::warn!("Config file is from a newer version ({}), backed it up to {:?} before saving", version, backup_path);
+        }
+        
+        if let Some(parent) = path.parent() {
+            fs::create_dir_all(parent).map_err(|e| e.to_string())?;
+        }
+        
+        fs::write(path, content).map_err(|e| e.to_string())?;
+        Ok(())
+    }
+}
+
+/// Copies the config file next to itself as `config.toml.<timestamp>.bak`.
+fn backup(path: &Path) -> Result<PathBuf, String> {
+    let file_name = path.file_name().and_then(|n| n.to_str()).unwrap_or("config.toml");
+    let backup_path = path.with_file_name(format!("{}.{}.bak", file_name, chrono::Local::now().format("%Y%m%d-%H%M%S")));
+    fs::copy(path, &backup_path).map_err(|e| format!("Failed to back up config file: {}", e))?;
+    Ok(backup_path)
+}
+
+fn get_config_path(app: &AppHandle) -> Option<PathBuf> {
+    app.path().app_config_dir().ok().map(|p| p.join("config.toml"))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_default_config() {
+        let config = AppConfig::default();
+        assert_eq!(config.recording.framerate, 60);
+        assert_eq!(config.recording.bitrate, None);
+        assert_eq!(config.recording.encoder, EncoderChoice::Auto);
+    }
+
+    #[test]
+    fn test_serialization() {
+        let config = AppConfig::default();
+        let toml = toml::to_string(&config).unwrap();
+        let deserialized: AppConfig = toml::from_str(&toml).unwrap();
+        
+        assert_eq!(config.recording.framerate, deserialized.recording.framerate);
+        assert_eq!(config.recording.bitrate, deserialized.recording.bitrate);
+    }
+
+    #[test]
+    fn test_save_backs_up_newer_file() {
+        let dir = std::env::temp_dir().join(format!("squadsync_config_{}", std::process::id()));
+        let _ = fs::remove_dir_all(&dir);
+        fs::create_dir_all(&dir).unwrap();
+        let path = dir.join("config.toml");
+        let newer = "version = 99\nfuture_section = { a = 1 }\n";
+        fs::write(&path, newer).unwrap();
+
+        AppConfig::default().save_to_path(&path).unwrap();
+        let backups: Vec<PathBuf> = fs::read_dir(&dir).unwrap().flatten().map(|e| e.path()).filter(|p| p.extension().is_some_and(|e| e == "bak")).collect();
+        assert_eq!(backups.len(), 1);
+        assert_eq!(fs::read_to_string(&backups[0]).unwrap(), newer);
+        assert_eq!(migrate::file_version(&fs::read_to_string(&path).unwrap()), Some(crate::constants::CONFIG_VERSION));
+
+        // Our own file isn't backed up again
+        AppConfig::default().save_to_path(&path).unwrap();
+        assert_eq!(fs::read_dir(&dir).unwrap().count(), 2);
+        let _ = fs::remove_dir_all(&dir);
+    }
+}
@@ -0,0 +1,238 @@
+//! Recording profiles: named sets of video settings applied over `[recording]`.
+//!
+//! The profile in use is the detected game's (`[[game_detection.games]]` `profile`), else
+//! `active_profile`, else none. A profile only overrides the fields it sets, so a shared
+//! profile file carries just the settings it's about and keeps everyone's devices and paths.
+
+use serde::{Deserialize, Serialize};
+
+use super::{AppConfig, Bitrate, EncoderChoice, RecordingConfig, Resolution};
+use crate::ffmpeg::encoder::VideoCodec;
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct RecordingProfile {
+    pub name: String,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub resolution: Option<Resolution>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub framerate: Option<u32>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub bitrate: Option<Bitrate>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub encoder: Option<EncoderChoice>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub video_codec: Option<VideoCodec>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub video_preset: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub video_tune: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub video_profile: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub audio_bitrate: Option<Bitrate>,
+}
+
+impl RecordingProfile {
+    pub fn new(name: &str) -> Self {
+        Self {
+            name: name.to_string(),
+            resolution: None,
+            framerate: None,
+            bitrate: None,
+            encoder: None,
+            video_codec: None,
+            video_preset: None,
+            video_tune: None,
+            video_profile: None,
+            audio_bitrate: None,
+        }
+    }
+
+    /// `recording` with this profile's fields in place of its own.
+    pub fn apply(&self, recording: &RecordingConfig) -> RecordingConfig {
+        let mut applied = recording.clone();
+        if let Some(resolution) = self.resolution {
+            applied.resolution = resolution;
+        }
+        if let Some(framerate) = self.framerate {
+            applied.framerate = framerate;
+        }
+        if self.bitrate.is_some() {
+            applied.bitrate = self.bitrate;
+        }
+        if let Some(encoder) = self.encoder {
+            applied.encoder = encoder;
+        }
+        if self.video_codec.is_some() {
+            applied.video_codec = self.video_codec;
+        }
+        if self.video_preset.is_some() {
+            applied.video_preset = self.video_preset.clone();
+        }
+        if self.video_tune.is_some() {
+            applied.video_tune = self.video_tune.clone();
+        }
+        if self.video_profile.is_some() {
+            applied.video_profile = self.video_profile.clone();
+        }
+        if self.audio_bitrate.is_some() {
+            applied.audio_bitrate = self.audio_bitrate;
+        }
+        applied
+    }
+
+    /// Names of the `[recording]` fields this profile overrides.
+    pub fn fields(&self) -> Vec<&'static str> {
+        [
+            ("resolution", self.resolution.is_some()),
+            ("framerate", self.framerate.is_some()),
+            ("bitrate", self.bitrate.is_some()),
+            ("encoder", self.encoder.is_some()),
+            ("video_codec", self.video_codec.is_some()),
+            ("video_preset", self.video_preset.is_some()),
+            ("video_tune", self.video_tune.is_some()),
+            ("video_profile", self.video_profile.is_some()),
+            ("audio_bitrate", self.audio_bitrate.is_some()),
+        ]
+        .into_iter()
+        .filter_map(|(field, set)| set.then_some(field))
+        .collect()
+    }
+
+    /// Reads a profile shared as a TOML file.
+    pub fn from_toml(content: &str) -> Result<Self, String> {
+        let profile: Self = toml::from_str(content).map_err(|e| format!("Invalid profile file: {}", e))?;
+        if profile.name.trim().is_empty() {
+            return Err("Invalid profile file: the name is empty".to_string());
+        }
+        Ok(profile)
+    }
+
+    pub fn to_toml(&self) -> Result<String, String> {
+        toml::to_string_pretty(self).map_err(|e| format!("Failed to serialize profile: {}", e))
+    }
+}
+
+pub fn default_profiles() -> Vec<RecordingProfile> {
+    vec![
+        RecordingProfile {
+            resolution: Some(Resolution::Fixed { width: 1920, height: 1080 }),
+            framerate: Some(144),
+            bitrate: Some(Bitrate(30_000_000)),
+            video_preset: Some("speed".to_string()),
+            ..RecordingProfile::new("Competitive 1080p144")
+        },
+        RecordingProfile {
+            resolution: Some(Resolution::Fixed { width: 1280, height: 720 }),
+            framerate: Some(30),
+            bitrate: Some(Bitrate(4_000_000)),
+            video_preset: Some("speed".to_string()),
+            ..RecordingProfile::new("Low-end 720p30")
+        },
+        // Leaves headroom for a streaming encoder running next to it
+        RecordingProfile {
+            resolution: Some(Resolution::Fixed { width: 1920, height: 1080 }),
+            framerate: Some(60),
+            bitrate: Some(Bitrate(10_000_000)),
+            video_preset: Some("balanced".to_string()),
+            ..RecordingProfile::new("Streaming-safe")
+        },
+    ]
+}
+
+impl AppConfig {
+    pub fn profile(&self, name: &str) -> Option<&RecordingProfile> {
+        self.profiles.iter().find(|p| p.name == name)
+    }
+
+    /// The profile recording uses while `game` is detected.
+    pub fn profile_for(&self, game: Option<&str>) -> Option<&RecordingProfile> {
+        let game_profile = game
+            .and_then(|game| self.game_detection.games.iter().find(|g| g.name == game))
+            .and_then(|g| g.profile.as_deref());
+        game_profile.or(self.active_profile.as_deref()).and_then(|name| self.profile(name))
+    }
+
+    /// This config with `[recording]` as it's recorded with while `game` is detected.
+    pub fn effective(&self, game: Option<&str>) -> AppConfig {
+        let mut effective = self.clone();
+        if let Some(profile) = self.profile_for(game) {
+            effective.recording = profile.apply(&self.recording);
+        }
+        effective
+    }
+
+    /// Adds `profile`, replacing any profile with the same name.
+    pub fn upsert_profile(&mut self, profile: RecordingProfile) {
+        match self.profiles.iter_mut().find(|p| p.name == profile.name) {
+            Some(existing) => *existing = profile,
+            None => self.profiles.push(profile),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::config::{ChangeKind, ConfigDiff};
+
+    #[test]
+    fn test_profile_selection_and_overrides() {
+        let mut config = AppConfig::default();
+        config.recording.bitrate = Some(Bitrate(15_000_000));
+        config.recording.video_preset = Some("quality".into());
+        assert_eq!(config.effective(None).recording.framerate, config.recording.framerate);
+
+        config.active_profile = Some("Low-end 720p30".into());
+        config.game_detection.games[0].profile = Some("Competitive 1080p144".into());
+        let game = config.game_detection.games[0].name.clone();
+
+        let effective = config.effective(None).recording;
+        assert_eq!((effective.resolution, effective.framerate), (Resolution::Fixed { width: 1280, height: 720 }, 30));
+        // Fields the profile doesn't set are kept
+        assert_eq!(effective.path, config.recording.path);
+        assert_eq!(effective.encoder, config.recording.encoder);
+
+        assert_eq!(config.effective(Some(&game)).recording.framerate, 144);
+        assert_eq!(config.effective(Some("Some other game")).recording.framerate, 30);
+
+        // A profile that doesn't exist (any more) leaves `[recording]` as it is
+        config.active_profile = Some("Deleted".into());
+        assert_eq!(config.effective(None).recording.bitrate, Some(Bitrate(15_000_000)));
+    }
+
+    #[test]
+    fn test_profile_switch_drops_segments_on_framerate_change() {
+        let mut config = AppConfig { active_profile: Some("Streaming-safe".into()), ..Default::default() };
+        config.game_detection.games[0].profile = Some("Competitive 1080p144".into());
+        let game = config.game_detection.games[0].name.clone();
+
+        // Same resolution and codec, 60 vs 144 fps: the buffer can't be stream-copied across it
+        let (before, after) = (config.effective(None), config.effective(Some(&game)));
+        assert_eq!(before.recording.resolution, after.recording.resolution);
+        let diff = ConfigDiff::new(&before, &after);
+        assert_eq!(diff.kind(), Some(ChangeKind::FullRestart));
+        assert!(!diff.keeps_segments());
+        assert!(!ConfigDiff::new(&after, &before).keeps_segments());
+    }
+
+    #[test]
+    fn test_toml_round_trip() {
+        let shared = include_str!("fixtures/profile_squad.toml");
+        let profile = RecordingProfile::from_toml(shared).unwrap();
+        assert_eq!(profile.name, "Squad scrims");
+        assert_eq!(profile.fields(), vec!["resolution", "framerate", "bitrate", "encoder"]);
+        assert_eq!(profile.encoder, Some(EncoderChoice::Auto));
+        assert_eq!(RecordingProfile::from_toml(&profile.to_toml().unwrap()), Ok(profile.clone()));
+
+        let mut config = AppConfig::default();
+        let count = config.profiles.len();
+        config.upsert_profile(profile.clone());
+        config.upsert_profile(RecordingProfile { framerate: Some(144), ..profile });
+        assert_eq!(config.profiles.len(), count + 1);
+        assert_eq!(config.profile("Squad scrims").and_then(|p| p.framerate), Some(144));
+
+        assert!(RecordingProfile::from_toml("name = \"\"\nframerate = 60").is_err());
+        assert!(RecordingProfile::from_toml("name = \"Broken\"\nbitrate = \"fast\"").is_err());
+    }
+}
@@ -0,0 +1,197 @@
+//! Checks a config for values that parse but can't be recorded with, so the settings UI can show
+//! them next to the field instead of the recorder quietly using something else.
+
+use serde::Serialize;
+
+use super::{AppConfig, Bitrate, RecordingConfig, Resolution};
+use crate::constants::{
+    CONFIG_MAX_AUDIO_BITRATE, CONFIG_MAX_DIMENSION, CONFIG_MAX_FRAMERATE, CONFIG_MAX_VIDEO_BITRATE,
+    CONFIG_MIN_AUDIO_BITRATE, CONFIG_MIN_VIDEO_BITRATE,
+};
+use crate::ffmpeg::capture::CaptureTarget;
+
+/// Presets `FfmpegCommandBuilder` knows how to map onto every encoder.
+const KNOWN_PRESETS: &[&str] = &[
+    "p1", "p2", "p3", "p4", "p5", "p6", "p7", "speed", "balanced", "quality",
+    "ultrafast", "superfast", "veryfast", "faster", "fast", "medium", "slow", "slower", "veryslow",
+];
+
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct FieldError {
+    /// Dotted path of the field, e.g. "recording.framerate"
+    pub field: String,
+    pub message: String,
+}
+
+impl FieldError {
+    fn new(field: &str, message: impl Into<String>) -> Self {
+        Self { field: field.to_string(), message: message.into() }
+    }
+}
+
+fn range_message(min: u32, max: u32) -> String {
+    format!("Must be between {} and {}", Bitrate(min), Bitrate(max))
+}
+
+impl AppConfig {
+    /// Every problem found, by field. Run before saving; `update_config` refuses invalid configs.
+    pub fn validate(&self) -> Result<(), Vec<FieldError>> {
+        let mut errors = Vec::new();
+        let recording = &self.recording;
+        validate_video(recording, &mut errors);
+
+        if recording.buffer_duration == 0 {
+            errors.push(FieldError::new("recording.buffer_duration", "Must be at least 1 second"));
+        }
+        if recording.segment_time == 0 || recording.segment_time > recording.buffer_duration.max(1) {
+            errors.push(FieldError::new("recording.segment_time", "Must be between 1 second and the buffer duration"));
+        }
+        if recording.buffer_retention_seconds < recording.buffer_duration {
+            errors.push(FieldError::new("recording.buffer_retention_seconds", "Must be at least the buffer duration"));
+        }
+
+        let mut names = std::collections::HashSet::new();
+        for (i, profile) in self.profiles.iter().enumerate() {
+            if profile.name.trim().is_empty() {
+                errors.push(FieldError::new(&format!("profiles.{}.name", i), "Must not be empty"));
+            } else if !names.insert(profile.name.as_str()) {
+                errors.push(FieldError::new(&format!("profiles.{}.name", i), format!("Another profile is already called '{}'", profile.name)));
+            }
+            // Only what the profile sets; the rest is `[recording]`'s and reported there
+            let mut profile_errors = Vec::new();
+            validate_video(&profile.apply(recording), &mut profile_errors);
+            let fields = profile.fields();
+            for error in profile_errors {
+                if let Some(field) = error.field.strip_prefix("recording.").filter(|f| fields.contains(f)) {
+                    errors.push(FieldError::new(&format!("profiles.{}.{}", i, field), error.message));
+                }
+            }
+        }
+        if let Some(name) = self.active_profile.as_deref().filter(|name| self.profile(name).is_none()) {
+            errors.push(FieldError::new("active_profile", format!("No profile is called '{}'", name)));
+        }
+        for (i, game) in self.game_detection.games.iter().enumerate() {
+            if let Some(name) = game.profile.as_deref().filter(|name| self.profile(name).is_none()) {
+                errors.push(FieldError::new(&format!("game_detection.games.{}.profile", i), format!("No profile is called '{}'", name)));
+            }
+        }
+
+        if self.upload.max_concurrent == 0 {
+            errors.push(FieldError::new("upload.max_concurrent", "Must be at least 1"));
+        }
+        if self.clock.ntp_servers.iter().all(|s| s.trim().is_empty()) {
+            errors.push(FieldError::new("clock.ntp_servers", "At least one server is needed"));
+        }
+        if self.clock.samples_per_server == 0 {
+            errors.push(FieldError::new("clock.samples_per_server", "Must be at least 1"));
+        }
+
+        let (_, problems) = crate::hotkeys::resolve(&self.hotkeys);
+        for problem in problems {
+            let action = serde_json::to_value(problem.action).ok().and_then(|v| v.as_str().map(String::from)).unwrap_or_default();
+            errors.push(FieldError::new(&format!("hotkeys.{}", action), problem.error.unwrap_or_default()));
+        }
+
+        if self.plugin_api.enabled && self.plugin_api.port == 0 {
+            errors.push(FieldError::new("plugin_api.port", "Must not be 0"));
+        }
+        if self.gsi.enabled && self.gsi.port == 0 {
+            errors.push(FieldError::new("gsi.port", "Must not be 0"));
+        }
+        if self.plugin_api.enabled && self.gsi.enabled && self.plugin_api.port == self.gsi.port {
+            errors.push(FieldError::new("gsi.port", "Must differ from the plugin API port"));
+        }
+
+        if errors.is_empty() {
+            Ok(())
+        } else {
+            Err(errors)
+        }
+    }
+}
+
+/// The video format fields, which profiles can override.
+fn validate_video(recording: &RecordingConfig, errors: &mut Vec<FieldError>) {
+    if recording.framerate == 0 || recording.framerate > CONFIG_MAX_FRAMERATE {
+        errors.push(FieldError::new("recording.framerate", format!("Must be between 1 and {}", CONFIG_MAX_FRAMERATE)));
+    }
+    if let Resolution::Fixed { width, height } = recording.resolution {
+        if width > CONFIG_MAX_DIMENSION || height > CONFIG_MAX_DIMENSION {
+            errors.push(FieldError::new("recording.resolution", format!("Width and height can be at most {}", CONFIG_MAX_DIMENSION)));
+        } else if width % 2 != 0 || height % 2 != 0 {
+            errors.push(FieldError::new("recording.resolution", "Width and height must be even"));
+        }
+    }
+    if let Some(bitrate) = recording.bitrate {
+        if bitrate.bits_per_sec() < CONFIG_MIN_VIDEO_BITRATE || bitrate.bits_per_sec() > CONFIG_MAX_VIDEO_BITRATE {
+            errors.push(FieldError::new("recording.bitrate", range_message(CONFIG_MIN_VIDEO_BITRATE, CONFIG_MAX_VIDEO_BITRATE)));
+        }
+    }
+    if let Some(bitrate) = recording.audio_bitrate {
+        if bitrate.bits_per_sec() < CONFIG_MIN_AUDIO_BITRATE || bitrate.bits_per_sec() > CONFIG_MAX_AUDIO_BITRATE {
+            errors.push(FieldError::new("recording.audio_bitrate", range_message(CONFIG_MIN_AUDIO_BITRATE, CONFIG_MAX_AUDIO_BITRATE)));
+        }
+    }
+    if let Some(preset) = &recording.video_preset {
+        if !KNOWN_PRESETS.contains(&preset.to_lowercase().as_str()) && preset.parse::<u8>().map_or(true, |n| n > 13) {
+            errors.push(FieldError::new("recording.video_preset", format!("Unknown preset '{}'", preset)));
+        }
+    }
+    if let Some(CaptureTarget::Region { width, height, .. }) = &recording.capture_target {
+        if *width < 2 || *height < 2 {
+            errors.push(FieldError::new("recording.capture_target", "Region must be at least 2x2 pixels"));
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::hotkeys::HotkeyAction;
+
+    #[test]
+    fn test_default_config_is_valid() {
+        assert_eq!(AppConfig::default().validate(), Ok(()));
+    }
+
+    #[test]
+    fn test_reports_each_invalid_field() {
+        let mut config = AppConfig::default();
+        config.recording.framerate = 1000;
+        config.recording.resolution = Resolution::Fixed { width: 1279, height: 720 };
+        config.recording.bitrate = Some(Bitrate(1_000_000_000));
+        config.recording.video_preset = Some("ludicrous".into());
+        config.recording.segment_time = config.recording.buffer_duration + 1;
+        config.hotkeys.insert(HotkeyAction::AddMarker, "Alt+F10".into());
+
+        let fields: Vec<String> = config.validate().unwrap_err().into_iter().map(|e| e.field).collect();
+        assert_eq!(fields, vec![
+            "recording.framerate",
+            "recording.resolution",
+            "recording.bitrate",
+            "recording.video_preset",
+            "recording.segment_time",
+            "hotkeys.add_marker",
+        ]);
+    }
+
+    #[test]
+    fn test_reports_invalid_profiles() {
+        let mut config = AppConfig::default();
+        // Not set by any profile, so only reported once
+        config.recording.audio_bitrate = Some(Bitrate(8_000));
+        config.profiles[0].framerate = Some(0);
+        config.profiles[1].name = config.profiles[0].name.clone();
+        config.active_profile = Some("Missing".into());
+        config.game_detection.games[2].profile = Some("Missing".into());
+
+        let fields: Vec<String> = config.validate().unwrap_err().into_iter().map(|e| e.field).collect();
+        assert_eq!(fields, vec![
+            "recording.audio_bitrate",
+            "profiles.0.framerate",
+            "profiles.1.name",
+            "active_profile",
+            "game_detection.games.2.profile",
+        ]);
+    }
+}
@@ -0,0 +1,205 @@
+//! Typed recording settings. Each one is stored in the config file as the same string the old
+//! free-form field took ("1920x1080", "8M", "hevc_nvenc", ...), so existing files keep working.
+
+use serde::{Deserialize, Serialize};
+use std::fmt;
+use std::str::FromStr;
+
+use crate::ffmpeg::encoder::{VideoCodec, VideoEncoder};
+
+/// Output resolution. `Native` records at the capture size, `Fixed` scales to it.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize)]
+#[serde(try_from = "String", into = "String")]
+pub enum Resolution {
+    #[default]
+    Native,
+    Fixed { width: u32, height: u32 },
+}
+
+impl Resolution {
+    /// The size to encode at, given the capture size, and whether that needs the scaler.
+    pub fn target(&self, width: u32, height: u32) -> (u32, u32, bool) {
+        match *self {
+            Resolution::Fixed { width: w, height: h } if (w, h) != (width, height) => (w, h, true),
+            _ => (width, height, false),
+        }
+    }
+}
+
+impl FromStr for Resolution {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let s = s.trim();
+        if s.eq_ignore_ascii_case("native") {
+            return Ok(Resolution::Native);
+        }
+        let (w, h) = s.split_once(['x', 'X']).ok_or_else(|| format!("Invalid resolution '{}', expected WIDTHxHEIGHT or native", s))?;
+        match (w.trim().parse::<u32>(), h.trim().parse::<u32>()) {
+            (Ok(width), Ok(height)) if width > 0 && height > 0 => Ok(Resolution::Fixed { width, height }),
+            _ => Err(format!("Invalid resolution '{}', expected WIDTHxHEIGHT or native", s)),
+        }
+    }
+}
+
+impl fmt::Display for Resolution {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Resolution::Native => write!(f, "native"),
+            Resolution::Fixed { width, height } => write!(f, "{}x{}", width, height),
+        }
+    }
+}
+
+/// A bitrate in bits per second. Written as "8M", "5000k" or plain bits.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Serialize, Deserialize)]
+#[serde(try_from = "String", into = "String")]
+pub struct Bitrate(pub u32);
+
+impl Bitrate {
+    pub fn bits_per_sec(&self) -> u32 {
+        self.0
+    }
+
+    pub fn kbps(&self) -> u32 {
+        self.0 / 1000
+    }
+}
+
+impl FromStr for Bitrate {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let s = s.trim();
+        let (number, unit) = match s.char_indices().last() {
+            Some((i, 'M' | 'm')) => (&s[..i], 1_000_000.0),
+            Some((i, 'K' | 'k')) => (&s[..i], 1_000.0),
+            _ => (s, 1.0),
+        };
+        match number.trim().parse::<f64>() {
+            Ok(value) if value > 0.0 && value * unit <= u32::MAX as f64 => Ok(Bitrate((value * unit).round() as u32)),
+            _ => Err(format!("Invalid bitrate '{}', expected e.g. 8M or 5000k", s)),
+        }
+    }
+}
+
+impl fmt::Display for Bitrate {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        if self.0 % 1_000_000 == 0 {
+            write!(f, "{}M", self.0 / 1_000_000)
+        } else if self.0 % 1_000 == 0 {
+            write!(f, "{}k", self.0 / 1_000)
+        } else {
+            write!(f, "{}", self.0)
+        }
+    }
+}
+
+/// `Auto` picks the best working encoder for `video_codec`; `Fixed` is an FFmpeg encoder name.
+#[derive(Debug, Clone, Copy, PartialEq, Default, Serialize, Deserialize)]
+#[serde(try_from = "String", into = "String")]
+pub enum EncoderChoice {
+    #[default]
+    Auto,
+    Fixed(VideoEncoder, VideoCodec),
+}
+
+impl FromStr for EncoderChoice {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let s = s.trim();
+        if s.eq_ignore_ascii_case("auto") {
+            return Ok(EncoderChoice::Auto);
+        }
+        VideoEncoder::from_ffmpeg_codec(s)
+            .map(|(encoder, codec)| EncoderChoice::Fixed(encoder, codec))
+            .ok_or_else(|| format!("Unknown encoder '{}', expected auto or an FFmpeg encoder such as h264_nvenc", s))
+    }
+}
+
+impl fmt::Display for EncoderChoice {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            EncoderChoice::Auto => write!(f, "auto"),
+            EncoderChoice::Fixed(encoder, codec) => write!(f, "{}", encoder.ffmpeg_codec(*codec)),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum AudioBackend {
+    #[default]
+    Cpal,
+    Dshow,
+}
+
+/// Serde glue: every type above is stored as its `Display` form and parsed with `FromStr`.
+macro_rules! string_serde {
+    ($($ty:ty),*) => {$(
+        impl TryFrom<String> for $ty {
+            type Error = String;
+            fn try_from(value: String) -> Result<Self, Self::Error> {
+                value.parse()
+            }
+        }
+
+        impl From<$ty> for String {
+            fn from(value: $ty) -> Self {
+                value.to_string()
+            }
+        }
+    )*};
+}
+
+string_serde!(Resolution, Bitrate, EncoderChoice);
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_and_display() {
+        assert_eq!("native".parse(), Ok(Resolution::Native));
+        assert_eq!("1280x720".parse(), Ok(Resolution::Fixed { width: 1280, height: 720 }));
+        assert!("1080p".parse::<Resolution>().is_err());
+        assert!("0x720".parse::<Resolution>().is_err());
+        assert_eq!(Resolution::Fixed { width: 2560, height: 1440 }.to_string(), "2560x1440");
+
+        assert_eq!("6M".parse(), Ok(Bitrate(6_000_000)));
+        assert_eq!("5000k".parse(), Ok(Bitrate(5_000_000)));
+        assert_eq!("2.5M".parse(), Ok(Bitrate(2_500_000)));
+        assert_eq!("8000".parse(), Ok(Bitrate(8000)));
+        assert!("invalid".parse::<Bitrate>().is_err());
+        assert!("0M".parse::<Bitrate>().is_err());
+        assert_eq!(Bitrate(2_500_000).to_string(), "2500k");
+        assert_eq!(Bitrate(20_000_000).to_string(), "20M");
+
+        assert_eq!("auto".parse(), Ok(EncoderChoice::Auto));
+        assert_eq!("hevc_nvenc".parse(), Ok(EncoderChoice::Fixed(VideoEncoder::Nvenc, VideoCodec::Hevc)));
+        assert!("h264_magic".parse::<EncoderChoice>().is_err());
+        assert_eq!(EncoderChoice::Fixed(VideoEncoder::Software, VideoCodec::Av1).to_string(), "libsvtav1");
+    }
+
+    #[test]
+    fn test_serde_round_trip() {
+        #[derive(Debug, PartialEq, Serialize, Deserialize)]
+        struct Values {
+            resolution: Resolution,
+            bitrate: Option<Bitrate>,
+            encoder: EncoderChoice,
+            audio_backend: AudioBackend,
+        }
+        let toml_str = "resolution = \"1920x1080\"\nbitrate = \"12M\"\nencoder = \"h264_amf\"\naudio_backend = \"dshow\"\n";
+        let values: Values = toml::from_str(toml_str).unwrap();
+        assert_eq!(values, Values {
+            resolution: Resolution::Fixed { width: 1920, height: 1080 },
+            bitrate: Some(Bitrate(12_000_000)),
+            encoder: EncoderChoice::Fixed(VideoEncoder::Amf, VideoCodec::H264),
+            audio_backend: AudioBackend::Dshow,
+        });
+        assert_eq!(toml::to_string(&values).unwrap(), toml_str);
+        assert!(toml::from_str::<Values>("resolution = \"big\"\nencoder = \"auto\"\naudio_backend = \"cpal\"").is_err());
+    }
+}
@@ -10,8 +10,12 @@ pub const DEFAULT_MIC_CHANNELS: u16 = 1;
 pub const DEFAULT_AUDIO_BITRATE: &str = "192k";
 pub const DEFAULT_AUDIO_CODEC: &str = "aac";
 pub const AUDIO_LATENCY_THRESHOLD_MS: u64 = 20;
-pub const AUDIO_SILENCE_TIMEOUT_MS: u64 = 20;
+pub const AUDIO_SILENCE_TIMEOUT_MS: u64 = 20; // Pipe task tick: how often the audio clock is pulled without new data
+pub const AUDIO_CLOCK_LATENCY_MS: u64 = 40; // Jitter absorbed by the audio clock before output
+pub const AUDIO_GAP_THRESHOLD_MS: u64 = 100; // No input for this long = true gap, pad silence
+pub const AUDIO_MAX_BUFFER_MS: u64 = 500; // Audio clock queue limit, oldest frames dropped beyond this
 pub const BYTES_PER_SAMPLE: u32 = 4; // f32 = 4 bytes
+pub const AUDIO_DEVICE_POLL_MS: u64 = 1000; // Device supervisor: stream error / device list check interval
 
 // Video Defaults
 pub const DEFAULT_VIDEO_CODEC: &str = "libx264";
@@ -41,6 +45,7 @@ pub const PRESET_ULTRAFAST: &str = "ultrafast";
 pub const TUNE_ULL: &str = "ull";
 pub const TUNE_ZEROLATENCY: &str = "zerolatency";
 pub const PROFILE_HIGH: &str = "high";
+pub const PROFILE_MAIN: &str = "main";
 
 // System / Errors
 pub const ERROR_NO_DATA: i32 = 232; // Windows Error 232: The pipe is being closed.
@@ -53,6 +58,7 @@ pub const OUTPUT_FORMAT_SEGMENT: &str = "segment";
 pub const OUTPUT_FORMAT_MP4: &str = "mp4";
 pub const OUTPUT_FORMAT_LAVFI: &str = "lavfi";
 pub const OUTPUT_FORMAT_DSHOW: &str = "dshow";
+pub const OUTPUT_FORMAT_GDIGRAB: &str = "gdigrab";
 pub const OUTPUT_FORMAT_F32LE: &str = "f32le";
 
 // FFmpeg Analysis
@@ -71,9 +77,84 @@ pub const DEFAULT_HEIGHT: u32 = 1080;
 // Replay Logic
 pub const REPLAY_WAIT_RETRIES: u32 = 15;
 pub const REPLAY_WAIT_DELAY_MS: u64 = 1000;
-pub const REPLAY_AUDIO_SYNC_RETRIES: u32 = 5;
-pub const REPLAY_AUDIO_SYNC_THRESHOLD_SEC: f64 = 0.5;
+pub const REPLAY_AUDIO_SYNC_THRESHOLD_SEC: f64 = 0.5; // A/V skew above this is logged as a warning
 pub const REPLAY_SEGMENT_AGE_THRESHOLD_SEC: u64 = 5;
 pub const REPLAY_COPY_RETRIES: u32 = 20;
 pub const REPLAY_COPY_DELAY_MS: u64 = 50;
 pub const REPLAY_FLUSH_WAIT_MS: u64 = 500;
+
+// Uploads
+pub const UPLOAD_CHUNK_SIZE: usize = 64 * 1024; // Read/throttle granularity of upload bodies
+pub const UPLOAD_BASE_BACKOFF_MS: u64 = 500;
+pub const UPLOAD_MAX_BACKOFF_MS: u64 = 30_000;
+pub const UPLOAD_CONNECT_TIMEOUT_SECS: u64 = 10;
+pub const UPLOAD_MIN_RATE_BYTES: u64 = 64 * 1024; // Slowest expected rate, sizes request timeouts
+pub const UPLOAD_PROGRESS_INTERVAL_MS: u64 = 250;
+pub const UPLOAD_QUEUE_FILE: &str = "uploads.json";
+pub const UPLOAD_QUEUE_MAX_ATTEMPTS: u32 = 3; // Queue-level attempts, each with its own request retries
+pub const UPLOAD_QUEUE_RETRY_MS: u64 = 30_000;
+pub const UPLOAD_QUEUE_RETENTION_MS: u64 = 24 * 60 * 60 * 1000; // Finished jobs stay listed this long
+pub const UPLOAD_URL_EXPIRY_MARGIN_MS: u64 = 60_000; // Don't start an upload on a URL about to expire
+pub const DEFAULT_UPLOAD_MAX_CONCURRENT: usize = 2;
+pub const UPLOAD_S3_URL_EXPIRY_SECS: u64 = 3600; // Locally presigned URLs for the direct S3 backend
+
+// Markers
+pub const MARKERS_FILE: &str = "markers.json"; // Kept in the segment buffer directory
+pub const DEFAULT_MARKER_LABEL: &str = "Marker";
+
+// Plugin API
+pub const DEFAULT_PLUGIN_API_PORT: u16 = 47_820;
+pub const PLUGIN_API_MAX_BODY_BYTES: usize = 64 * 1024;
+pub const PLUGIN_RATE_WINDOW_MS: u64 = 60_000;
+pub const DEFAULT_PLUGIN_TRIGGERS_PER_MINUTE: u32 = 6;
+pub const DEFAULT_PLUGIN_EVENTS_PER_MINUTE: u32 = 120;
+pub const PLUGIN_MAX_ID_LEN: usize = 64;
+pub const PLUGIN_MAX_LABEL_LEN: usize = 100;
+
+// Game state integration
+pub const DEFAULT_GSI_PORT: u16 = 47_821;
+pub const GSI_MAX_BODY_BYTES: usize = 256 * 1024;
+pub const GSI_PLUGIN_ID: &str = "gsi"; // Plugin ID of clips saved by GSI rules
+
+// Game detection
+pub const GAME_POLL_INTERVAL_MS: u64 = 3_000;
+pub const DEFAULT_GAME_GRACE_SECS: u32 = 60; // Keep recording this long after the game exits
+
+// Clock sync
+pub const DEFAULT_NTP_SERVERS: &[&str] = &["time.cloudflare.com", "time.google.com", "time.windows.com", "pool.ntp.org"];
+pub const DEFAULT_NTP_SAMPLES_PER_SERVER: u32 = 4;
+pub const DEFAULT_NTP_SYNC_INTERVAL_SECS: u32 = 15 * 60;
+pub const NTP_RETRY_SECS: u32 = 30; // First retry after a failed sync, doubling up to the interval
+pub const NTP_QUERY_TIMEOUT_MS: u64 = 2_000;
+pub const NTP_SAMPLE_GAP_MS: u64 = 100;
+pub const NTP_MIN_ERROR_MS: f64 = 1.0; // Added to every sample's error bound for timestamp resolution
+pub const NTP_DRIFT_HISTORY: usize = 8; // Syncs used to fit the drift rate
+pub const NTP_DRIFT_MIN_SPAN_US: i64 = 10 * 60 * 1_000_000; // Shorter histories are too noisy for a rate
+pub const NTP_MAX_DRIFT_PPM: f64 = 500.0;
+pub const NTP_STEP_THRESHOLD_US: i64 = 1_000_000; // Larger offset jumps reset the drift history
+pub const NTP_UNCERTAINTY_GROWTH_PPM: f64 = 20.0; // Assumed drift error when extrapolating
+pub const NTP_MAX_SYNCED_UNCERTAINTY_MS: f64 = 100.0; // Above this, replays are flagged unsynced
+pub const SIGNALING_SOURCE_NAME: &str = "signaling"; // Server name of room server time samples
+pub const SIGNALING_MAX_SAMPLES: usize = 32;
+pub const SIGNALING_SAMPLE_MAX_AGE_MS: u64 = 15 * 60 * 1000; // Older samples are dropped at sync time
+
+// Time base
+pub const TIME_REANCHOR_THRESHOLD_US: i64 = 30_000_000; // System vs monotonic gap that means suspend or a clock change
+pub const SEGMENT_TIME_FORMAT: &str = "%Y%m%dT%H%M%SZ"; // UTC, e.g. video_20231027T120000Z.mkv
+pub const FFMPEG_SEGMENT_TZ: &str = "UTC0"; // Makes FFmpeg's -strftime format in UTC (MSVCRT and POSIX syntax)
+pub const SEGMENT_INDEX_FILE: &str = "segments.jsonl"; // NTP start time of each segment, in the buffer dir
+
+// Config
+pub const CONFIG_VERSION: u32 = 1; // Bump together with a new migration in config::migrate
+pub const CONFIG_MAX_FRAMERATE: u32 = 240;
+pub const CONFIG_MAX_DIMENSION: u32 = 7680; // 8K
+pub const CONFIG_MIN_VIDEO_BITRATE: u32 = 500_000;
+pub const CONFIG_MAX_VIDEO_BITRATE: u32 = 200_000_000;
+pub const CONFIG_MIN_AUDIO_BITRATE: u32 = 32_000;
+pub const CONFIG_MAX_AUDIO_BITRATE: u32 = 512_000;
+
+// Benchmark
+pub const BENCHMARK_DURATION_SECS: u32 = 4; // Synthetic content encoded per run
+pub const BENCHMARK_MIN_REALTIME_FACTOR: f64 = 1.3; // Headroom for load spikes while a game runs
+pub const BENCHMARK_MAX_CPU_SHARE: f64 = 0.5; // Software encoders may take at most this share of all cores
+pub const BENCHMARK_PROFILE_NAME: &str = "Benchmarked"; // Recording profile the recommendation is saved as
@@ -0,0 +1,277 @@
+//! Encoder benchmark: how much video this machine can encode while a game runs.
+//!
+//! Each available encoder encodes a few seconds of synthetic content (see
+//! [CommandMode::Benchmark]) down a ladder of quality tiers until one is sustained. The realtime
+//! factor and CPU time come from FFmpeg's `-benchmark` summary. A tier is sustained when it
+//! encodes comfortably faster than realtime and, for software encoders, leaves most of the CPU
+//! to the game. CPU time includes generating the test pattern, so it errs on the high side.
+
+use serde::Serialize;
+#[cfg(target_os = "windows")]
+use std::os::windows::process::CommandExt;
+use std::path::Path;
+use std::process::Command;
+use tauri::AppHandle;
+
+use crate::config::{Bitrate, EncoderChoice, RecordingProfile, Resolution};
+use crate::constants::{BENCHMARK_DURATION_SECS, BENCHMARK_MAX_CPU_SHARE, BENCHMARK_MIN_REALTIME_FACTOR, BENCHMARK_PROFILE_NAME};
+use crate::ffmpeg::commands::{CommandMode, FfmpegCommandBuilder};
+use crate::ffmpeg::encoder::{self, VideoCodec, VideoEncoder};
+
+pub const BENCHMARK_PROGRESS_EVENT: &str = "benchmark-progress";
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Tier {
+    pub width: u32,
+    pub height: u32,
+    pub framerate: u32,
+    pub preset: &'static str,
+}
+
+/// Best first.
+pub const TIERS: &[Tier] = &[
+    Tier { width: 2560, height: 1440, framerate: 60, preset: "balanced" },
+    Tier { width: 1920, height: 1080, framerate: 144, preset: "speed" },
+    Tier { width: 1920, height: 1080, framerate: 60, preset: "balanced" },
+    Tier { width: 1920, height: 1080, framerate: 60, preset: "speed" },
+    Tier { width: 1280, height: 720, framerate: 60, preset: "speed" },
+    Tier { width: 1280, height: 720, framerate: 30, preset: "speed" },
+];
+
+impl Tier {
+    /// The bitrate recording would pick for this size and rate.
+    fn bitrate(&self) -> String {
+        crate::ffmpeg::utils::calculate_dynamic_bitrate(self.width, self.height, self.framerate)
+    }
+}
+
+/// FFmpeg's `-benchmark` summary, in seconds.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct BenchTimes {
+    pub user: f64,
+    pub system: f64,
+    pub real: f64,
+}
+
+/// Reads `bench: utime=1.234s stime=0.056s rtime=2.345s` from FFmpeg's stderr.
+pub fn parse_bench(stderr: &str) -> Option<BenchTimes> {
+    let line = stderr.lines().rev().find(|l| l.contains("bench:") && l.contains("rtime="))?;
+    let field = |name: &str| -> Option<f64> {
+        let start = line.find(name)? + name.len();
+        line[start..].split(|c: char| c == 's' || c.is_whitespace()).next()?.parse().ok()
+    };
+    Some(BenchTimes { user: field("utime=")?, system: field("stime=")?, real: field("rtime=")? })
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct BenchmarkResult {
+    /// FFmpeg encoder name
+    pub encoder: String,
+    pub hardware: bool,
+    /// Index into [TIERS], lower is better
+    pub tier: usize,
+    pub width: u32,
+    pub height: u32,
+    pub framerate: u32,
+    pub preset: String,
+    /// Content seconds encoded per wall-clock second
+    pub realtime_factor: f64,
+    pub cpu_seconds: f64,
+    /// Share of all cores busy while recording at this tier (0.25 = a quarter of the machine)
+    pub cpu_share: f64,
+    pub sustained: bool,
+    /// Why the run failed, if it did
+    pub error: Option<String>,
+}
+
+impl BenchmarkResult {
+    fn new(codec: &str, hardware: bool, tier: usize) -> Self {
+        let t = TIERS[tier];
+        Self {
+            encoder: codec.to_string(),
+            hardware,
+            tier,
+            width: t.width,
+            height: t.height,
+            framerate: t.framerate,
+            preset: t.preset.to_string(),
+            realtime_factor: 0.0,
+            cpu_seconds: 0.0,
+            cpu_share: 0.0,
+            sustained: false,
+            error: None,
+        }
+    }
+
+    fn measured(mut self, times: BenchTimes, seconds: u32, cores: usize) -> Self {
+        self.realtime_factor = if times.real > 0.0 { seconds as f64 / times.real } else { 0.0 };
+        self.cpu_seconds = times.user + times.system;
+        self.cpu_share = self.cpu_seconds / seconds as f64 / cores.max(1) as f64;
+        self.sustained = self.realtime_factor >= BENCHMARK_MIN_REALTIME_FACTOR
+            && (self.hardware || self.cpu_share <= BENCHMARK_MAX_CPU_SHARE);
+        self
+    }
+
+    fn failed(mut self, error: String) -> Self {
+        self.error = Some(error);
+        self
+    }
+
+    /// A profile recording at this result's settings.
+    pub fn to_profile(&self) -> RecordingProfile {
+        let tier = TIERS[self.tier];
+        RecordingProfile {
+            resolution: Some(Resolution::Fixed { width: self.width, height: self.height }),
+            framerate: Some(self.framerate),
+            bitrate: tier.bitrate().parse::<Bitrate>().ok(),
+            encoder: VideoEncoder::from_ffmpeg_codec(&self.encoder).map(|(e, c)| EncoderChoice::Fixed(e, c)),
+            video_preset: Some(self.preset.clone()),
+            ..RecordingProfile::new(BENCHMARK_PROFILE_NAME)
+        }
+    }
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct BenchmarkReport {
+    pub results: Vec<BenchmarkResult>,
+    /// None if no encoder sustained even the lowest tier
+    pub recommended: Option<RecordingProfile>,
+    /// Whether `recommended` was saved and made the active profile
+    pub applied: bool,
+}
+
+/// The highest sustained tier. Between encoders that sustain it, hardware (which leaves the
+/// CPU to the game), then the lowest CPU share.
+pub fn recommend(results: &[BenchmarkResult]) -> Option<&BenchmarkResult> {
+    results.iter().filter(|r| r.sustained).min_by(|a, b| {
+        a.tier.cmp(&b.tier)
+            .then(b.hardware.cmp(&a.hardware))
+            .then(a.cpu_share.total_cmp(&b.cpu_share))
+    })
+}
+
+/// Encoders to benchmark: every working one in `codec`'s family or H.264. libx264 is always
+/// among them, so CPU-only machines get a result too.
+fn candidates(available: &[(VideoEncoder, VideoCodec)], codec: VideoCodec) -> Vec<(VideoEncoder, VideoCodec)> {
+    available.iter().copied().filter(|(_, c)| *c == codec || *c == VideoCodec::H264).collect()
+}
+
+/// Runs the benchmark, calling `on_result` after each run. Blocks for a while: up to
+/// `TIERS.len()` runs of `BENCHMARK_DURATION_SECS` per encoder.
+pub fn run(app: &AppHandle, codec: VideoCodec, mut on_result: impl FnMut(&BenchmarkResult)) -> Vec<BenchmarkResult> {
+    let ffmpeg_path = crate::ffmpeg::utils::get_sidecar_path(app, "ffmpeg")
+        .unwrap_or_else(|_| std::path::PathBuf::from("ffmpeg"));
+    let cores = std::thread::available_parallelism().map(|n| n.get()).unwrap_or(1);
+    let available = encoder::get_available_encoders(app);
+
+    let mut results = Vec::new();
+    for (backend, family) in candidates(&available, codec) {
+        let name = backend.ffmpeg_codec(family);
+        for tier in 0..TIERS.len() {
+            let result = run_tier(&ffmpeg_path, name, backend.is_hardware(), tier, cores);
+            log::info!(
+                "Benchmark {} {}x{}@{} {}: {:.2}x realtime, {:.0}% CPU{}",
+                name, result.width, result.height, result.framerate, result.preset,
+                result.realtime_factor, result.cpu_share * 100.0,
+                result.error.as_deref().map(|e| format!(" ({})", e)).unwrap_or_default()
+            );
+            on_result(&result);
+            let sustained = result.sustained;
+            results.push(result);
+            // Lower tiers would only be worse
+            if sustained {
+                break;
+            }
+        }
+    }
+    results
+}
+
+fn run_tier(ffmpeg_path: &Path, codec: &str, hardware: bool, tier: usize, cores: usize) -> BenchmarkResult {
+    let t = TIERS[tier];
+    let result = BenchmarkResult::new(codec, hardware, tier);
+    let args = FfmpegCommandBuilder::new(String::new())
+        .with_mode(CommandMode::Benchmark(BENCHMARK_DURATION_SECS))
+        .with_video_codec(codec.to_string())
+        .with_preset(Some(t.preset.to_string()))
+        .with_bitrate(t.bitrate())
+        .with_framerate(t.framerate)
+        .with_resolution(Some(format!("{}x{}", t.width, t.height)))
+        .build();
+
+    let mut cmd = Command::new(ffmpeg_path);
+    #[cfg(target_os = "windows")]
+    cmd.creation_flags(0x08000000); // CREATE_NO_WINDOW
+    let output = match cmd.arg("-hide_banner").args(args).output() {
+        Ok(output) => output,
+        Err(e) => return result.failed(format!("Failed to run FFmpeg: {}", e)),
+    };
+    let stderr = String::from_utf8_lossy(&output.stderr);
+    if !output.status.success() {
+        let last = stderr.lines().rev().find(|l| !l.trim().is_empty()).unwrap_or("unknown error");
+        return result.failed(format!("Encoding failed: {}", last.trim()));
+    }
+    match parse_bench(&stderr) {
+        Some(times) => result.measured(times, BENCHMARK_DURATION_SECS, cores),
+        None => result.failed("No benchmark summary in FFmpeg's output".to_string()),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_bench() {
+        let stderr = "frame=  240 fps=118 q=-0.0 Lsize=N/A time=00:00:04.00 bitrate=N/A speed=1.97x\n\
+                      bench: utime=7.421s stime=0.312s rtime=2.031s\n\
+                      bench: maxrss=182344KiB\n";
+        assert_eq!(parse_bench(stderr), Some(BenchTimes { user: 7.421, system: 0.312, real: 2.031 }));
+        assert_eq!(parse_bench("frame=  240 fps=118 speed=1.97x"), None);
+    }
+
+    #[test]
+    fn test_sustained_needs_speed_and_cpu_headroom() {
+        let times = BenchTimes { user: 7.0, system: 1.0, real: 2.0 };
+        // 2x realtime on 8 cores: 2 CPU seconds per second is a quarter of the machine
+        let x264 = BenchmarkResult::new("libx264", false, 3).measured(times, 4, 8);
+        assert_eq!((x264.realtime_factor, x264.cpu_share), (2.0, 0.25));
+        assert!(x264.sustained);
+
+        // Fast enough, but takes all of a 2-core CPU away from the game
+        assert!(!BenchmarkResult::new("libx264", false, 3).measured(times, 4, 2).sustained);
+        // Hardware encoders aren't held to the CPU limit
+        assert!(BenchmarkResult::new("h264_nvenc", true, 3).measured(times, 4, 2).sustained);
+        // Too slow
+        let slow = BenchTimes { user: 4.0, system: 0.0, real: 3.5 };
+        assert!(!BenchmarkResult::new("h264_nvenc", true, 3).measured(slow, 4, 8).sustained);
+    }
+
+    #[test]
+    fn test_recommend_and_profile() {
+        let times = BenchTimes { user: 2.0, system: 0.0, real: 2.0 };
+        let x264 = BenchmarkResult::new("libx264", false, 2).measured(times, 4, 8);
+        let nvenc = BenchmarkResult::new("h264_nvenc", true, 2).measured(times, 4, 8);
+        let failed = BenchmarkResult::new("hevc_nvenc", true, 0).failed("No capable devices found".into());
+        let lower = BenchmarkResult::new("h264_qsv", true, 4).measured(times, 4, 8);
+        let results = vec![failed, x264.clone(), lower, nvenc.clone()];
+        assert_eq!(recommend(&results), Some(&nvenc));
+        assert_eq!(recommend(std::slice::from_ref(&x264)), Some(&x264));
+        assert_eq!(recommend(&[]), None);
+
+        let profile = nvenc.to_profile();
+        assert_eq!(profile.name, BENCHMARK_PROFILE_NAME);
+        assert_eq!(profile.resolution, Some(Resolution::Fixed { width: 1920, height: 1080 }));
+        assert_eq!(profile.framerate, Some(60));
+        assert_eq!(profile.encoder, Some(EncoderChoice::Fixed(VideoEncoder::Nvenc, VideoCodec::H264)));
+        assert_eq!(profile.video_preset.as_deref(), Some("balanced"));
+        assert!(profile.bitrate.is_some());
+    }
+
+    #[test]
+    fn test_cpu_only_machine_benchmarks_libx264() {
+        let cpu = [(VideoEncoder::Software, VideoCodec::Hevc), (VideoEncoder::Software, VideoCodec::H264)];
+        assert_eq!(candidates(&cpu, VideoCodec::H264), vec![(VideoEncoder::Software, VideoCodec::H264)]);
+        assert_eq!(candidates(&cpu, VideoCodec::Hevc), cpu.to_vec());
+    }
+}
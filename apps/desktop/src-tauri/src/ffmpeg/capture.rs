@@ -0,0 +1,296 @@
+//! Capture Targets
+//!
+//! Describes what the video process records: a whole monitor, a single window, or a fixed
+//! region of a monitor.
+//!
+//! Monitors are identified by name, desktop position and size rather than list index, because
+//! Windows reorders both the monitor list and the DXGI outputs when displays are reconnected.
+//! The ddagrab `output_idx` is looked up from the desktop coordinates at recording start.
+
+use serde::{Deserialize, Serialize};
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct MonitorIdentity {
+    pub name: String,
+    pub x: i32,
+    pub y: i32,
+    pub width: u32,
+    pub height: u32,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum CaptureTarget {
+    /// A whole monitor. `None` records the primary monitor.
+    Monitor {
+        #[serde(default)]
+        monitor: Option<MonitorIdentity>,
+    },
+    /// A single window, matched by title (case-insensitive substring) and/or executable name.
+    Window {
+        #[serde(default)]
+        title: Option<String>,
+        #[serde(default)]
+        process: Option<String>,
+    },
+    /// A rectangle relative to the top-left corner of a monitor.
+    Region {
+        #[serde(default)]
+        monitor: Option<MonitorIdentity>,
+        x: i32,
+        y: i32,
+        width: u32,
+        height: u32,
+    },
+}
+
+impl Default for CaptureTarget {
+    fn default() -> Self {
+        CaptureTarget::Monitor { monitor: None }
+    }
+}
+
+impl CaptureTarget {
+    /// The monitor this target lives on, if it names one.
+    pub fn monitor(&self) -> Option<&MonitorIdentity> {
+        match self {
+            CaptureTarget::Monitor { monitor } | CaptureTarget::Region { monitor, .. } => monitor.as_ref(),
+            CaptureTarget::Window { .. } => None,
+        }
+    }
+}
+
+/// Finds `wanted` among the connected monitors.
+///
+/// Tries the exact identity first, then the same desktop rectangle (Windows renamed the display),
+/// then the same name and size (the layout was rearranged), then the name alone (resolution changed).
+pub fn select_monitor(monitors: &[MonitorIdentity], wanted: &MonitorIdentity) -> Option<usize> {
+    let same_rect = |m: &MonitorIdentity| m.x == wanted.x && m.y == wanted.y && m.width == wanted.width && m.height == wanted.height;
+    let same_size = |m: &MonitorIdentity| m.width == wanted.width && m.height == wanted.height;
+
+    monitors.iter().position(|m| m == wanted)
+        .or_else(|| monitors.iter().position(same_rect))
+        .or_else(|| monitors.iter().position(|m| m.name == wanted.name && same_size(m)))
+        .or_else(|| monitors.iter().position(|m| m.name == wanted.name))
+}
+
+/// Clips a region to the monitor bounds and rounds the size down to even numbers (NV12 needs it).
+/// Returns None if nothing of the region is left.
+pub fn clamp_region(monitor_width: u32, monitor_height: u32, x: i32, y: i32, width: u32, height: u32) -> Option<(u32, u32, u32, u32)> {
+    let left = x.clamp(0, monitor_width as i32) as u32;
+    let top = y.clamp(0, monitor_height as i32) as u32;
+    let right = (x as i64 + width as i64).clamp(0, monitor_width as i64) as u32;
+    let bottom = (y as i64 + height as i64).clamp(0, monitor_height as i64) as u32;
+
+    let w = right.saturating_sub(left) & !1;
+    let h = bottom.saturating_sub(top) & !1;
+    if w == 0 || h == 0 {
+        None
+    } else {
+        Some((left, top, w, h))
+    }
+}
+
+/// Parses the GDI display number out of a monitor name (`\\.\DISPLAY2` -> 1).
+/// Only a fallback: display numbers follow DXGI output order on most, but not all, systems.
+pub fn parse_display_index(name: &str) -> Option<u32> {
+    let idx = name.find("DISPLAY")?;
+    let num: String = name[idx + 7..].chars().take_while(|c| c.is_ascii_digit()).collect();
+    num.parse::<u32>().ok().filter(|n| *n > 0).map(|n| n - 1)
+}
+
+/// Whether a top-level window matches the requested title and/or process.
+pub fn window_matches(title: &str, exe: &str, want_title: Option<&str>, want_process: Option<&str>) -> bool {
+    if want_title.is_none() && want_process.is_none() {
+        return false;
+    }
+
+    let title_ok = want_title.map_or(true, |t| title.to_lowercase().contains(&t.to_lowercase()));
+    let process_ok = want_process.map_or(true, |p| {
+        let file = exe.rsplit(['\\', '/']).next().unwrap_or(exe).to_lowercase();
+        let p = p.to_lowercase();
+        file == p || file.strip_suffix(".exe") == Some(p.as_str())
+    });
+    title_ok && process_ok
+}
+
+/// Index of the DXGI output covering exactly this desktop rectangle, as used by ddagrab's `output_idx`.
+/// ddagrab enumerates the outputs of the default (first) adapter.
+#[cfg(windows)]
+pub fn ddagrab_output_index(monitor: &MonitorIdentity) -> Option<u32> {
+    use windows::Win32::Graphics::Dxgi::{CreateDXGIFactory1, IDXGIFactory1, DXGI_OUTPUT_DESC};
+
+    unsafe {
+        let factory: IDXGIFactory1 = CreateDXGIFactory1().ok()?;
+        let adapter = factory.EnumAdapters1(0).ok()?;
+        let mut index = 0;
+        while let Ok(output) = adapter.EnumOutputs(index) {
+            let mut desc = DXGI_OUTPUT_DESC::default();
+            if output.GetDesc(&mut desc).is_ok() {
+                let rect = desc.DesktopCoordinates;
+                if rect.left == monitor.x
+                    && rect.top == monitor.y
+                    && (rect.right - rect.left) as u32 == monitor.width
+                    && (rect.bottom - rect.top) as u32 == monitor.height
+                {
+                    return Some(index);
+                }
+            }
+            index += 1;
+        }
+    }
+    None
+}
+
+/// A visible top-level window picked for gdigrab capture.
+#[derive(Debug, Clone)]
+pub struct WindowInfo {
+    pub title: String,
+    pub width: u32,
+    pub height: u32,
+}
+
+/// Finds the first visible top-level window matching the title and/or process name.
+#[cfg(windows)]
+pub fn find_window(want_title: Option<&str>, want_process: Option<&str>) -> Option<WindowInfo> {
+    use windows::Win32::Foundation::{BOOL, CloseHandle, HWND, LPARAM, RECT};
+    use windows::Win32::System::Threading::{OpenProcess, QueryFullProcessImageNameW, PROCESS_NAME_WIN32, PROCESS_QUERY_LIMITED_INFORMATION};
+    use windows::Win32::UI::WindowsAndMessaging::{EnumWindows, GetWindowRect, GetWindowTextW, GetWindowThreadProcessId, IsWindowVisible};
+    use windows::core::PWSTR;
+
+    unsafe extern "system" fn collect(hwnd: HWND, lparam: LPARAM) -> BOOL {
+        let windows = &mut *(lparam.0 as *mut Vec<HWND>);
+        windows.push(hwnd);
+        true.into()
+    }
+
+    let mut handles: Vec<HWND> = Vec::new();
+    unsafe {
+        let _ = EnumWindows(Some(collect), LPARAM(&mut handles as *mut Vec<HWND> as isize));
+    }
+
+    for hwnd in handles {
+        unsafe {
+            if !IsWindowVisible(hwnd).as_bool() {
+                continue;
+            }
+
+            let mut title_buf = [0u16; 512];
+            let len = GetWindowTextW(hwnd, &mut title_buf);
+            if len <= 0 {
+                continue;
+            }
+            let title = String::from_utf16_lossy(&title_buf[..len as usize]);
+
+            let mut pid = 0u32;
+            GetWindowThreadProcessId(hwnd, Some(&mut pid));
+            let mut exe = String::new();
+            if let Ok(process) = OpenProcess(PROCESS_QUERY_LIMITED_INFORMATION, false, pid) {
+                let mut exe_buf = [0u16; 1024];
+                let mut size = exe_buf.len() as u32;
+                if QueryFullProcessImageNameW(process, PROCESS_NAME_WIN32, PWSTR(exe_buf.as_mut_ptr()), &mut size).is_ok() {
+                    exe = String::from_utf16_lossy(&exe_buf[..size as usize]);
+                }
+                let _ = CloseHandle(process);
+            }
+
+            if !window_matches(&title, &exe, want_title, want_process) {
+                continue;
+            }
+
+            let mut rect = RECT::default();
+            if GetWindowRect(hwnd, &mut rect).is_err() {
+                continue;
+            }
+            return Some(WindowInfo {
+                title,
+                width: (rect.right - rect.left).max(0) as u32,
+                height: (rect.bottom - rect.top).max(0) as u32,
+            });
+        }
+    }
+    None
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn monitor(name: &str, x: i32, y: i32, width: u32, height: u32) -> MonitorIdentity {
+        MonitorIdentity { name: name.to_string(), x, y, width, height }
+    }
+
+    #[test]
+    fn test_select_monitor_survives_reordering() {
+        let saved = monitor("\\\\.\\DISPLAY2", 1920, 0, 2560, 1440);
+        // Reconnected: list order swapped and Windows renumbered the displays
+        let now = vec![
+            monitor("\\\\.\\DISPLAY1", 1920, 0, 2560, 1440),
+            monitor("\\\\.\\DISPLAY2", 0, 0, 1920, 1080),
+        ];
+        assert_eq!(select_monitor(&now, &saved), Some(0));
+    }
+
+    #[test]
+    fn test_select_monitor_after_layout_or_resolution_change() {
+        let saved = monitor("\\\\.\\DISPLAY2", 1920, 0, 2560, 1440);
+
+        // Moved to the left of the primary
+        let moved = vec![monitor("\\\\.\\DISPLAY1", 0, 0, 1920, 1080), monitor("\\\\.\\DISPLAY2", -2560, 0, 2560, 1440)];
+        assert_eq!(select_monitor(&moved, &saved), Some(1));
+
+        // Resolution lowered
+        let resized = vec![monitor("\\\\.\\DISPLAY1", 0, 0, 1920, 1080), monitor("\\\\.\\DISPLAY2", 1920, 0, 1920, 1080)];
+        assert_eq!(select_monitor(&resized, &saved), Some(1));
+
+        // Gone
+        let gone = vec![monitor("\\\\.\\DISPLAY1", 0, 0, 1920, 1080)];
+        assert_eq!(select_monitor(&gone, &saved), None);
+    }
+
+    #[test]
+    fn test_clamp_region() {
+        assert_eq!(clamp_region(1920, 1080, 100, 200, 800, 600), Some((100, 200, 800, 600)));
+        // Partly off-screen, odd size
+        assert_eq!(clamp_region(1920, 1080, -10, 1000, 501, 200), Some((0, 1000, 490, 80)));
+        assert_eq!(clamp_region(1920, 1080, 2000, 0, 100, 100), None);
+    }
+
+    #[test]
+    fn test_parse_display_index() {
+        assert_eq!(parse_display_index("\\\\.\\DISPLAY1"), Some(0));
+        assert_eq!(parse_display_index("\\\\.\\DISPLAY12"), Some(11));
+        assert_eq!(parse_display_index("\\\\.\\DISPLAY0"), None);
+        assert_eq!(parse_display_index("Generic PnP Monitor"), None);
+    }
+
+    #[test]
+    fn test_window_matches() {
+        let exe = "C:\\Program Files\\Steam\\steamapps\\common\\cs2\\game\\bin\\win64\\cs2.exe";
+        assert!(window_matches("Counter-Strike 2", exe, None, Some("cs2")));
+        assert!(window_matches("Counter-Strike 2", exe, None, Some("CS2.exe")));
+        assert!(window_matches("Counter-Strike 2", exe, Some("counter-strike"), Some("cs2")));
+        assert!(!window_matches("Counter-Strike 2", exe, Some("Valorant"), Some("cs2")));
+        assert!(!window_matches("Counter-Strike 2", exe, None, Some("cs")));
+        assert!(!window_matches("Counter-Strike 2", exe, None, None));
+    }
+
+    #[test]
+    fn test_capture_target_toml_roundtrip() {
+        #[derive(Serialize, Deserialize)]
+        struct Wrapper {
+            capture_target: CaptureTarget,
+        }
+
+        let region = Wrapper {
+            capture_target: CaptureTarget::Region { monitor: Some(monitor("\\\\.\\DISPLAY1", 0, 0, 1920, 1080)), x: 10, y: 20, width: 640, height: 480 },
+        };
+        let text = toml::to_string(&region).unwrap();
+        assert!(text.contains("type = \"region\""));
+        let parsed: Wrapper = toml::from_str(&text).unwrap();
+        assert_eq!(parsed.capture_target, region.capture_target);
+
+        let window: Wrapper = toml::from_str("[capture_target]\ntype = \"window\"\nprocess = \"cs2.exe\"\n").unwrap();
+        assert_eq!(window.capture_target, CaptureTarget::Window { title: None, process: Some("cs2.exe".to_string()) });
+    }
+}
@@ -9,12 +9,13 @@ use crate::constants::{
     DEFAULT_VIDEO_CODEC, DEFAULT_VIDEO_BITRATE, DEFAULT_VIDEO_FRAMERATE,
     DEFAULT_AUDIO_SAMPLE_RATE, DEFAULT_AUDIO_CHANNELS,
     AUDIO_BUFFER_SIZE_MS, RTBUFSIZE, PRESET_P4,
-    TUNE_ZEROLATENCY, PROFILE_HIGH,
+    TUNE_ZEROLATENCY, PROFILE_HIGH, PROFILE_MAIN,
     SEGMENT_LIST_SIZE, SEGMENT_LIST_TYPE, SEGMENT_FORMAT_MKV,
-    OUTPUT_FORMAT_SEGMENT, OUTPUT_FORMAT_MP4, OUTPUT_FORMAT_LAVFI, OUTPUT_FORMAT_DSHOW, OUTPUT_FORMAT_F32LE,
+    OUTPUT_FORMAT_SEGMENT, OUTPUT_FORMAT_MP4, OUTPUT_FORMAT_LAVFI, OUTPUT_FORMAT_GDIGRAB, OUTPUT_FORMAT_DSHOW, OUTPUT_FORMAT_F32LE,
     BITRATE_MAX_MULTIPLIER, BITRATE_MAX_DIVISOR, BITRATE_BUF_MULTIPLIER, GOP_MULTIPLIER,
     DEFAULT_MIC_CHANNELS
 };
+use crate::config::AudioBackend;
 use crate::ffmpeg::encoder::HardwareScalingMode;
 
 #[derive(Debug, Clone)]
@@ -29,6 +30,8 @@ pub struct FfmpegCommandBuilder {
     resolution: Option<String>,
     video_size: Option<String>,
     monitor_index: u32,
+    capture_offset: Option<(u32, u32)>, // Region capture: offset inside the monitor
+    window_title: Option<String>,       // Window capture via gdigrab instead of ddagrab
     
     // Audio Config
     audio_source: Option<String>, // Microphone
@@ -42,7 +45,7 @@ pub struct FfmpegCommandBuilder {
     audio_sample_rate: u32,       // Target Sample Rate (e.g. 48000)
     audio_channels: u16,
 
-    audio_backend: String,
+    audio_backend: AudioBackend,
     
     // Segment Config
     segment_time: Option<u32>,
@@ -59,6 +62,8 @@ pub enum CommandMode {
     Combined,
     VideoOnly,
     AudioOnly,
+    /// Encodes this many seconds of synthetic video to nowhere, see [crate::ffmpeg::benchmark]
+    Benchmark(u32),
 }
 
 
@@ -75,6 +80,8 @@ impl FfmpegCommandBuilder {
             resolution: None,
             video_size: None,
             monitor_index: 0,
+            capture_offset: None,
+            window_title: None,
             audio_source: None,
             system_audio: false,
             system_sample_rate: DEFAULT_AUDIO_SAMPLE_RATE,
@@ -86,7 +93,7 @@ impl FfmpegCommandBuilder {
             audio_sample_rate: DEFAULT_AUDIO_SAMPLE_RATE,
             audio_channels: DEFAULT_AUDIO_CHANNELS,
 
-            audio_backend: "cpal".to_string(),
+            audio_backend: AudioBackend::Cpal,
             segment_time: None,
             segment_wrap: None,
             segment_list: None,
@@ -156,6 +163,18 @@ impl FfmpegCommandBuilder {
         self
     }
 
+    /// Captures only part of the monitor, starting at `(x, y)`. The size comes from `with_video_size`.
+    pub fn with_region_offset(mut self, offset: Option<(u32, u32)>) -> Self {
+        self.capture_offset = offset;
+        self
+    }
+
+    /// Captures a single window by its exact title. Frames arrive in system memory, not D3D11.
+    pub fn with_window_capture(mut self, title: Option<String>) -> Self {
+        self.window_title = title;
+        self
+    }
+
     pub fn with_audio_source(mut self, source: Option<String>) -> Self {
         self.audio_source = source;
         self
@@ -183,7 +202,7 @@ impl FfmpegCommandBuilder {
     }
 
 
-    pub fn with_audio_backend(mut self, backend: String) -> Self {
+    pub fn with_audio_backend(mut self, backend: AudioBackend) -> Self {
         self.audio_backend = backend;
         self
     }
@@ -219,6 +238,12 @@ impl FfmpegCommandBuilder {
                 args.extend(self.build_video_encoding());
                 args.extend(self.build_output());
             }
+            CommandMode::Benchmark(seconds) => {
+                args.extend(self.build_benchmark_input(seconds));
+                args.extend(self.build_video_encoding());
+                self.use_system_memory_pix_fmt(&mut args);
+                args.extend(vec!["-f".to_string(), "null".to_string(), "-".to_string()]);
+            }
             CommandMode::AudioOnly => {
                 args.extend(self.build_audio_inputs());
                 // No complex filters for audio-only usually, but we might need aresample
@@ -228,11 +253,47 @@ impl FfmpegCommandBuilder {
             }
         }
 
+        if self.window_title.is_some() {
+            self.use_system_memory_pix_fmt(&mut args);
+        }
+
         args
     }
 
+    /// gdigrab and the benchmark's lavfi source deliver frames in system memory, which the
+    /// encoding options' d3d11 pix_fmt can't take: swaps it for one they convert to.
+    fn use_system_memory_pix_fmt(&self, args: &mut [String]) {
+        let pix_fmt = if Self::is_software_codec(&self.video_codec) { "yuv420p" } else { "nv12" };
+        if let Some(i) = args.iter().position(|a| a == "-pix_fmt") {
+            args[i + 1] = pix_fmt.to_string();
+        }
+    }
+
+    // --- BENCHMARK HELPERS ---
+    /// `testsrc2` at the output size and rate: moving, detailed content that costs the encoder
+    /// about what a game does, without capturing anything.
+    fn build_benchmark_input(&self, seconds: u32) -> Vec<String> {
+        let size = self.resolution.clone().or_else(|| self.video_size.clone()).unwrap_or_else(|| "1920x1080".to_string());
+        vec![
+            "-benchmark".to_string(),
+            "-f".to_string(), OUTPUT_FORMAT_LAVFI.to_string(),
+            "-i".to_string(), format!("testsrc2=size={}:rate={}", size, self.framerate),
+            "-t".to_string(), seconds.to_string(),
+        ]
+    }
+
     // --- VIDEO ONLY HELPERS ---
     fn build_video_inputs(&self) -> Vec<String> {
+        if let Some(title) = &self.window_title {
+            return vec![
+                "-f".to_string(), OUTPUT_FORMAT_GDIGRAB.to_string(),
+                "-thread_queue_size".to_string(), FFMPEG_THREAD_QUEUE_SIZE.to_string(),
+                "-framerate".to_string(), self.framerate.to_string(),
+                "-use_wallclock_as_timestamps".to_string(), "1".to_string(),
+                "-i".to_string(), format!("title={}", title),
+            ];
+        }
+
         let mut args = vec![
             "-f".to_string(),
             OUTPUT_FORMAT_LAVFI.to_string(),
@@ -244,6 +305,9 @@ impl FfmpegCommandBuilder {
         if let Some(size) = &self.video_size {
             filter_opts.push_str(&format!(":video_size={}", size));
         }
+        if let Some((x, y)) = self.capture_offset {
+            filter_opts.push_str(&format!(":offset_x={}:offset_y={}", x, y));
+        }
         
         // EXTRA HW FRAMES
         args.push("-extra_hw_frames".to_string());
@@ -262,7 +326,7 @@ impl FfmpegCommandBuilder {
     fn build_video_filters(&self) -> Vec<String> {
         let mut args = Vec::new();
         let mut video_filters = String::new();
-        let mut is_hardware_frame = true; // ddagrab starts in D3D11
+        let mut is_hardware_frame = self.window_title.is_none(); // ddagrab starts in D3D11, gdigrab in system memory
 
         // Resolution Logic
         let use_native_res = match &self.resolution {
@@ -274,7 +338,9 @@ impl FfmpegCommandBuilder {
             if let Some(res) = &self.resolution {
                 let parts: Vec<&str> = res.split('x').collect();
                 if parts.len() == 2 {
-                    match self.scaling_mode {
+                    // Hardware scalers need D3D11 input
+                    let scaling_mode = if is_hardware_frame { self.scaling_mode.clone() } else { HardwareScalingMode::None };
+                    match scaling_mode {
                         HardwareScalingMode::D3D11 => {
                             video_filters.push_str(&format!("scale_d3d11=width={}:height={}:format=nv12", parts[0], parts[1]));
                             // Still D3D11
@@ -288,7 +354,9 @@ impl FfmpegCommandBuilder {
                         HardwareScalingMode::None => {
                             // Fallback to software scale
                             // hwdownload -> format (force bgra/rgba to prevent nv12 negotiation error) -> scale
-                            video_filters.push_str("hwdownload,format=bgra,");
+                            if is_hardware_frame {
+                                video_filters.push_str("hwdownload,format=bgra,");
+                            }
                             video_filters.push_str(&format!("scale={}:{}", parts[0], parts[1]));
                             is_hardware_frame = false;
                         }
@@ -317,7 +385,7 @@ impl FfmpegCommandBuilder {
                 // But ensuring nv12 is good.
                 video_filters.push_str("format=nv12");
             }
-        } else if self.video_codec.contains("libx264") {
+        } else if Self::is_software_codec(&self.video_codec) {
             if is_hardware_frame {
                 // Software encoding needs system memory
                 // hwdownload -> format (force bgra/rgba) -> format=nv12 (for encoder)
@@ -361,7 +429,7 @@ impl FfmpegCommandBuilder {
 
         // Input 0: Microphone
         if let Some(mic_source) = &self.audio_source {
-            if self.audio_backend == "dshow" {
+            if self.audio_backend == AudioBackend::Dshow {
                  args.extend(vec![
                      "-f".to_string(), OUTPUT_FORMAT_DSHOW.to_string(),
                     "-audio_buffer_size".to_string(), AUDIO_BUFFER_SIZE_MS.to_string(),
@@ -377,7 +445,7 @@ impl FfmpegCommandBuilder {
                     "-thread_queue_size".to_string(), FFMPEG_AUDIO_THREAD_QUEUE_SIZE.to_string(),
                     "-ar".to_string(), mic_rate.to_string(),
                     "-ac".to_string(), mic_ch,
-                    "-use_wallclock_as_timestamps".to_string(), "1".to_string(),
+                    // No wallclock timestamps: the pipe is paced sample-accurately by audio::clock
                     "-i".to_string(), MIC_AUDIO_PIPE_NAME.to_string(),
                 ]);
             }
@@ -391,7 +459,6 @@ impl FfmpegCommandBuilder {
                 "-thread_queue_size".to_string(), FFMPEG_AUDIO_THREAD_QUEUE_SIZE.to_string(),
                 "-ar".to_string(), self.system_sample_rate.to_string(),
                 "-ac".to_string(), sys_ch,
-                "-use_wallclock_as_timestamps".to_string(), "1".to_string(),
                 "-i".to_string(), SYSTEM_AUDIO_PIPE_NAME.to_string(),
             ]);
         }
@@ -509,7 +576,7 @@ impl FfmpegCommandBuilder {
         // --- FORMAT BRIDGING ---
         if self.video_codec.contains("qsv") {
             video_filters.push_str("hwmap=derive_device=qsv,format=qsv");
-        } else if self.video_codec.contains("libx264") {
+        } else if Self::is_software_codec(&self.video_codec) {
             video_filters.push_str("hwdownload,format=nv12");
         }
 
@@ -572,10 +639,10 @@ impl FfmpegCommandBuilder {
 
         // Restore pix_fmt d3d11 for hardware encoders (ddagrab path)
         // Previous behavior was to set d3d11 for ddagrab. Since ddagrab is now default/hardcoded,
-        // we set it for all hardware encoders. Software (x264) needs yuv420p.
+        // we set it for all hardware encoders. Software (x264/x265/SVT-AV1) needs yuv420p.
         // WARNING: DO NOT TOUCH THIS WITHOUT EXPLICIT PERMISSION.
         // Changing this will break scale_d3d11 and cause A/V desync.
-        if !self.video_codec.contains("libx264") {
+        if !Self::is_software_codec(&self.video_codec) {
              args.extend(vec!["-pix_fmt".to_string(), "d3d11".to_string()]);
         } else {
              args.extend(vec!["-pix_fmt".to_string(), "yuv420p".to_string()]);
@@ -594,7 +661,7 @@ impl FfmpegCommandBuilder {
                 "-maxrate".to_string(), format!("{}k", kbps * BITRATE_MAX_MULTIPLIER / BITRATE_MAX_DIVISOR),
                 "-bufsize".to_string(), format!("{}k", kbps * BITRATE_BUF_MULTIPLIER),
                 "-preset".to_string(), preset,
-                "-profile:v".to_string(), self.profile.clone().unwrap_or(PROFILE_HIGH.to_string()),
+                "-profile:v".to_string(), self.profile.clone().unwrap_or(Self::default_profile(&self.video_codec).to_string()),
             ]);
             if let Some(tune) = &self.tune {
                  args.extend(vec!["-tune".to_string(), tune.clone()]);
@@ -605,16 +672,23 @@ impl FfmpegCommandBuilder {
                 "-b:v".to_string(), self.bitrate.clone(),
                 "-usage".to_string(), "transcoding".to_string(),
                 "-quality".to_string(), preset, // AMF uses -quality, not -preset
-                "-profile:v".to_string(), self.profile.clone().unwrap_or(PROFILE_HIGH.to_string()),
+                "-profile:v".to_string(), self.profile.clone().unwrap_or(Self::default_profile(&self.video_codec).to_string()),
             ]);
         } else if self.video_codec.contains("qsv") {
             args.extend(vec![
                 "-rc".to_string(), "vbr".to_string(),
                 "-b:v".to_string(), self.bitrate.clone(),
                 "-preset".to_string(), preset,
-                "-profile:v".to_string(), self.profile.clone().unwrap_or(PROFILE_HIGH.to_string()),
+                "-profile:v".to_string(), self.profile.clone().unwrap_or(Self::default_profile(&self.video_codec).to_string()),
+            ]);
+        } else if self.video_codec == "libsvtav1" {
+            // SVT-AV1 has no zerolatency tune; a bitrate alone selects VBR
+            args.extend(vec![
+                "-b:v".to_string(), self.bitrate.clone(),
+                "-preset".to_string(), preset,
             ]);
         } else {
+            // libx264 / libx265
             args.extend(vec![
                 "-b:v".to_string(), self.bitrate.clone(),
                 "-preset".to_string(), preset,
@@ -696,6 +770,19 @@ impl FfmpegCommandBuilder {
         args
     }
 
+    fn is_software_codec(codec: &str) -> bool {
+        codec.starts_with("lib")
+    }
+
+    /// `-profile:v` default per codec family ("high" only exists for H.264).
+    fn default_profile(codec: &str) -> &'static str {
+        if codec.starts_with("hevc_") || codec.starts_with("av1_") {
+            PROFILE_MAIN
+        } else {
+            PROFILE_HIGH
+        }
+    }
+
     fn sanitize_preset(codec: &str, preset: &str) -> String {
         let p = preset.to_lowercase();
         
@@ -724,8 +811,17 @@ impl FfmpegCommandBuilder {
                 "p5" | "p6" | "p7" | "quality" | "slow" | "slower" | "veryslow" => "veryslow".to_string(),
                 _ => "veryfast".to_string(),
             }
+        } else if codec == "libsvtav1" {
+            // SVT-AV1 expects 0 (slowest) to 13 (fastest)
+            match p.as_str() {
+                "p1" | "p2" | "speed" | "ultrafast" | "superfast" | "veryfast" => "12".to_string(),
+                "p3" | "p4" | "balanced" | "faster" | "fast" | "medium" => "10".to_string(),
+                "p5" | "p6" | "p7" | "quality" | "slow" | "slower" | "veryslow" => "8".to_string(),
+                val if val.parse::<u8>().is_ok_and(|n| n <= 13) => val.to_string(),
+                _ => "10".to_string(),
+            }
         } else {
-            // Software (x264) - Standard presets
+            // Software (x264/x265) - Standard presets
             match p.as_str() {
                 "p1" | "p2" | "speed" => "ultrafast".to_string(),
                 "p3" | "p4" | "balanced" => "veryfast".to_string(),
@@ -918,6 +1014,20 @@ mod tests {
     }
 
 
+    #[test]
+    fn test_pipe_inputs_not_wallclock_stamped() {
+        let builder = FfmpegCommandBuilder::new("audio_%03d.mkv".to_string())
+            .with_mode(CommandMode::AudioOnly)
+            .with_audio_source(Some("Mic".to_string()))
+            .with_system_audio(true)
+            .with_audio_input_config(48000, Some(48000), Some(1), Some(2));
+        let args = builder.build();
+
+        // Raw f32le pipes are sample-accurate; wallclock stamping would re-introduce jitter
+        assert!(args.contains(&MIC_AUDIO_PIPE_NAME.to_string()));
+        assert!(!args.contains(&"-use_wallclock_as_timestamps".to_string()));
+    }
+
     #[test]
     fn test_builder_video_only() {
         let builder = FfmpegCommandBuilder::new("video_%03d.ts".to_string())
@@ -1030,4 +1140,112 @@ mod tests {
         let pix_fmt_idx_sw = args_sw.iter().position(|r| r == "-pix_fmt").unwrap();
         assert_eq!(args_sw[pix_fmt_idx_sw + 1], "yuv420p", "Software encoder should use yuv420p pix_fmt");
     }
+
+    #[test]
+    fn test_region_capture_offsets_ddagrab() {
+        let args = FfmpegCommandBuilder::new("output.mkv".to_string())
+            .with_mode(CommandMode::VideoOnly)
+            .with_monitor_index(1)
+            .with_video_size("1280x720".to_string())
+            .with_region_offset(Some((320, 180)))
+            .build();
+
+        assert!(args.contains(&"ddagrab=output_idx=1:video_size=1280x720:offset_x=320:offset_y=180".to_string()));
+    }
+
+    #[test]
+    fn test_window_capture_uses_gdigrab() {
+        let args = FfmpegCommandBuilder::new("output.mkv".to_string())
+            .with_mode(CommandMode::VideoOnly)
+            .with_video_codec("h264_nvenc".to_string())
+            .with_scaling_mode(HardwareScalingMode::D3D11)
+            .with_resolution(Some("1280x720".to_string()))
+            .with_window_capture(Some("Counter-Strike 2".to_string()))
+            .build();
+
+        let input = args.iter().position(|a| a == "-i").unwrap();
+        assert_eq!(args[input + 1], "title=Counter-Strike 2");
+        assert!(args.contains(&"gdigrab".to_string()));
+        assert!(!args.iter().any(|a| a.contains("ddagrab")));
+
+        // Software frames: no D3D11 scaler, no hwdownload, no d3d11 pix_fmt
+        let vf = args.iter().position(|a| a == "-vf").unwrap();
+        assert_eq!(args[vf + 1], "scale=1280:720,format=nv12");
+        let pix_fmt = args.iter().position(|a| a == "-pix_fmt").unwrap();
+        assert_eq!(args[pix_fmt + 1], "nv12");
+    }
+
+    #[test]
+    fn test_sanitize_preset_hevc_av1() {
+        assert_eq!(FfmpegCommandBuilder::sanitize_preset("hevc_nvenc", "quality"), "p6");
+        assert_eq!(FfmpegCommandBuilder::sanitize_preset("av1_amf", "p2"), "speed");
+        assert_eq!(FfmpegCommandBuilder::sanitize_preset("hevc_qsv", "balanced"), "medium");
+        assert_eq!(FfmpegCommandBuilder::sanitize_preset("libx265", "p4"), "veryfast");
+        assert_eq!(FfmpegCommandBuilder::sanitize_preset("libsvtav1", "p4"), "10");
+        assert_eq!(FfmpegCommandBuilder::sanitize_preset("libsvtav1", "veryfast"), "12");
+        assert_eq!(FfmpegCommandBuilder::sanitize_preset("libsvtav1", "6"), "6");
+        assert_eq!(FfmpegCommandBuilder::sanitize_preset("libsvtav1", "20"), "10");
+    }
+
+    fn arg_after<'a>(args: &'a [String], flag: &str) -> Option<&'a str> {
+        args.iter().position(|a| a == flag).map(|i| args[i + 1].as_str())
+    }
+
+    #[test]
+    fn test_hevc_and_av1_hardware_rate_control() {
+        for codec in ["hevc_nvenc", "av1_nvenc"] {
+            let args = FfmpegCommandBuilder::new("output.mkv".to_string())
+                .with_mode(CommandMode::VideoOnly)
+                .with_video_codec(codec.to_string())
+                .with_bitrate("20M".to_string())
+                .build();
+            assert_eq!(arg_after(&args, "-rc"), Some("vbr"));
+            assert_eq!(arg_after(&args, "-profile:v"), Some("main"), "{} has no high profile", codec);
+            assert_eq!(arg_after(&args, "-pix_fmt"), Some("d3d11"));
+        }
+
+        let args = FfmpegCommandBuilder::new("output.mkv".to_string())
+            .with_video_codec("h264_nvenc".to_string())
+            .build();
+        assert_eq!(arg_after(&args, "-profile:v"), Some("high"));
+    }
+
+    #[test]
+    fn test_software_hevc_and_av1() {
+        let x265 = FfmpegCommandBuilder::new("output.mkv".to_string())
+            .with_mode(CommandMode::VideoOnly)
+            .with_video_codec("libx265".to_string())
+            .build();
+        assert_eq!(arg_after(&x265, "-pix_fmt"), Some("yuv420p"));
+        assert_eq!(arg_after(&x265, "-tune"), Some("zerolatency"));
+        assert!(x265.iter().any(|a| a.contains("hwdownload")), "software encoders need system memory frames");
+
+        let svt = FfmpegCommandBuilder::new("output.mkv".to_string())
+            .with_mode(CommandMode::VideoOnly)
+            .with_video_codec("libsvtav1".to_string())
+            .build();
+        assert_eq!(arg_after(&svt, "-pix_fmt"), Some("yuv420p"));
+        assert_eq!(arg_after(&svt, "-preset"), Some("10"));
+        assert!(!svt.contains(&"-tune".to_string()));
+        assert!(!svt.contains(&"-profile:v".to_string()));
+    }
+
+    #[test]
+    fn test_benchmark_encodes_synthetic_input_to_null() {
+        let args = FfmpegCommandBuilder::new(String::new())
+            .with_mode(CommandMode::Benchmark(4))
+            .with_video_codec("h264_nvenc".to_string())
+            .with_preset(Some("speed".to_string()))
+            .with_framerate(144)
+            .with_resolution(Some("1920x1080".to_string()))
+            .build();
+        assert_eq!(args[0], "-benchmark");
+        assert_eq!(arg_after(&args, "-i"), Some("testsrc2=size=1920x1080:rate=144"));
+        assert_eq!(arg_after(&args, "-t"), Some("4"));
+        // lavfi frames are in system memory, like gdigrab's
+        assert_eq!(arg_after(&args, "-pix_fmt"), Some("nv12"));
+        assert_eq!(arg_after(&args, "-preset"), Some("p2"));
+        assert!(args.ends_with(&["-f".to_string(), "null".to_string(), "-".to_string()]));
+        assert!(!args.iter().any(|a| a.contains("ddagrab") || a.contains("segment")));
+    }
 }
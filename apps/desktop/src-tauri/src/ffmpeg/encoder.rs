@@ -1,51 +1,127 @@
+use serde::{Deserialize, Serialize};
 use std::process::Command;
 use std::os::windows::process::CommandExt;
 
-#[derive(Debug, Clone, PartialEq)]
+/// Encoder backend (which hardware or library does the work).
+#[derive(Debug, Clone, Copy, PartialEq)]
 pub enum VideoEncoder {
     Nvenc,
     Amf,
     Qsv,
     Vaapi,
-    X264,
+    Software,
+}
+
+/// Codec family, independent of the backend.
+#[derive(Debug, Clone, Copy, PartialEq, Default, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum VideoCodec {
+    #[default]
+    #[serde(alias = "avc")]
+    H264,
+    #[serde(alias = "h265")]
+    Hevc,
+    Av1,
+}
+
+impl VideoCodec {
+    /// Family of an FFmpeg encoder name (`hevc_nvenc` -> Hevc, `libsvtav1` -> Av1).
+    pub fn of_ffmpeg_codec(codec: &str) -> Self {
+        if codec.starts_with("hevc_") || codec == "libx265" {
+            VideoCodec::Hevc
+        } else if codec.starts_with("av1_") || codec == "libsvtav1" {
+            VideoCodec::Av1
+        } else {
+            VideoCodec::H264
+        }
+    }
 }
 
+const BACKENDS: [VideoEncoder; 5] = [VideoEncoder::Nvenc, VideoEncoder::Amf, VideoEncoder::Qsv, VideoEncoder::Vaapi, VideoEncoder::Software];
+const CODECS: [VideoCodec; 3] = [VideoCodec::H264, VideoCodec::Hevc, VideoCodec::Av1];
+
 impl VideoEncoder {
-    pub fn as_ffmpeg_codec(&self) -> &'static str {
-        match self {
-            VideoEncoder::Nvenc => "h264_nvenc",
-            VideoEncoder::Amf => "h264_amf",
-            VideoEncoder::Qsv => "h264_qsv",
-            VideoEncoder::Vaapi => "h264_vaapi",
-            VideoEncoder::X264 => "libx264",
+    /// FFmpeg encoder name for this backend and codec family.
+    pub fn ffmpeg_codec(&self, codec: VideoCodec) -> &'static str {
+        match (self, codec) {
+            (VideoEncoder::Nvenc, VideoCodec::H264) => "h264_nvenc",
+            (VideoEncoder::Nvenc, VideoCodec::Hevc) => "hevc_nvenc",
+            (VideoEncoder::Nvenc, VideoCodec::Av1) => "av1_nvenc",
+            (VideoEncoder::Amf, VideoCodec::H264) => "h264_amf",
+            (VideoEncoder::Amf, VideoCodec::Hevc) => "hevc_amf",
+            (VideoEncoder::Amf, VideoCodec::Av1) => "av1_amf",
+            (VideoEncoder::Qsv, VideoCodec::H264) => "h264_qsv",
+            (VideoEncoder::Qsv, VideoCodec::Hevc) => "hevc_qsv",
+            (VideoEncoder::Qsv, VideoCodec::Av1) => "av1_qsv",
+            (VideoEncoder::Vaapi, VideoCodec::H264) => "h264_vaapi",
+            (VideoEncoder::Vaapi, VideoCodec::Hevc) => "hevc_vaapi",
+            (VideoEncoder::Vaapi, VideoCodec::Av1) => "av1_vaapi",
+            (VideoEncoder::Software, VideoCodec::H264) => "libx264",
+            (VideoEncoder::Software, VideoCodec::Hevc) => "libx265",
+            (VideoEncoder::Software, VideoCodec::Av1) => "libsvtav1",
         }
     }
+
+    /// H.264 encoder name, kept for callers that predate codec families.
+    pub fn as_ffmpeg_codec(&self) -> &'static str {
+        self.ffmpeg_codec(VideoCodec::H264)
+    }
+
+    /// Inverse of [VideoEncoder::ffmpeg_codec] for config values like "hevc_nvenc".
+    pub fn from_ffmpeg_codec(name: &str) -> Option<(VideoEncoder, VideoCodec)> {
+        BACKENDS.iter()
+            .flat_map(|b| CODECS.iter().map(move |c| (*b, *c)))
+            .find(|(b, c)| b.ffmpeg_codec(*c) == name)
+    }
+
+    pub fn is_hardware(&self) -> bool {
+        *self != VideoEncoder::Software
+    }
 }
 
 use tauri::AppHandle;
 
-pub fn get_best_encoder(app: &AppHandle) -> VideoEncoder {
+/// Best working encoder for `codec`. See [pick_encoder] for the fallback order.
+pub fn get_best_encoder(app: &AppHandle, codec: VideoCodec) -> (VideoEncoder, VideoCodec) {
     let available = get_available_encoders(app);
-    
-    // Priority list
-    if available.contains(&VideoEncoder::Nvenc) {
-        return VideoEncoder::Nvenc;
-    }
-    if available.contains(&VideoEncoder::Amf) {
-        return VideoEncoder::Amf;
+    pick_encoder(&available, codec)
+}
+
+/// Hardware in the requested family, then hardware H.264 (software HEVC/AV1 is too heavy to run
+/// next to a game when a GPU encoder exists), then software in the requested family.
+pub fn pick_encoder(available: &[(VideoEncoder, VideoCodec)], codec: VideoCodec) -> (VideoEncoder, VideoCodec) {
+    // Priority list (VAAPI is often tricky on non-Linux or without specific setup, but we include it)
+    let hardware = [VideoEncoder::Nvenc, VideoEncoder::Amf, VideoEncoder::Qsv, VideoEncoder::Vaapi];
+
+    if let Some(backend) = hardware.iter().find(|b| available.contains(&(**b, codec))) {
+        return (*backend, codec);
     }
-    if available.contains(&VideoEncoder::Qsv) {
-        return VideoEncoder::Qsv;
+    if codec != VideoCodec::H264 {
+        if let Some(backend) = hardware.iter().find(|b| available.contains(&(**b, VideoCodec::H264))) {
+            log::warn!("No hardware {:?} encoder available. Falling back to {:?} H.264.", codec, backend);
+            return (*backend, VideoCodec::H264);
+        }
     }
-    // VAAPI is often tricky on non-Linux or without specific setup, but we include it
-    if available.contains(&VideoEncoder::Vaapi) {
-        return VideoEncoder::Vaapi;
+    if available.contains(&(VideoEncoder::Software, codec)) {
+        return (VideoEncoder::Software, codec);
     }
+    (VideoEncoder::Software, VideoCodec::H264)
+}
 
-    VideoEncoder::X264
+/// Encoder names from `ffmpeg -encoders` (second column of the video encoder lines).
+pub fn parse_encoder_list(output: &str) -> Vec<String> {
+    output.lines()
+        .filter_map(|line| {
+            let mut parts = line.split_whitespace();
+            let flags = parts.next()?;
+            let name = parts.next()?;
+            let is_name = name.chars().all(|c| c.is_ascii_alphanumeric() || c == '_');
+            (flags.len() == 6 && flags.starts_with('V') && is_name).then(|| name.to_string())
+        })
+        .collect()
 }
 
-fn get_available_encoders(app: &AppHandle) -> Vec<VideoEncoder> {
+pub fn get_available_encoders(app: &AppHandle) -> Vec<(VideoEncoder, VideoCodec)> {
     let mut encoders = Vec::new();
     
     // We try to run "ffmpeg -encoders" and parse the output.
@@ -59,37 +135,37 @@ fn get_available_encoders(app: &AppHandle) -> Vec<VideoEncoder> {
         .output() 
     {
         Ok(o) => o,
-        Err(_) => return vec![VideoEncoder::X264], // Fallback if we can't run ffmpeg
+        Err(_) => return vec![(VideoEncoder::Software, VideoCodec::H264)], // Fallback if we can't run ffmpeg
     };
 
-    let stdout = String::from_utf8_lossy(&output.stdout);
-    
-    // Helper to check and probe
-    let mut check_and_add = |name: &str, encoder: VideoEncoder| {
-        if stdout.contains(name) {
-            if probe_encoder(&ffmpeg_path, &encoder) {
-                encoders.push(encoder);
+    let listed = parse_encoder_list(&String::from_utf8_lossy(&output.stdout));
+
+    for backend in BACKENDS {
+        for codec in CODECS {
+            let name = backend.ffmpeg_codec(codec);
+            // libx264 is always assumed to work (Software fallback)
+            if backend == VideoEncoder::Software && codec == VideoCodec::H264 {
+                continue;
+            }
+            if !listed.iter().any(|l| l == name) {
+                continue;
+            }
+            if probe_encoder(&ffmpeg_path, name) {
+                encoders.push((backend, codec));
             } else {
                 log::warn!("Encoder {} is present but failed probe (driver/hardware missing?)", name);
             }
         }
-    };
-
-    check_and_add("h264_nvenc", VideoEncoder::Nvenc);
-    check_and_add("h264_amf", VideoEncoder::Amf);
-    check_and_add("h264_qsv", VideoEncoder::Qsv);
-    check_and_add("h264_vaapi", VideoEncoder::Vaapi);
+    }
     
-    encoders.push(VideoEncoder::X264); // Always supported (Software)
+    encoders.push((VideoEncoder::Software, VideoCodec::H264)); // Always supported (Software)
     encoders
 }
 
-fn probe_encoder(ffmpeg_path: &std::path::PathBuf, encoder: &VideoEncoder) -> bool {
+fn probe_encoder(ffmpeg_path: &std::path::PathBuf, codec: &str) -> bool {
     // Run a dummy encoding: 1 frame of black video
     // ffmpeg -y -f lavfi -i color=c=black:s=128x128 -frames:v 1 -c:v <encoder> -f null -
     
-    let codec = encoder.as_ffmpeg_codec();
-    
     let output = Command::new(ffmpeg_path)
         .args([
             "-y",
@@ -128,7 +204,52 @@ mod tests {
     #[test]
     fn test_encoder_to_codec() {
         assert_eq!(VideoEncoder::Nvenc.as_ffmpeg_codec(), "h264_nvenc");
-        assert_eq!(VideoEncoder::X264.as_ffmpeg_codec(), "libx264");
+        assert_eq!(VideoEncoder::Software.as_ffmpeg_codec(), "libx264");
+        assert_eq!(VideoEncoder::Amf.ffmpeg_codec(VideoCodec::Hevc), "hevc_amf");
+        assert_eq!(VideoEncoder::Qsv.ffmpeg_codec(VideoCodec::Av1), "av1_qsv");
+        assert_eq!(VideoEncoder::Software.ffmpeg_codec(VideoCodec::Hevc), "libx265");
+        assert_eq!(VideoEncoder::Software.ffmpeg_codec(VideoCodec::Av1), "libsvtav1");
+    }
+
+    #[test]
+    fn test_codec_name_roundtrip() {
+        for backend in BACKENDS {
+            for codec in CODECS {
+                let name = backend.ffmpeg_codec(codec);
+                assert_eq!(VideoEncoder::from_ffmpeg_codec(name), Some((backend, codec)));
+                assert_eq!(VideoCodec::of_ffmpeg_codec(name), codec);
+            }
+        }
+        assert_eq!(VideoEncoder::from_ffmpeg_codec("mpeg4"), None);
+    }
+
+    #[test]
+    fn test_parse_encoder_list() {
+        let output = " V....D libx264              libx264 H.264 / AVC / MPEG-4 AVC (codec h264)
+ V....D libx265              libx265 H.265 / HEVC (codec hevc)
+ V....D libsvtav1            SVT-AV1(Scalable Video Technology for AV1) encoder (codec av1)
+ V....D hevc_nvenc           NVIDIA NVENC hevc encoder (codec hevc)
+ A....D aac                  AAC (Advanced Audio Coding)
+ ------
+ V..... = Video";
+        assert_eq!(parse_encoder_list(output), vec!["libx264", "libx265", "libsvtav1", "hevc_nvenc"]);
+    }
+
+    #[test]
+    fn test_pick_encoder() {
+        use VideoCodec::*;
+        use VideoEncoder::*;
+
+        let gpu = [(Nvenc, H264), (Nvenc, Hevc), (Software, Hevc), (Software, H264)];
+        assert_eq!(pick_encoder(&gpu, Hevc), (Nvenc, Hevc));
+        // No AV1 on this GPU: stay on hardware rather than encode AV1 on the CPU
+        assert_eq!(pick_encoder(&gpu, Av1), (Nvenc, H264));
+
+        // CPU-only box
+        let cpu = [(Software, Hevc), (Software, Av1), (Software, H264)];
+        assert_eq!(pick_encoder(&cpu, Av1), (Software, Av1));
+        assert_eq!(pick_encoder(&cpu, H264), (Software, H264));
+        assert_eq!(pick_encoder(&[(Software, H264)], Hevc), (Software, H264));
     }
 }
 
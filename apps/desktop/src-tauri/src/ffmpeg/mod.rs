@@ -8,12 +8,18 @@
 //! * `session`: Manages the actual FFmpeg child process, including spawning, monitoring, and cleanup.
 //! * `commands`: Builder pattern for constructing complex FFmpeg CLI arguments.
 //! * `monitor`: Parses FFmpeg stderr output to track recording status (bitrate, time, etc.).
+//! * `capture`: Capture targets (monitor by stable identity, window, region) and DXGI output lookup.
+//! * `segment_index`: NTP-corrected start time of every buffer segment, recorded as FFmpeg opens it.
 //! * `encoder`: Handles hardware encoder detection and selection.
+//! * `benchmark`: Measures which quality tier each encoder sustains and recommends a profile.
 //! * `utils`: Shared utility functions.
 
 pub mod process;
+pub mod benchmark;
+pub mod capture;
 pub mod commands;
 pub mod encoder;
 pub mod monitor;
+pub mod segment_index;
 pub mod session;
 pub mod utils;
@@ -1,10 +1,13 @@
 use tauri_plugin_shell::process::CommandEvent;
 use tokio::sync::mpsc::Receiver;
 
+use super::segment_index::{opened_segment, SegmentRecorder};
+
 pub struct FfmpegMonitor;
 
 impl FfmpegMonitor {
-    pub fn start(mut rx: Receiver<CommandEvent>, target_bitrate: Option<String>, label: String) {
+    /// `segments` records the start of every segment FFmpeg opens, if given.
+    pub fn start(mut rx: Receiver<CommandEvent>, target_bitrate: Option<String>, label: String, segments: Option<SegmentRecorder>) {
         tauri::async_runtime::spawn(async move {
             let mut last_log_time = std::time::Instant::now();
             let mut first_log = true;
@@ -15,6 +18,11 @@ impl FfmpegMonitor {
                         let line_str = String::from_utf8_lossy(&line);
                         let line_string = line_str.to_string();
 
+                        // Checked first: the line may also carry progress output ending in '\r'
+                        if let (Some(recorder), Some(file)) = (&segments, opened_segment(&line_string)) {
+                            recorder.record(file);
+                        }
+
                         // Check if this is a progress line
                         // Video has "frame=", Audio usually has "size=" but no "frame="
                         // Both have "time=" and "bitrate="
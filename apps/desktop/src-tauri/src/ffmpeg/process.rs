@@ -1,15 +1,18 @@
 //! FFmpeg Process Manager
 //! 
 //! This module orchestrates the recording process. It handles:
-//! 1. Configuration resolution (resolution, bitrate, encoder).
-//! 2. Temp buffer management.
+//! 1. Configuration resolution (recording profile, capture target, resolution, bitrate, encoder).
+//! 2. Temp buffer management (kept across restarts that don't change the video format).
 //! 3. Command construction via [crate::ffmpeg::commands::FfmpegCommandBuilder].
 //! 4. Session spawning via [crate::ffmpeg::session::RecordingSession].
 
 use tauri::{AppHandle, Manager};
+use crate::commands::monitors::monitor_identity;
+use crate::ffmpeg::capture::{self, CaptureTarget, MonitorIdentity};
 use crate::ffmpeg::commands::FfmpegCommandBuilder;
-use crate::ffmpeg::encoder::{self, VideoEncoder};
-use crate::ffmpeg::session::RecordingSession;
+use crate::config::EncoderChoice;
+use crate::ffmpeg::encoder;
+use crate::ffmpeg::session::{AudioSettings, RecordingSession, RecordingSessionConfig};
 use crate::state::RecordingState;
 use crate::state::RecordingMessage;
 use std::sync::mpsc::Sender;
@@ -18,15 +21,22 @@ use crate::constants::{
     DEFAULT_WIDTH, DEFAULT_HEIGHT
 };
 
-pub async fn start_recording_process(app: &AppHandle) -> Result<(Sender<RecordingMessage>, std::thread::JoinHandle<()>), String> {
+/// Starts a session. With `keep_segments` the buffer from the previous session is kept, which
+/// is only right when the new video segments have the same format (see [crate::config::ConfigDiff]).
+pub async fn start_recording_process(app: &AppHandle, keep_segments: bool) -> Result<(Sender<RecordingMessage>, std::thread::JoinHandle<()>), String> {
     let state = app.state::<RecordingState>();
-    let config = state.config.lock().map_err(|e| e.to_string())?.clone(); 
+    let game = state.active_game.lock().map_err(|e| e.to_string())?.clone();
+    // `[recording]` with the profile for the detected game (or the active profile) applied
+    let config = state.config.lock().map_err(|e| e.to_string())?.effective(game.as_deref());
+    if let Some(profile) = config.profile_for(game.as_deref()) {
+        println!("Recording profile: {}", profile.name);
+    }
 
     // 1. Determine Output Path (Temp Buffer)
     let temp_path_str = config.recording.temp_path.replace("%TEMP%", &std::env::temp_dir().to_string_lossy());
     let buffer_dir = std::path::PathBuf::from(temp_path_str);
     
-    if buffer_dir.exists() {
+    if buffer_dir.exists() && !keep_segments {
         let _ = std::fs::remove_dir_all(&buffer_dir);
     }
     std::fs::create_dir_all(&buffer_dir).map_err(|e| e.to_string())?;
@@ -41,9 +51,8 @@ pub async fn start_recording_process(app: &AppHandle) -> Result<(Sender<Recordin
 
     println!("Buffer Dir: {:?}", buffer_dir);
     // Patterns must match what session.rs uses (strftime format)
-    // Added %03u for millisecond precision
-    let video_pattern = buffer_dir.join("video_%Y%m%d%H%M%S%03u.mkv").to_string_lossy().to_string();
-    let audio_pattern = buffer_dir.join("audio_%Y%m%d%H%M%S%03u.mkv").to_string_lossy().to_string();
+    let video_pattern = crate::ffmpeg::utils::segment_pattern(&buffer_dir, "video").to_string_lossy().to_string();
+    let audio_pattern = crate::ffmpeg::utils::segment_pattern(&buffer_dir, "audio").to_string_lossy().to_string();
 
     println!("Buffer Dir: {:?}", buffer_dir);
     println!("Video Pattern: {}", video_pattern);
@@ -51,36 +60,43 @@ pub async fn start_recording_process(app: &AppHandle) -> Result<(Sender<Recordin
     println!("Wrap Limit: {}", wrap_limit);
 
     // 2. Select Encoder
-    let encoder = if config.recording.encoder == "auto" {
-        encoder::get_best_encoder(app)
-    } else {
-        match config.recording.encoder.as_str() {
-            "h264_nvenc" => VideoEncoder::Nvenc,
-            "h264_amf" => VideoEncoder::Amf,
-            "h264_qsv" => VideoEncoder::Qsv,
-            "h264_vaapi" => VideoEncoder::Vaapi,
-            _ => VideoEncoder::X264,
-        }
+    let (encoder, codec) = match config.recording.encoder {
+        EncoderChoice::Auto => encoder::get_best_encoder(app, config.recording.video_codec.unwrap_or_default()),
+        // Explicit FFmpeg encoder name, e.g. "hevc_nvenc" or "libsvtav1"
+        EncoderChoice::Fixed(encoder, codec) => (encoder, codec),
     };
-    println!("Selected encoder: {:?}", encoder);
+    println!("Selected encoder: {:?} {:?} ({})", encoder, codec, encoder.ffmpeg_codec(codec));
 
-    // 3. Get Monitor Info
+    // 3. Resolve Capture Target (monitor by identity, window, or region)
+    let capture_target = config.recording.capture_target.clone().unwrap_or_default();
     let (monitor, actual_monitor_index) = if let Some(window) = app.get_webview_window("main") {
         let monitors = window.available_monitors().unwrap_or_default();
-        let requested_index_opt = config.recording.monitor_index;
 
-        if let Some(idx) = requested_index_opt {
+        if let Some(wanted) = capture_target.monitor() {
+            let identities: Vec<MonitorIdentity> = monitors.iter().map(monitor_identity).collect();
+            match capture::select_monitor(&identities, wanted) {
+                Some(idx) => {
+                    println!("Configured monitor {} found at index {}.", wanted.name, idx);
+                    (Some(monitors[idx].clone()), Some(idx as u32))
+                }
+                None => {
+                    println!("Configured monitor {} ({}x{} at {},{}) not connected. Falling back to Primary.",
+                        wanted.name, wanted.width, wanted.height, wanted.x, wanted.y);
+                    find_primary_monitor(&window, &monitors)
+                }
+            }
+        } else if let Some(idx) = config.recording.monitor_index.filter(|_| config.recording.capture_target.is_none()) {
+            // Legacy configs: list index
             let idx_usize = idx as usize;
             if idx_usize < monitors.len() {
                 println!("Requested monitor index {} is valid.", idx);
                 (Some(monitors[idx_usize].clone()), Some(idx))
             } else {
                 println!("Requested monitor index {} is invalid. Falling back to Primary.", idx);
-                // Fallback logic below
                 find_primary_monitor(&window, &monitors)
             }
         } else {
-            println!("No monitor index configured (Auto). Defaulting to Primary.");
+            println!("No monitor configured (Auto). Defaulting to Primary.");
             find_primary_monitor(&window, &monitors)
         }
     } else {
@@ -88,73 +104,63 @@ pub async fn start_recording_process(app: &AppHandle) -> Result<(Sender<Recordin
         (None, None)
     };
 
-
-
-    // Fix: ddagrab uses DXGI indices which usually match the Display Number (DISPLAY1 -> 0)
-    // Tauri's list index is arbitrary. We must parse the name to get the correct index.
+    // ddagrab's output_idx is the DXGI output index, which is neither Tauri's list index nor
+    // guaranteed to match the display number. Look it up by desktop coordinates.
     let ddagrab_index = if let Some(m) = &monitor {
-        if let Some(name) = m.name() {
-            println!("Inspecting monitor name for index: {}", name);
-            if let Some(idx) = name.find("DISPLAY") {
-                let num_part = &name[idx + 7..];
-                let num_str: String = num_part.chars().take_while(|c| c.is_ascii_digit()).collect();
-                if let Ok(num) = num_str.parse::<u32>() {
-                    if num > 0 {
-                        println!("Parsed ddagrab index {} from name {}", num - 1, name);
-                        num - 1
-                    } else {
-                        println!("Parsed invalid display number 0. Fallback to list index.");
-                        actual_monitor_index.unwrap_or(0)
-                    }
-                } else {
-                    println!("Failed to parse number from {}. Fallback to list index.", name);
-                    actual_monitor_index.unwrap_or(0)
-                }
-            } else {
-                println!("'DISPLAY' not found in name. Fallback to list index.");
-                actual_monitor_index.unwrap_or(0)
-            }
-        } else {
-            actual_monitor_index.unwrap_or(0)
-        }
+        let identity = monitor_identity(m);
+        capture::ddagrab_output_index(&identity)
+            .or_else(|| {
+                println!("No DXGI output matches {} at {},{}. Falling back to display number.", identity.name, identity.x, identity.y);
+                capture::parse_display_index(&identity.name)
+            })
+            .unwrap_or_else(|| actual_monitor_index.unwrap_or(0))
     } else {
         0
     };
+    println!("Using ddagrab output index {}", ddagrab_index);
+
+    let capture_window = match &capture_target {
+        CaptureTarget::Window { title, process } => {
+            let found = capture::find_window(title.as_deref(), process.as_deref());
+            match &found {
+                Some(w) => println!("Capturing window '{}' ({}x{})", w.title, w.width, w.height),
+                None => println!("No window matches title {:?} / process {:?}. Recording the monitor instead.", title, process),
+            }
+            found
+        }
+        _ => None,
+    };
 
-    let (width, height, _x, _y) = if let Some(m) = &monitor {
+    let (mut width, mut height) = if let Some(m) = &monitor {
         let size = m.size();
-        let pos = m.position();
-        (size.width, size.height, pos.x, pos.y)
+        (size.width, size.height)
     } else {
-        (DEFAULT_WIDTH, DEFAULT_HEIGHT, 0, 0)
+        (DEFAULT_WIDTH, DEFAULT_HEIGHT)
     };
 
+    let mut region_offset = None;
+    if let Some(w) = &capture_window {
+        width = w.width & !1;
+        height = w.height & !1;
+    } else if let CaptureTarget::Region { x, y, width: region_width, height: region_height, .. } = &capture_target {
+        match capture::clamp_region(width, height, *x, *y, *region_width, *region_height) {
+            Some((offset_x, offset_y, w, h)) => {
+                println!("Capturing region {}x{} at {},{}", w, h, offset_x, offset_y);
+                region_offset = Some((offset_x, offset_y));
+                width = w;
+                height = h;
+            }
+            None => println!("Capture region lies outside the monitor. Recording the whole monitor."),
+        }
+    }
+
     // 4. Smart Resolution & Bitrate Logic
     let scaling_mode = encoder::get_best_scaling_mode(app);
 
-    let (target_width, target_height, use_scaler) = if let Some(res_str) = &config.recording.resolution {
-        if res_str.to_lowercase() == "native" {
-            (width, height, false)
-        } else {
-            let parts: Vec<&str> = res_str.split('x').collect();
-            if parts.len() == 2 {
-                let w = parts[0].parse::<u32>().unwrap_or(width);
-                let h = parts[1].parse::<u32>().unwrap_or(height);
-                if w == width && h == height {
-                    (width, height, false)
-                } else {
-                    (w, h, true)
-                }
-            } else {
-                (width, height, false)
-            }
-        }
-    } else {
-        (width, height, false)
-    };
+    let (target_width, target_height, use_scaler) = config.recording.resolution.target(width, height);
 
-    let bitrate = if let Some(b) = &config.recording.bitrate {
-        b.clone()
+    let bitrate = if let Some(b) = config.recording.bitrate {
+        b.to_string()
     } else {
         // Dynamic Bitrate: (Pixels * FPS) / 10 -> 0.1 bits per pixel
         // See [crate::ffmpeg::utils::calculate_dynamic_bitrate]
@@ -165,14 +171,12 @@ pub async fn start_recording_process(app: &AppHandle) -> Result<(Sender<Recordin
         target_width, target_height, config.recording.framerate, bitrate, use_scaler);
     println!("Scaling Mode: {:?}", scaling_mode);
 
-    let system_audio_device = config.recording.system_audio_device.clone();
-    let system_audio_enabled = system_audio_device.is_some();
-    let system_sample_rate = DEFAULT_AUDIO_SAMPLE_RATE; 
+    let audio = AudioSettings::from_config(&config.recording);
     
     // 6. Build Command
     let builder = FfmpegCommandBuilder::new(output_pattern)
         .with_scaling_mode(scaling_mode)
-        .with_video_codec(encoder.as_ffmpeg_codec().to_string())
+        .with_video_codec(encoder.ffmpeg_codec(codec).to_string())
         .with_preset(config.recording.video_preset.clone())
         .with_tune(config.recording.video_tune.clone())
         .with_profile(config.recording.video_profile.clone())
@@ -181,28 +185,20 @@ pub async fn start_recording_process(app: &AppHandle) -> Result<(Sender<Recordin
         .with_resolution(if use_scaler { Some(format!("{}x{}", target_width, target_height)) } else { None })
         .with_video_size(format!("{}x{}", width, height))
         .with_monitor_index(ddagrab_index)
-        .with_audio_source(config.recording.audio_source.clone())
-        .with_system_audio(system_audio_enabled)
-        .with_audio_input_config(system_sample_rate, None, None, None)
-        .with_audio_output_config(Some("pcm_s16le".to_string()), config.recording.audio_bitrate.clone(), DEFAULT_AUDIO_SAMPLE_RATE, DEFAULT_AUDIO_CHANNELS)
-        .with_audio_backend(config.recording.audio_backend.clone())
+        .with_region_offset(region_offset)
+        .with_window_capture(capture_window.map(|w| w.title))
+        .with_audio_source(audio.audio_source.clone())
+        .with_system_audio(audio.system_audio_device.is_some())
+        .with_audio_input_config(audio.system_sample_rate, None, None, None)
+        .with_audio_output_config(audio.audio_codec.clone(), audio.audio_bitrate.clone(), DEFAULT_AUDIO_SAMPLE_RATE, DEFAULT_AUDIO_CHANNELS)
+        .with_audio_backend(audio.audio_backend)
         .with_segment_config(segment_time, wrap_limit, playlist_path);
 
     // 7. Spawn Session
-    // 7. Spawn Session
-    use crate::ffmpeg::session::RecordingSessionConfig;
-    
     let session_config = RecordingSessionConfig {
-        audio_source: config.recording.audio_source,
-        system_audio_enabled,
-        system_sample_rate,
-        system_audio_device,
-        audio_codec: Some("pcm_s16le".to_string()),
-        audio_bitrate: config.recording.audio_bitrate,
+        audio,
         video_bitrate: bitrate,
         buffer_dir,
-        retention_seconds: config.recording.buffer_retention_seconds,
-        audio_backend: config.recording.audio_backend,
     };
 
     RecordingSession::spawn(
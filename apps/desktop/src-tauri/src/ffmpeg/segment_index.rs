@@ -0,0 +1,174 @@
+//! Sidecar index of buffer segment start times (`segments.jsonl` next to the segments).
+//!
+//! FFmpeg logs `Opening '<segment>' for writing` the moment it starts a segment. The monitor
+//! stamps that line with NTP time and appends an entry, which beats the file name (whole
+//! seconds of system time) and ffprobe (0, because of `-reset_timestamps 1`).
+
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::io::Write;
+use std::path::{Path, PathBuf};
+use std::sync::{Arc, Mutex};
+
+use crate::constants::SEGMENT_INDEX_FILE;
+use crate::ntp::NtpManager;
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct SegmentEntry {
+    /// Segment file name (no directory)
+    pub file: String,
+    /// NTP-corrected start, Unix microseconds
+    pub start_us: i64,
+    /// NTP minus system clock when the segment started, microseconds
+    pub offset_us: i64,
+    /// Clock uncertainty at the time, None if the clock was never synced
+    pub uncertainty_ms: Option<f64>,
+    pub drift_ppm: f64,
+    /// NTP start of the FFmpeg run that wrote the segment, Unix microseconds. Segments of
+    /// different runs aren't contiguous. 0 for entries written before this was recorded.
+    #[serde(default)]
+    pub run_start_us: i64,
+}
+
+impl SegmentEntry {
+    pub fn start_ms(&self) -> u64 {
+        self.start_us.max(0) as u64 / 1000
+    }
+
+    /// Start in the system clock, the time base of the segment file names.
+    pub fn system_start_ms(&self) -> u64 {
+        (self.start_us - self.offset_us).max(0) as u64 / 1000
+    }
+}
+
+/// The segment file FFmpeg just opened, from one line of its log.
+pub fn opened_segment(line: &str) -> Option<&str> {
+    let start = line.find("Opening '")? + "Opening '".len();
+    let end = start + line[start..].find("' for writing")?;
+    let name = line[start..end].rsplit(['/', '\\']).next()?;
+    let is_segment = (name.starts_with("video_") || name.starts_with("audio_")) && name.ends_with(".mkv");
+    is_segment.then_some(name)
+}
+
+/// Serializes appends (monitor tasks) with pruning (session cleanup).
+static INDEX_LOCK: Mutex<()> = Mutex::new(());
+
+pub struct SegmentIndex {
+    path: PathBuf,
+}
+
+impl SegmentIndex {
+    pub fn new(buffer_dir: &Path) -> Self {
+        Self { path: buffer_dir.join(SEGMENT_INDEX_FILE) }
+    }
+
+    /// Appends one entry. Append-only, so a crash loses at most the line being written.
+    pub fn append(&self, entry: &SegmentEntry) -> Result<(), String> {
+        let line = serde_json::to_string(entry).map_err(|e| e.to_string())?;
+        let _guard = INDEX_LOCK.lock().unwrap_or_else(|e| e.into_inner());
+        let mut file = std::fs::OpenOptions::new()
+            .create(true)
+            .append(true)
+            .open(&self.path)
+            .map_err(|e| format!("Failed to open segment index: {}", e))?;
+        writeln!(file, "{}", line).map_err(|e| format!("Failed to write segment index: {}", e))
+    }
+
+    /// Entries by file name. Unreadable lines are skipped; a later entry for the same file wins.
+    pub fn load(&self) -> HashMap<String, SegmentEntry> {
+        let Ok(content) = std::fs::read_to_string(&self.path) else {
+            return HashMap::new();
+        };
+        content
+            .lines()
+            .filter_map(|line| serde_json::from_str::<SegmentEntry>(line).ok())
+            .map(|entry| (entry.file.clone(), entry))
+            .collect()
+    }
+
+    /// Drops entries whose segment has been deleted.
+    pub fn prune(&self) -> Result<(), String> {
+        let Some(dir) = self.path.parent() else {
+            return Ok(());
+        };
+        let _guard = INDEX_LOCK.lock().unwrap_or_else(|e| e.into_inner());
+        let mut entries: Vec<SegmentEntry> = self.load().into_values().filter(|e| dir.join(&e.file).exists()).collect();
+        entries.sort_by_key(|e| e.start_us);
+        let content: String = entries
+            .iter()
+            .filter_map(|e| serde_json::to_string(e).ok())
+            .map(|line| line + "\n")
+            .collect();
+        std::fs::write(&self.path, content).map_err(|e| format!("Failed to prune segment index: {}", e))
+    }
+}
+
+/// Stamps segments with NTP time as the monitor sees them opened. One per FFmpeg run.
+pub struct SegmentRecorder {
+    index: SegmentIndex,
+    ntp: Arc<NtpManager>,
+    run_start_us: i64,
+}
+
+impl SegmentRecorder {
+    pub fn new(buffer_dir: &Path, ntp: Arc<NtpManager>) -> Self {
+        let run_start_us = ntp.get_ntp_time_us();
+        Self { index: SegmentIndex::new(buffer_dir), ntp, run_start_us }
+    }
+
+    pub fn record(&self, file: &str) {
+        let (start_us, offset_us) = (self.ntp.get_ntp_time_us(), self.ntp.get_offset_us());
+        let (uncertainty_ms, drift_ppm) = match self.ntp.health() {
+            Ok(health) => (health.uncertainty_ms, health.drift_ppm),
+            Err(e) => {
+                log::error!("{}", e);
+                (None, 0.0)
+            }
+        };
+        let entry = SegmentEntry { file: file.to_string(), start_us, offset_us, uncertainty_ms, drift_ppm, run_start_us: self.run_start_us };
+        match self.index.append(&entry) {
+            Ok(()) => log::debug!("Segment {} started at {} us (NTP)", file, start_us),
+            Err(e) => log::warn!("{}", e),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_opened_segment() {
+        let line = r"[segment @ 000001d2c3] Opening 'C:\Users\me\AppData\Local\Temp\squad_sync\video_20231027T120000Z.mkv' for writing";
+        assert_eq!(opened_segment(line), Some("video_20231027T120000Z.mkv"));
+        let line = "[segment @ 0x55d5] Opening '/tmp/buffer/audio_20231027T120015Z.mkv' for writing";
+        assert_eq!(opened_segment(line), Some("audio_20231027T120015Z.mkv"));
+        assert_eq!(opened_segment("[segment @ 0x55d5] Opening '/tmp/buffer/video_list.m3u8.tmp' for writing"), None);
+        assert_eq!(opened_segment("frame= 123 fps= 60.0 time=00:00:10.00"), None);
+    }
+
+    #[test]
+    fn test_append_load_prune() {
+        let dir = std::env::temp_dir().join("squad_sync_test_segment_index");
+        let _ = std::fs::remove_dir_all(&dir);
+        std::fs::create_dir_all(&dir).unwrap();
+        let index = SegmentIndex::new(&dir);
+        let entry = |file: &str, start_us| SegmentEntry { file: file.into(), start_us, offset_us: 250_000, uncertainty_ms: Some(4.0), drift_ppm: 1.5, run_start_us: 0 };
+
+        std::fs::write(dir.join("video_20231027T120000Z.mkv"), b"").unwrap();
+        index.append(&entry("video_20231027T120000Z.mkv", 1_698_408_000_250_500)).unwrap();
+        index.append(&entry("video_20231027T115945Z.mkv", 1_698_407_985_250_000)).unwrap();
+        std::fs::OpenOptions::new().append(true).open(dir.join(SEGMENT_INDEX_FILE)).unwrap().write_all(b"{\"file\":").unwrap();
+
+        let loaded = index.load();
+        assert_eq!(loaded.len(), 2);
+        let first = &loaded["video_20231027T120000Z.mkv"];
+        assert_eq!(first.start_ms(), 1_698_408_000_250);
+        assert_eq!(first.system_start_ms(), 1_698_408_000_000);
+
+        // The older segment's file is gone
+        index.prune().unwrap();
+        assert_eq!(index.load().into_keys().collect::<Vec<_>>(), vec!["video_20231027T120000Z.mkv"]);
+        let _ = std::fs::remove_dir_all(&dir);
+    }
+}
@@ -178,6 +178,7 @@ pub fn check_filter_support(app: &AppHandle, filter_name: &str) -> bool {
 }
 
 #[cfg(test)]
+#[allow(clippy::items_after_test_module)]
 mod tests {
     use super::*;
 
@@ -200,29 +201,54 @@ mod tests {
 
     #[test]
     fn test_parse_segment_filename_to_epoch_ms() {
-        use chrono::{TimeZone, Local};
-        // Valid (Legacy 14 digits)
-        // Construct expected value dynamically to handle local timezone of the test runner
-        let naive = chrono::NaiveDateTime::parse_from_str("20231027120000", "%Y%m%d%H%M%S").unwrap();
-        let expected = Local.from_local_datetime(&naive).latest().unwrap().timestamp_millis() as u64;
+        // 2023-10-27T12:00:00Z
+        let expected = 1_698_408_000_000;
+        assert_eq!(parse_segment_filename_to_epoch_ms("video_20231027T120000Z.mkv").unwrap(), expected);
         
-        assert_eq!(parse_segment_filename_to_epoch_ms("video_20231027120000.mkv").unwrap(), expected);
-        
-        // Valid (New 17 digits with milliseconds)
-        // Expected = Base timestamp + 123ms
-        let expected_ms = expected + 123;
-        assert_eq!(parse_segment_filename_to_epoch_ms("video_20231027120000123.mkv").unwrap(), expected_ms);
+        // With milliseconds
+        assert_eq!(parse_segment_filename_to_epoch_ms("video_20231027T120000123Z.mkv").unwrap(), expected + 123);
 
-        // Invalid format
+        // Invalid format, including the old local-time names
         assert!(parse_segment_filename_to_epoch_ms("video_invalid.mkv").is_err());
+        assert!(parse_segment_filename_to_epoch_ms("video_20231027120000.mkv").is_err());
         
         // Invalid date
-        assert!(parse_segment_filename_to_epoch_ms("video_20239999120000.mkv").is_err());
+        assert!(parse_segment_filename_to_epoch_ms("video_20239999T120000Z.mkv").is_err());
+    }
+
+    #[test]
+    fn test_segment_names_across_dst_transitions() {
+        use chrono::{DateTime, Duration, Utc};
+
+        // EU and US spring-forward and fall-back instants: local-time names would skip or
+        // repeat an hour here
+        for transition in ["2023-03-26T01:00:00Z", "2023-10-29T01:00:00Z", "2024-03-10T07:00:00Z", "2024-11-03T06:00:00Z"] {
+            let transition: DateTime<Utc> = transition.parse().unwrap();
+            let mut previous = None;
+            for step in -8..=8 {
+                let at = transition + Duration::minutes(15 * step);
+                let name = format!("video_{}.mkv", at.format(crate::constants::SEGMENT_TIME_FORMAT));
+                let parsed = parse_segment_filename_to_epoch_ms(&name).unwrap();
+                assert_eq!(parsed, at.timestamp_millis() as u64, "{}", name);
+                assert!(previous.map_or(true, |p| parsed == p + 15 * 60 * 1000), "{}", name);
+                previous = Some(parsed);
+            }
+        }
+
+        let pattern = segment_pattern(std::path::Path::new("buffer"), "audio");
+        assert_eq!(pattern.file_name().unwrap(), "audio_%Y%m%dT%H%M%SZ.mkv");
     }
 }
 
+/// FFmpeg `-strftime` output pattern for buffer segments, e.g. `video_20231027T120000Z.mkv`.
+/// FFmpeg formats in local time, so the process must run with `TZ` set to
+/// [`crate::constants::FFMPEG_SEGMENT_TZ`] for the `Z` to hold.
+pub fn segment_pattern(dir: &std::path::Path, prefix: &str) -> PathBuf {
+    dir.join(format!("{}_{}.mkv", prefix, crate::constants::SEGMENT_TIME_FORMAT))
+}
+
 /// Parses a segment filename to extract the UTC Epoch timestamp in milliseconds.
-/// Expected format: ...YYYYMMDDHHMMSS.ext (last 14 digits before extension)
+/// Expected format: ...YYYYMMDDTHHMMSS[mmm]Z.ext (UTC, optional milliseconds)
 /// 
 /// # Arguments
 /// * `filename` - The filename to parse.
@@ -230,27 +256,20 @@ mod tests {
 /// # Returns
 /// * `Result<u64, String>` - The UTC Epoch timestamp in milliseconds.
 pub fn parse_segment_filename_to_epoch_ms(filename: &str) -> Result<u64, String> {
-    use chrono::{TimeZone, Local};
     use regex::Regex;
 
-    // Regex to find the last 14 digits (legacy) or 17 digits (new) before the extension
-    // Matches: ...YYYYMMDDHHMMSS.ext OR ...YYYYMMDDHHMMSSmmm.ext
-    // Group 1: YYYYMMDDHHMMSS (14 digits)
+    // Group 1: YYYYMMDDTHHMMSS
     // Group 2: mmm (3 digits, optional)
-    let re = Regex::new(r"(\d{14})(\d{3})?\.([a-zA-Z0-9]+)$").map_err(|e| e.to_string())?;
+    let re = Regex::new(r"(\d{8}T\d{6})(\d{3})?Z\.([a-zA-Z0-9]+)$").map_err(|e| e.to_string())?;
     
     if let Some(caps) = re.captures(filename) {
         if let Some(ts_str) = caps.get(1) {
-            let naive = chrono::NaiveDateTime::parse_from_str(ts_str.as_str(), "%Y%m%d%H%M%S")
+            let naive = chrono::NaiveDateTime::parse_from_str(ts_str.as_str(), "%Y%m%dT%H%M%S")
                 .map_err(|e| format!("Failed to parse date string '{}': {}", ts_str.as_str(), e))?;
             
-            // The filename timestamp is in LOCAL time (as per current implementation)
-            // We need to convert it to UTC Epoch.
-            // Note: This relies on the system timezone being correct.
-            let local_dt = Local.from_local_datetime(&naive).latest()
-                .ok_or_else(|| format!("Ambiguous or invalid local time: {}", ts_str.as_str()))?;
-                
-            let mut epoch_ms = local_dt.timestamp_millis() as u64;
+            // UTC: one instant per name, whatever the system timezone or DST
+            let mut epoch_ms = u64::try_from(naive.and_utc().timestamp_millis())
+                .map_err(|_| format!("Timestamp before 1970: {}", ts_str.as_str()))?;
 
             // Add milliseconds if present
             if let Some(ms_str) = caps.get(2) {
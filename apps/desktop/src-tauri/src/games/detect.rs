@@ -0,0 +1,159 @@
+//! Matching running processes against the configured game list.
+
+use serde::{Deserialize, Serialize};
+use super::process::ProcessInfo;
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct GameEntry {
+    /// Display name, tagged into saved clips
+    pub name: String,
+    /// Executable file name ("cs2.exe") or full path. Case-insensitive.
+    pub executable: String,
+    /// Recording profile to switch to while this game runs
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub profile: Option<String>,
+}
+
+impl GameEntry {
+    fn is_path(&self) -> bool {
+        self.executable.contains(['/', '\\'])
+    }
+
+    pub fn matches(&self, process: &ProcessInfo) -> bool {
+        if self.is_path() {
+            process.path.as_deref().is_some_and(|p| normalize_path(p) == normalize_path(&self.executable))
+        } else {
+            process.name.eq_ignore_ascii_case(self.executable.trim())
+        }
+    }
+}
+
+fn normalize_path(path: &str) -> String {
+    path.trim().replace('\\', "/").to_lowercase()
+}
+
+/// Whether any entry needs full process paths to match.
+pub fn needs_paths(games: &[GameEntry]) -> bool {
+    games.iter().any(GameEntry::is_path)
+}
+
+/// The first configured game that is running (list order is priority order).
+pub fn find_game<'a>(games: &'a [GameEntry], processes: &[ProcessInfo]) -> Option<&'a GameEntry> {
+    games.iter().find(|game| processes.iter().any(|p| game.matches(p)))
+}
+
+pub fn default_games() -> Vec<GameEntry> {
+    [
+        ("Counter-Strike 2", "cs2.exe"),
+        ("VALORANT", "VALORANT-Win64-Shipping.exe"),
+        ("Apex Legends", "r5apex.exe"),
+        ("Fortnite", "FortniteClient-Win64-Shipping.exe"),
+        ("Overwatch 2", "Overwatch.exe"),
+        ("League of Legends", "League of Legends.exe"),
+        ("Dota 2", "dota2.exe"),
+        ("Rocket League", "RocketLeague.exe"),
+        ("Rainbow Six Siege", "RainbowSix.exe"),
+    ]
+    .into_iter()
+    .map(|(name, executable)| GameEntry { name: name.to_string(), executable: executable.to_string(), profile: None })
+    .collect()
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum WatchEvent {
+    Started(String),
+    /// The game has been gone for the whole grace period
+    Exited(String),
+}
+
+/// Debounces detection results into start/exit transitions.
+pub struct GameWatcher {
+    grace_ms: u64,
+    current: Option<String>,
+    missing_since: Option<u64>,
+}
+
+impl GameWatcher {
+    pub fn new(grace_ms: u64) -> Self {
+        Self { grace_ms, current: None, missing_since: None }
+    }
+
+    pub fn set_grace_ms(&mut self, grace_ms: u64) {
+        self.grace_ms = grace_ms;
+    }
+
+    pub fn current(&self) -> Option<&str> {
+        self.current.as_deref()
+    }
+
+    /// Feeds one poll result. A different game showing up while one is active keeps the
+    /// first one: the session continues until no listed game is running.
+    pub fn update(&mut self, detected: Option<&str>, now_ms: u64) -> Option<WatchEvent> {
+        match (&self.current, detected) {
+            (None, Some(game)) => {
+                self.current = Some(game.to_string());
+                self.missing_since = None;
+                Some(WatchEvent::Started(game.to_string()))
+            }
+            (Some(_), Some(_)) => {
+                self.missing_since = None;
+                None
+            }
+            (Some(game), None) => {
+                let since = *self.missing_since.get_or_insert(now_ms);
+                if now_ms.saturating_sub(since) < self.grace_ms {
+                    return None;
+                }
+                let game = game.clone();
+                self.current = None;
+                self.missing_since = None;
+                Some(WatchEvent::Exited(game))
+            }
+            (None, None) => None,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn process(name: &str, path: Option<&str>) -> ProcessInfo {
+        ProcessInfo { pid: 1, name: name.to_string(), path: path.map(str::to_string) }
+    }
+
+    #[test]
+    fn test_matching() {
+        let games = vec![
+            GameEntry { name: "Custom".into(), executable: "D:\\Games\\Custom\\game.exe".into(), profile: None },
+            GameEntry { name: "Counter-Strike 2".into(), executable: "cs2.exe".into(), profile: None },
+        ];
+        assert!(needs_paths(&games));
+        let running = [process("explorer.exe", None), process("CS2.EXE", None)];
+        assert_eq!(find_game(&games, &running).map(|g| g.name.as_str()), Some("Counter-Strike 2"));
+
+        // Paths only match the exact executable
+        assert!(games[0].matches(&process("game.exe", Some("d:/games/custom/GAME.exe"))));
+        assert!(!games[0].matches(&process("game.exe", Some("C:\\Other\\game.exe"))));
+        assert!(!games[0].matches(&process("game.exe", None)));
+        assert!(!needs_paths(&default_games()));
+    }
+
+    #[test]
+    fn test_watcher_grace_period() {
+        let mut watcher = GameWatcher::new(30_000);
+        assert_eq!(watcher.update(None, 0), None);
+        assert_eq!(watcher.update(Some("CS2"), 1_000), Some(WatchEvent::Started("CS2".into())));
+        assert_eq!(watcher.update(Some("CS2"), 2_000), None);
+
+        // Restarting the game within the grace period keeps the session
+        assert_eq!(watcher.update(None, 3_000), None);
+        assert_eq!(watcher.update(Some("CS2"), 20_000), None);
+
+        assert_eq!(watcher.update(None, 40_000), None);
+        assert_eq!(watcher.update(None, 69_999), None);
+        assert_eq!(watcher.update(None, 70_000), Some(WatchEvent::Exited("CS2".into())));
+        assert_eq!(watcher.current(), None);
+        assert_eq!(watcher.update(Some("Dota 2"), 71_000), Some(WatchEvent::Started("Dota 2".into())));
+    }
+}
@@ -0,0 +1,126 @@
+//! Game Detection
+//!
+//! Starts the replay buffer when a listed game launches and stops it once the game has been
+//! gone for a grace period, so nobody has to remember to turn it on.
+//!
+//! # Architecture
+//!
+//! * `process`: Lists running processes (Windows Toolhelp, Linux `/proc`).
+//! * `detect`: The game list, matching, and the start/exit debouncer.
+//!
+//! `spawn_watcher` polls in the background and re-reads `[game_detection]` on every poll, so
+//! config changes apply without a restart. Only a buffer the watcher started itself is stopped
+//! on exit. The detected game is kept in `RecordingState::active_game` and tagged into clips,
+//! and a running buffer switches to the game's recording profile if it names one.
+
+pub mod detect;
+pub mod process;
+
+use std::time::Duration;
+use tauri::{AppHandle, Emitter, Manager};
+
+use crate::config::ConfigDiff;
+use crate::state::RecordingState;
+use detect::{find_game, needs_paths, GameWatcher, WatchEvent};
+
+pub const GAME_STATUS_EVENT: &str = "game-status";
+
+/// Starts the background poll loop. Runs for the lifetime of the app.
+pub fn spawn_watcher(app: AppHandle) {
+    tauri::async_runtime::spawn(async move {
+        let mut watcher = GameWatcher::new(0);
+        let mut auto_started = false;
+        let mut ticker = tokio::time::interval(Duration::from_millis(crate::constants::GAME_POLL_INTERVAL_MS));
+
+        loop {
+            ticker.tick().await;
+            let settings = match app.state::<RecordingState>().config.lock() {
+                Ok(config) => config.game_detection.clone(),
+                Err(e) => {
+                    log::error!("Failed to lock config: {}", e);
+                    continue;
+                }
+            };
+            watcher.set_grace_ms(settings.grace_secs as u64 * 1000);
+
+            // Disabled: let an active session run out through the normal exit path
+            let detected = if settings.enabled {
+                let with_paths = needs_paths(&settings.games);
+                match tauri::async_runtime::spawn_blocking(move || process::list_processes(with_paths)).await {
+                    Ok(Ok(processes)) => find_game(&settings.games, &processes).map(|g| g.name.clone()),
+                    Ok(Err(e)) => {
+                        log::warn!("Failed to list processes: {}", e);
+                        continue;
+                    }
+                    Err(e) => {
+                        log::error!("Process listing task failed: {}", e);
+                        continue;
+                    }
+                }
+            } else if watcher.current().is_none() {
+                continue;
+            } else {
+                None
+            };
+
+            let now_ms = chrono::Utc::now().timestamp_millis().max(0) as u64;
+            match watcher.update(detected.as_deref(), now_ms) {
+                Some(WatchEvent::Started(game)) => {
+                    log::info!("Game detected: {}", game);
+                    switch_game(&app, Some(game)).await;
+                    let running = app.state::<RecordingState>().tx.lock().map(|tx| tx.is_some()).unwrap_or(true);
+                    if !running {
+                        match crate::commands::recording::enable_replay(app.clone()).await {
+                            Ok(()) => auto_started = true,
+                            Err(e) => log::error!("Failed to start replay buffer for game: {}", e),
+                        }
+                    }
+                }
+                Some(WatchEvent::Exited(game)) => {
+                    log::info!("Game exited: {}", game);
+                    if std::mem::take(&mut auto_started) {
+                        set_active_game(&app, None);
+                        // Already stopped by hand is fine
+                        if let Err(e) = crate::commands::recording::disable_replay(app.clone()).await {
+                            log::info!("Replay buffer not stopped: {}", e);
+                        }
+                    } else {
+                        // Started by hand: keep recording, back on the active profile
+                        switch_game(&app, None).await;
+                    }
+                }
+                None => {}
+            }
+        }
+    });
+}
+
+/// Sets the detected game and moves a running buffer to the recording profile for it.
+async fn switch_game(app: &AppHandle, game: Option<String>) {
+    let state = app.state::<RecordingState>();
+    let previous = state.active_game.lock().map(|g| g.clone()).unwrap_or_default();
+    let (before, after) = match state.config.lock() {
+        Ok(config) => (config.effective(previous.as_deref()), config.effective(game.as_deref())),
+        Err(e) => {
+            log::error!("Failed to lock config: {}", e);
+            set_active_game(app, game);
+            return;
+        }
+    };
+    set_active_game(app, game);
+
+    let diff = ConfigDiff::new(&before, &after);
+    if let Err(e) = crate::commands::config::apply_recording_changes(app, &diff, &after.recording).await {
+        log::error!("Failed to switch recording profile: {}", e);
+    }
+}
+
+fn set_active_game(app: &AppHandle, game: Option<String>) {
+    match app.state::<RecordingState>().active_game.lock() {
+        Ok(mut active) => *active = game.clone(),
+        Err(e) => log::error!("Failed to lock active game: {}", e),
+    }
+    if let Err(e) = app.emit(GAME_STATUS_EVENT, &game) {
+        log::error!("Failed to emit {}: {}", GAME_STATUS_EVENT, e);
+    }
+}
@@ -0,0 +1,116 @@
+//! Running process enumeration (Toolhelp snapshot on Windows, `/proc` on Linux).
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct ProcessInfo {
+    pub pid: u32,
+    /// Executable file name, e.g. "cs2.exe"
+    pub name: String,
+    /// Full executable path, when requested and readable
+    pub path: Option<String>,
+}
+
+/// Lists running processes. Full paths cost an extra syscall per process, so they are opt-in.
+#[cfg(target_os = "windows")]
+pub fn list_processes(with_paths: bool) -> Result<Vec<ProcessInfo>, String> {
+    use windows::Win32::Foundation::CloseHandle;
+    use windows::Win32::System::Diagnostics::ToolHelp::{
+        CreateToolhelp32Snapshot, Process32FirstW, Process32NextW, PROCESSENTRY32W, TH32CS_SNAPPROCESS,
+    };
+
+    unsafe {
+        let snapshot = CreateToolhelp32Snapshot(TH32CS_SNAPPROCESS, 0).map_err(|e| e.to_string())?;
+        let mut entry = PROCESSENTRY32W { dwSize: std::mem::size_of::<PROCESSENTRY32W>() as u32, ..Default::default() };
+        let mut processes = Vec::new();
+
+        let mut more = Process32FirstW(snapshot, &mut entry).is_ok();
+        while more {
+            let len = entry.szExeFile.iter().position(|&c| c == 0).unwrap_or(entry.szExeFile.len());
+            let pid = entry.th32ProcessID;
+            processes.push(ProcessInfo {
+                pid,
+                name: String::from_utf16_lossy(&entry.szExeFile[..len]),
+                path: if with_paths { process_path(pid) } else { None },
+            });
+            more = Process32NextW(snapshot, &mut entry).is_ok();
+        }
+
+        let _ = CloseHandle(snapshot);
+        Ok(processes)
+    }
+}
+
+#[cfg(target_os = "windows")]
+fn process_path(pid: u32) -> Option<String> {
+    use windows::core::PWSTR;
+    use windows::Win32::Foundation::CloseHandle;
+    use windows::Win32::System::Threading::{
+        OpenProcess, QueryFullProcessImageNameW, PROCESS_NAME_WIN32, PROCESS_QUERY_LIMITED_INFORMATION,
+    };
+
+    unsafe {
+        // Fails for protected and elevated processes; those just have no path
+        let handle = OpenProcess(PROCESS_QUERY_LIMITED_INFORMATION, false, pid).ok()?;
+        let mut buf = [0u16; 1024];
+        let mut size = buf.len() as u32;
+        let result = QueryFullProcessImageNameW(handle, PROCESS_NAME_WIN32, PWSTR(buf.as_mut_ptr()), &mut size);
+        let _ = CloseHandle(handle);
+        result.ok()?;
+        Some(String::from_utf16_lossy(&buf[..size as usize]))
+    }
+}
+
+#[cfg(target_os = "linux")]
+pub fn list_processes(with_paths: bool) -> Result<Vec<ProcessInfo>, String> {
+    let entries = std::fs::read_dir("/proc").map_err(|e| format!("Failed to read /proc: {}", e))?;
+    let mut processes = Vec::new();
+    for entry in entries.flatten() {
+        let Some(pid) = entry.file_name().to_str().and_then(|n| n.parse::<u32>().ok()) else { continue };
+        let dir = entry.path();
+        let exe = std::fs::read_link(dir.join("exe")).ok();
+        // `comm` is cut at 15 characters; prefer the executable's name when it is readable
+        let name = exe
+            .as_ref()
+            .and_then(|p| p.file_name())
+            .map(|n| n.to_string_lossy().to_string())
+            .or_else(|| std::fs::read_to_string(dir.join("comm")).ok().map(|c| c.trim_end().to_string()));
+        let Some(name) = name else { continue };
+        processes.push(ProcessInfo {
+            pid,
+            name,
+            path: exe.filter(|_| with_paths).map(|p| p.to_string_lossy().to_string()),
+        });
+    }
+    Ok(processes)
+}
+
+#[cfg(not(any(target_os = "windows", target_os = "linux")))]
+pub fn list_processes(_with_paths: bool) -> Result<Vec<ProcessInfo>, String> {
+    Err("Process listing is not supported on this platform".to_string())
+}
+
+#[cfg(all(test, target_os = "linux"))]
+mod tests {
+    use super::*;
+    use std::process::Command;
+
+    #[test]
+    fn test_lists_dummy_process() {
+        // A copy of `sleep` under a recognisable (and longer than 15 chars) name
+        let dir = std::env::temp_dir().join(format!("squadsync_proc_{}", std::process::id()));
+        std::fs::create_dir_all(&dir).unwrap();
+        let exe = dir.join("squadsync-dummy-game");
+        std::fs::copy("/bin/sleep", &exe).unwrap();
+
+        let mut child = Command::new(&exe).arg("30").spawn().unwrap();
+        let pid = child.id();
+        let found = list_processes(true).unwrap().into_iter().find(|p| p.pid == pid).expect("dummy process listed");
+        assert_eq!(found.name, "squadsync-dummy-game");
+        assert_eq!(found.path.as_deref(), Some(exe.to_string_lossy().as_ref()));
+        assert!(list_processes(false).unwrap().iter().all(|p| p.path.is_none()));
+
+        child.kill().unwrap();
+        child.wait().unwrap();
+        assert!(list_processes(false).unwrap().iter().all(|p| p.pid != pid));
+        let _ = std::fs::remove_dir_all(&dir);
+    }
+}
@@ -0,0 +1,163 @@
+//! Counter-Strike 2 Game State Integration payloads.
+//!
+//! CS2 posts the full state on every change, with the old values of whatever changed under
+//! `previously` and newly present keys under `added`. Events are derived from those deltas,
+//! only for the local player (`player.steamid == provider.steamid`): while dead and spectating,
+//! `player` is whoever is being watched.
+
+use serde_json::{json, Value};
+use crate::constants::GSI_PLUGIN_ID;
+use crate::plugins::protocol::PluginEvent;
+
+pub const GAME: &str = "cs2";
+
+fn at<'a>(value: &'a Value, path: &[&str]) -> Option<&'a Value> {
+    path.iter().try_fold(value, |v, key| v.get(key))
+}
+
+fn u64_at(value: &Value, path: &[&str]) -> Option<u64> {
+    at(value, path).and_then(Value::as_u64)
+}
+
+fn str_at<'a>(value: &'a Value, path: &[&str]) -> Option<&'a str> {
+    at(value, path).and_then(Value::as_str)
+}
+
+/// The `auth.token` from the game's cfg file, if any.
+pub fn auth_token(payload: &Value) -> Option<&str> {
+    str_at(payload, &["auth", "token"])
+}
+
+/// Increase of a counter under `player`, when the update says it changed.
+fn player_increase(payload: &Value, path: &[&str]) -> u64 {
+    let mut full = vec!["player"];
+    full.extend_from_slice(path);
+    let now = u64_at(payload, &full);
+    let mut old = vec!["previously"];
+    old.extend_from_slice(&full);
+    match (now, u64_at(payload, &old)) {
+        (Some(now), Some(before)) => now.saturating_sub(before),
+        _ => 0,
+    }
+}
+
+/// `round.<key>` changed to `value` in this update.
+fn round_became(payload: &Value, key: &str, value: &str) -> bool {
+    if str_at(payload, &["round", key]) != Some(value) {
+        return false;
+    }
+    let was = str_at(payload, &["previously", "round", key]);
+    let added = at(payload, &["added", "round", key]).is_some();
+    added || was.is_some_and(|w| w != value)
+}
+
+/// Events in one GSI update. `now_ms` (local Unix ms) stamps them: GSI only has second precision.
+pub fn parse_events(payload: &Value, now_ms: u64) -> Vec<PluginEvent> {
+    let mut events = Vec::new();
+    let mut push = |event_type: &str, metadata: Value| {
+        let metadata = match metadata {
+            Value::Object(map) => map,
+            _ => Default::default(),
+        };
+        events.push(PluginEvent {
+            plugin_id: GSI_PLUGIN_ID.to_string(),
+            event_id: format!("{}-{}-{}", GAME, now_ms, events.len()),
+            timestamp: now_ms,
+            game: GAME.to_string(),
+            event_type: event_type.to_string(),
+            metadata,
+        });
+    };
+
+    let own_steamid = str_at(payload, &["provider", "steamid"]);
+    let player_steamid = str_at(payload, &["player", "steamid"]);
+    // Switching the spectated player shows up as a steamid change; its deltas aren't ours
+    let switched_player = at(payload, &["previously", "player", "steamid"]).is_some();
+    let is_local = own_steamid.is_some() && own_steamid == player_steamid && !switched_player;
+
+    if is_local {
+        let kills = player_increase(payload, &["state", "round_kills"]);
+        let headshots = player_increase(payload, &["state", "round_killhs"]);
+        let round_kills = u64_at(payload, &["player", "state", "round_kills"]).unwrap_or(0);
+        for i in 0..kills {
+            // GSI doesn't say which kill was the headshot; count them as the first ones
+            let headshot = i < headshots;
+            let label = if headshot { "Headshot" } else { "Kill" };
+            push("kill", json!({ "label": label, "headshot": headshot, "roundKills": round_kills - kills + i + 1 }));
+        }
+        if player_increase(payload, &["match_stats", "deaths"]) > 0 {
+            push("death", json!({ "label": "Death" }));
+        }
+    }
+
+    for (state, event_type, label) in [
+        ("planted", "bomb_planted", "Bomb planted"),
+        ("defused", "bomb_defused", "Bomb defused"),
+        ("exploded", "bomb_exploded", "Bomb exploded"),
+    ] {
+        if round_became(payload, "bomb", state) {
+            push(event_type, json!({ "label": label }));
+        }
+    }
+
+    if round_became(payload, "phase", "over") {
+        let win_team = str_at(payload, &["round", "win_team"]);
+        let team = str_at(payload, &["player", "team"]).filter(|_| is_local);
+        let won = win_team.zip(team).map(|(w, t)| w == t);
+        let label = match won {
+            Some(true) => "Round won",
+            Some(false) => "Round lost",
+            None => "Round over",
+        };
+        push("round_end", json!({ "label": label, "winTeam": win_team, "won": won }));
+    }
+
+    events
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn fixture(name: &str) -> Value {
+        let text = match name {
+            "double_kill_hs" => include_str!("fixtures/cs2_double_kill_hs.json"),
+            "death" => include_str!("fixtures/cs2_death.json"),
+            "bomb_planted" => include_str!("fixtures/cs2_bomb_planted.json"),
+            "round_over" => include_str!("fixtures/cs2_round_over.json"),
+            "spectating" => include_str!("fixtures/cs2_spectating.json"),
+            _ => panic!("unknown fixture {}", name),
+        };
+        serde_json::from_str(text).unwrap()
+    }
+
+    fn summary(events: &[PluginEvent]) -> Vec<(String, String)> {
+        events.iter().map(|e| (e.event_type.clone(), e.marker_label())).collect()
+    }
+
+    #[test]
+    fn test_double_kill_with_headshot() {
+        let payload = fixture("double_kill_hs");
+        assert_eq!(auth_token(&payload), Some("gsi-secret"));
+        let events = parse_events(&payload, 1_000);
+        assert_eq!(summary(&events), vec![("kill".into(), "Headshot".into()), ("kill".into(), "Kill".into())]);
+        assert_eq!(events[1].metadata["roundKills"], 2);
+        assert!(events.iter().all(|e| e.game == GAME && e.timestamp == 1_000));
+        assert_ne!(events[0].event_id, events[1].event_id);
+    }
+
+    #[test]
+    fn test_death_bomb_and_round_end() {
+        assert_eq!(summary(&parse_events(&fixture("death"), 0)), vec![("death".into(), "Death".into())]);
+        assert_eq!(summary(&parse_events(&fixture("bomb_planted"), 0)), vec![("bomb_planted".into(), "Bomb planted".into())]);
+        assert_eq!(
+            summary(&parse_events(&fixture("round_over"), 0)),
+            vec![("bomb_exploded".into(), "Bomb exploded".into()), ("round_end".into(), "Round won".into())],
+        );
+    }
+
+    #[test]
+    fn test_spectated_player_is_ignored() {
+        assert!(parse_events(&fixture("spectating"), 0).is_empty());
+    }
+}
@@ -0,0 +1,81 @@
+//! Game Event Integration
+//!
+//! Built-in receiver for games that push their state over HTTP, starting with CS2 Game State
+//! Integration (GSI).
+//!
+//! # Architecture
+//!
+//! * `cs2`: Derives `PluginEvent`s (kills, deaths, bomb, round end) from GSI updates.
+//! * `rules`: Configurable "N events within T seconds → save S seconds" rules.
+//! * `server`: The loopback endpoint the game posts to.
+//!
+//! Events and rule matches go through the plugin API's handler and rate limits, so they become
+//! markers and replay saves exactly like messages from external plugins.
+
+pub mod cs2;
+pub mod rules;
+pub mod server;
+
+use std::sync::Arc;
+use tauri::{AppHandle, Manager};
+
+use crate::config::GsiConfig;
+use crate::plugins::AppPluginHandler;
+use crate::state::RecordingState;
+
+/// Gives an enabled receiver an auth token if it has none. Returns whether one was generated.
+pub fn ensure_token(config: &mut GsiConfig) -> bool {
+    if config.enabled && config.token.is_empty() {
+        config.token = crate::plugins::generate_token();
+        return true;
+    }
+    false
+}
+
+/// Stops the running receiver (if any) and starts one for `config` when enabled.
+pub async fn restart(app: &AppHandle, config: &GsiConfig) -> Result<(), String> {
+    let state = app.state::<RecordingState>();
+    let previous = state.gsi_server.lock().map_err(|e| e.to_string())?.take();
+    if let Some(server) = previous {
+        server.stop().await;
+    }
+    if !config.enabled {
+        return Ok(());
+    }
+
+    let handler = Arc::new(AppPluginHandler::new(app.clone()));
+    let receiver = server::Receiver::new(config.token.clone(), config.rules.clone(), handler, state.plugin_limiter.clone());
+    let server = server::start(config.port, receiver).await?;
+    *state.gsi_server.lock().map_err(|e| e.to_string())? = Some(server);
+    Ok(())
+}
+
+/// Contents of `game/csgo/cfg/gamestate_integration_squadsync.cfg` for this config.
+pub fn cs2_config_file(config: &GsiConfig) -> String {
+    format!(
+        r#""SquadSync"
+{{
+    "uri"       "http://127.0.0.1:{port}/cs2"
+    "timeout"   "1.0"
+    "buffer"    "0.0"
+    "throttle"  "0.1"
+    "heartbeat" "30.0"
+    "auth"
+    {{
+        "token" "{token}"
+    }}
+    "data"
+    {{
+        "provider"           "1"
+        "map"                "1"
+        "round"              "1"
+        "player_id"          "1"
+        "player_state"       "1"
+        "player_match_stats" "1"
+    }}
+}}
+"#,
+        port = config.port,
+        token = config.token,
+    )
+}
@@ -0,0 +1,116 @@
+//! Auto-clip rules: "on `count` `event`s within `within_secs`, save `save_secs`".
+
+use serde::{Deserialize, Serialize};
+use std::collections::VecDeque;
+use crate::plugins::protocol::PluginEvent;
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct GsiRule {
+    /// Event type, e.g. "kill", "death", "bomb_planted", "round_end"
+    pub event: String,
+    #[serde(default = "default_count")]
+    pub count: u32,
+    /// Window the `count` events must fall in. 0 = no window (only useful with count 1).
+    #[serde(default)]
+    pub within_secs: u32,
+    /// Length of the saved replay
+    pub save_secs: u32,
+    /// Clip/marker label. Defaults to the event type.
+    #[serde(default)]
+    pub label: String,
+}
+
+fn default_count() -> u32 {
+    1
+}
+
+/// The default rule set: double kills.
+pub fn default_rules() -> Vec<GsiRule> {
+    vec![GsiRule { event: "kill".into(), count: 2, within_secs: 5, save_secs: 20, label: "Double kill".into() }]
+}
+
+/// A rule that fired: save `save_secs` ending at `timestamp`.
+#[derive(Debug, Clone, PartialEq)]
+pub struct RuleMatch {
+    pub label: String,
+    pub save_secs: u32,
+    pub timestamp: u64,
+}
+
+/// Evaluates rules over the event stream. Events counted towards a match are used up,
+/// so a third kill doesn't fire "2 kills in 5s" again straight away.
+pub struct RuleEngine {
+    rules: Vec<(GsiRule, VecDeque<u64>)>,
+}
+
+impl RuleEngine {
+    pub fn new(rules: Vec<GsiRule>) -> Self {
+        Self { rules: rules.into_iter().map(|r| (r, VecDeque::new())).collect() }
+    }
+
+    pub fn observe(&mut self, event: &PluginEvent) -> Vec<RuleMatch> {
+        let mut matches = Vec::new();
+        for (rule, seen) in self.rules.iter_mut().filter(|(r, _)| r.event == event.event_type) {
+            seen.push_back(event.timestamp);
+            if rule.within_secs > 0 {
+                let window_ms = rule.within_secs as u64 * 1000;
+                while seen.front().is_some_and(|&t| t + window_ms < event.timestamp) {
+                    seen.pop_front();
+                }
+            }
+            if seen.len() as u64 >= rule.count.max(1) as u64 {
+                seen.clear();
+                let label = if rule.label.is_empty() { rule.event.clone() } else { rule.label.clone() };
+                matches.push(RuleMatch { label, save_secs: rule.save_secs, timestamp: event.timestamp });
+            }
+        }
+        matches
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn event(event_type: &str, timestamp: u64) -> PluginEvent {
+        PluginEvent { plugin_id: "gsi".into(), event_id: timestamp.to_string(), timestamp, game: "cs2".into(), event_type: event_type.into(), metadata: Default::default() }
+    }
+
+    #[test]
+    fn test_count_within_window() {
+        let mut engine = RuleEngine::new(default_rules());
+        assert!(engine.observe(&event("kill", 1_000)).is_empty());
+        // Too late for the first kill to count
+        assert!(engine.observe(&event("kill", 7_000)).is_empty());
+        assert!(engine.observe(&event("death", 8_000)).is_empty());
+        let fired = engine.observe(&event("kill", 11_000));
+        assert_eq!(fired, vec![RuleMatch { label: "Double kill".into(), save_secs: 20, timestamp: 11_000 }]);
+        // The kills are used up
+        assert!(engine.observe(&event("kill", 12_000)).is_empty());
+    }
+
+    #[test]
+    fn test_single_event_rules_and_config() {
+        let rules: Vec<GsiRule> = toml::from_str::<toml::Table>(r#"
+            [[rules]]
+            event = "bomb_defused"
+            save_secs = 15
+
+            [[rules]]
+            event = "kill"
+            count = 5
+            within_secs = 90
+            save_secs = 60
+            label = "Ace"
+        "#).unwrap()["rules"].clone().try_into().unwrap();
+        let mut engine = RuleEngine::new(rules);
+
+        let fired = engine.observe(&event("bomb_defused", 500));
+        assert_eq!(fired[0].label, "bomb_defused");
+        assert_eq!(fired[0].save_secs, 15);
+
+        let kills: Vec<_> = (0..5).map(|i| engine.observe(&event("kill", 1_000 + i * 10_000))).collect();
+        assert!(kills[..4].iter().all(|m| m.is_empty()));
+        assert_eq!(kills[4][0].label, "Ace");
+    }
+}
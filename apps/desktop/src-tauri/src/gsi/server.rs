@@ -0,0 +1,200 @@
+//! Local receiver for game state pushes. CS2 posts to `/cs2`.
+//!
+//! Markers and rule clips draw on the plugin API's rate limits under the key `gsi:cs2`, so a
+//! loose rule or a flood of updates can't save replays without bound.
+
+use std::sync::{Arc, Mutex};
+
+use axum::body::Bytes;
+use axum::extract::{DefaultBodyLimit, State};
+use axum::http::StatusCode;
+use axum::routing::post;
+use axum::Router;
+use serde_json::Value;
+
+use super::cs2;
+use super::rules::{GsiRule, RuleEngine};
+use crate::constants::{GSI_MAX_BODY_BYTES, GSI_PLUGIN_ID};
+use crate::plugins::protocol::ClipTrigger;
+use crate::plugins::rate_limit::PluginLimiter;
+use crate::plugins::server::{token_matches, LocalServer, PluginHandler};
+
+const CS2_RATE_KEY: &str = "gsi:cs2";
+
+/// Turns game updates into markers (every event) and replay saves (rule matches).
+pub struct Receiver {
+    token: String,
+    engine: Mutex<RuleEngine>,
+    handler: Arc<dyn PluginHandler>,
+    limiter: Arc<PluginLimiter>,
+}
+
+impl Receiver {
+    pub fn new(token: String, rules: Vec<GsiRule>, handler: Arc<dyn PluginHandler>, limiter: Arc<PluginLimiter>) -> Self {
+        Self { token, engine: Mutex::new(RuleEngine::new(rules)), handler, limiter }
+    }
+
+    /// Whether the update carries the token from our cfg file (always, if we have none).
+    pub fn authorized(&self, payload: &Value) -> bool {
+        self.token.is_empty() || cs2::auth_token(payload).is_some_and(|t| token_matches(&self.token, t))
+    }
+
+    /// Handles one authorized CS2 update. Returns the number of events it contained.
+    /// Rate-limited events and clips are dropped; handler failures are reported after the
+    /// rest of the update has been handled.
+    pub async fn receive_cs2(&self, payload: &Value, now_ms: u64) -> Result<usize, String> {
+        let events = cs2::parse_events(payload, now_ms);
+        let mut errors = Vec::new();
+        for event in &events {
+            let matches = self.engine.lock().map_err(|e| e.to_string())?.observe(event);
+            if self.limiter.check_event(CS2_RATE_KEY, now_ms).is_err() {
+                log::warn!("GSI: rate limit hit, event '{}' dropped", event.event_type);
+            } else if let Err(e) = self.handler.event(event.clone()).await {
+                errors.push(format!("event '{}' not recorded: {}", event.event_type, e));
+            }
+            for rule in matches {
+                if self.limiter.check_trigger(CS2_RATE_KEY, now_ms).is_err() {
+                    log::warn!("GSI: rate limit hit, rule '{}' not saved", rule.label);
+                    continue;
+                }
+                log::info!("GSI rule '{}' matched, saving {}s", rule.label, rule.save_secs);
+                let trigger = ClipTrigger {
+                    plugin_id: GSI_PLUGIN_ID.to_string(),
+                    timestamp: rule.timestamp,
+                    duration: rule.save_secs as f64,
+                    label: rule.label,
+                };
+                if let Err(e) = self.handler.clip(trigger).await {
+                    errors.push(format!("clip not saved: {}", e));
+                }
+            }
+        }
+        if !errors.is_empty() {
+            return Err(format!("GSI {}", errors.join("; ")));
+        }
+        Ok(events.len())
+    }
+}
+
+/// Starts the receiver on `127.0.0.1:port`.
+pub async fn start(port: u16, receiver: Receiver) -> Result<LocalServer, String> {
+    let router = Router::new()
+        .route("/cs2", post(post_cs2))
+        .layer(DefaultBodyLimit::max(GSI_MAX_BODY_BYTES))
+        .with_state(Arc::new(receiver));
+    LocalServer::bind("Game state receiver", port, router).await
+}
+
+async fn post_cs2(State(receiver): State<Arc<Receiver>>, body: Bytes) -> StatusCode {
+    let payload: Value = match serde_json::from_slice(&body) {
+        Ok(payload) => payload,
+        Err(e) => {
+            log::warn!("Ignoring malformed GSI payload: {}", e);
+            return StatusCode::BAD_REQUEST;
+        }
+    };
+    if !receiver.authorized(&payload) {
+        log::warn!("Invalid GSI auth token");
+        return StatusCode::UNAUTHORIZED;
+    }
+    let now_ms = chrono::Utc::now().timestamp_millis().max(0) as u64;
+    match receiver.receive_cs2(&payload, now_ms).await {
+        Ok(_) => StatusCode::OK,
+        Err(e) => {
+            log::warn!("{}", e);
+            StatusCode::INTERNAL_SERVER_ERROR
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::plugins::protocol::PluginEvent;
+    use crate::plugins::rate_limit::RateLimits;
+    use async_trait::async_trait;
+
+    #[derive(Default)]
+    struct Recorder {
+        clips: Mutex<Vec<ClipTrigger>>,
+        events: Mutex<Vec<String>>,
+        fail_clips: bool,
+    }
+
+    #[async_trait]
+    impl PluginHandler for Recorder {
+        async fn clip(&self, trigger: ClipTrigger) -> Result<(), String> {
+            self.clips.lock().unwrap().push(trigger);
+            if self.fail_clips {
+                return Err("Replay buffer is not running".to_string());
+            }
+            Ok(())
+        }
+
+        async fn event(&self, event: PluginEvent) -> Result<(), String> {
+            self.events.lock().unwrap().push(event.event_type);
+            Ok(())
+        }
+    }
+
+    #[test]
+    fn test_fixtures_feed_markers_and_rules() {
+        let rt = tokio::runtime::Builder::new_current_thread().enable_all().build().unwrap();
+        rt.block_on(async {
+            let recorder = Arc::new(Recorder::default());
+            let limiter = Arc::new(PluginLimiter::new(RateLimits { triggers_per_minute: 6, events_per_minute: 120 }));
+            let receiver = Receiver::new("gsi-secret".into(), super::super::rules::default_rules(), recorder.clone(), limiter);
+            let server = start(0, receiver).await.unwrap();
+            let url = format!("http://{}/cs2", server.addr());
+            let client = reqwest::Client::new();
+
+            for fixture in [
+                include_str!("fixtures/cs2_double_kill_hs.json"),
+                include_str!("fixtures/cs2_spectating.json"),
+                include_str!("fixtures/cs2_death.json"),
+                include_str!("fixtures/cs2_bomb_planted.json"),
+                include_str!("fixtures/cs2_round_over.json"),
+            ] {
+                let response = client.post(&url).body(fixture).send().await.unwrap();
+                assert_eq!(response.status().as_u16(), 200);
+            }
+            let forged = include_str!("fixtures/cs2_death.json").replace("gsi-secret", "guess");
+            assert_eq!(client.post(&url).body(forged).send().await.unwrap().status().as_u16(), 401);
+
+            assert_eq!(*recorder.events.lock().unwrap(), vec!["kill", "kill", "death", "bomb_planted", "bomb_exploded", "round_end"]);
+            server.stop().await;
+            let clips = recorder.clips.lock().unwrap();
+            assert_eq!(clips.len(), 1);
+            assert_eq!(clips[0].label, "Double kill");
+            assert_eq!(clips[0].duration, 20.0);
+            assert_eq!(clips[0].plugin_id, GSI_PLUGIN_ID);
+        });
+    }
+
+    #[test]
+    fn test_rate_limits_and_handler_errors() {
+        let rt = tokio::runtime::Builder::new_current_thread().enable_all().build().unwrap();
+        rt.block_on(async {
+            let recorder = Arc::new(Recorder { fail_clips: true, ..Default::default() });
+            let limits = RateLimits { triggers_per_minute: 1, events_per_minute: 3 };
+            let receiver = || Receiver::new("gsi-secret".into(), super::super::rules::default_rules(), recorder.clone(), Arc::new(PluginLimiter::new(limits)));
+            let double_kill = include_str!("fixtures/cs2_double_kill_hs.json");
+
+            // A failed save is a server error, not an auth failure
+            let server = start(0, receiver()).await.unwrap();
+            let response = reqwest::Client::new().post(format!("http://{}/cs2", server.addr())).body(double_kill).send().await.unwrap();
+            assert_eq!(response.status().as_u16(), 500);
+            server.stop().await;
+            recorder.clips.lock().unwrap().clear();
+            recorder.events.lock().unwrap().clear();
+
+            let receiver = receiver();
+            let payload: Value = serde_json::from_str(double_kill).unwrap();
+            assert!(receiver.receive_cs2(&payload, 1_000).await.unwrap_err().contains("clip not saved"));
+            // The same double kill again: one marker left in the budget, and no second save
+            assert_eq!(receiver.receive_cs2(&payload, 2_000).await, Ok(2));
+            assert_eq!(recorder.events.lock().unwrap().len(), 3);
+            assert_eq!(recorder.clips.lock().unwrap().len(), 1);
+        });
+    }
+}
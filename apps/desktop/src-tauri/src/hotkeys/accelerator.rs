@@ -0,0 +1,173 @@
+//! Accelerator strings ("Ctrl+Shift+F9") parsed into a validated, canonical form.
+
+use std::fmt;
+use std::str::FromStr;
+
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct Accelerator {
+    pub ctrl: bool,
+    pub alt: bool,
+    pub shift: bool,
+    pub super_key: bool,
+    /// Canonical key name, e.g. "F10", "A", "1", "Space", "NumpadAdd"
+    pub key: String,
+}
+
+/// Keys that may be bound without a modifier; anything else would swallow normal typing.
+const STANDALONE_KEYS: &[&str] = &[
+    "Pause", "ScrollLock", "PrintScreen", "Insert",
+    "MediaPlayPause", "MediaStop", "MediaTrackNext", "MediaTrackPrevious",
+];
+
+const NAMED_KEYS: &[&str] = &[
+    "Space", "Enter", "Tab", "Backspace", "Escape", "Delete", "Insert", "Home", "End",
+    "PageUp", "PageDown", "ArrowUp", "ArrowDown", "ArrowLeft", "ArrowRight",
+    "PrintScreen", "ScrollLock", "Pause", "CapsLock", "NumLock",
+    "Backquote", "Minus", "Equal", "BracketLeft", "BracketRight", "Backslash",
+    "Semicolon", "Quote", "Comma", "Period", "Slash",
+    "NumpadAdd", "NumpadSubtract", "NumpadMultiply", "NumpadDivide", "NumpadDecimal", "NumpadEnter",
+    "MediaPlayPause", "MediaStop", "MediaTrackNext", "MediaTrackPrevious",
+];
+
+impl Accelerator {
+    /// Whether binding this globally is allowed without a modifier.
+    fn standalone_ok(&self) -> bool {
+        is_function_key(&self.key) || STANDALONE_KEYS.contains(&self.key.as_str())
+    }
+
+    fn has_modifier(&self) -> bool {
+        self.ctrl || self.alt || self.shift || self.super_key
+    }
+}
+
+impl FromStr for Accelerator {
+    type Err = String;
+
+    fn from_str(value: &str) -> Result<Self, Self::Err> {
+        let value = value.trim();
+        if value.is_empty() {
+            return Err("Hotkey is empty".to_string());
+        }
+
+        let mut accel = Accelerator { ctrl: false, alt: false, shift: false, super_key: false, key: String::new() };
+        for token in value.split('+').map(str::trim) {
+            if token.is_empty() {
+                return Err(format!("'{}' has an empty key between '+' signs", value));
+            }
+            let modifier = match token.to_ascii_lowercase().as_str() {
+                "ctrl" | "control" => Some(&mut accel.ctrl),
+                "alt" | "option" => Some(&mut accel.alt),
+                "shift" => Some(&mut accel.shift),
+                "super" | "win" | "meta" | "cmd" | "command" => Some(&mut accel.super_key),
+                _ => None,
+            };
+            match modifier {
+                Some(flag) if *flag => return Err(format!("'{}' repeats the {} modifier", value, token)),
+                Some(flag) => *flag = true,
+                None if !accel.key.is_empty() => return Err(format!("'{}' has more than one key", value)),
+                None => accel.key = canonical_key(token).ok_or_else(|| format!("'{}' is not a supported key", token))?,
+            }
+        }
+
+        if accel.key.is_empty() {
+            return Err(format!("'{}' has no key, only modifiers", value));
+        }
+        if !accel.has_modifier() && !accel.standalone_ok() {
+            return Err(format!("'{}' needs a modifier (Ctrl, Alt, Shift or Win)", value));
+        }
+        Ok(accel)
+    }
+}
+
+/// Canonical form, in the order Ctrl+Alt+Shift+Super+Key. Also what the shortcut plugin parses.
+impl fmt::Display for Accelerator {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let modifiers = [(self.ctrl, "Ctrl"), (self.alt, "Alt"), (self.shift, "Shift"), (self.super_key, "Super")];
+        for (_, name) in modifiers.iter().filter(|(on, _)| *on) {
+            write!(f, "{}+", name)?;
+        }
+        write!(f, "{}", self.key)
+    }
+}
+
+fn is_function_key(key: &str) -> bool {
+    key.strip_prefix('F')
+        .and_then(|n| n.parse::<u8>().ok())
+        .is_some_and(|n| (1..=24).contains(&n))
+}
+
+fn canonical_key(token: &str) -> Option<String> {
+    let upper = token.to_ascii_uppercase();
+    if upper.len() == 1 {
+        let c = upper.chars().next()?;
+        if c.is_ascii_alphanumeric() {
+            return Some(upper);
+        }
+    }
+    if let Some(rest) = upper.strip_prefix("DIGIT").or_else(|| upper.strip_prefix("KEY")) {
+        if rest.len() == 1 && rest.chars().all(|c| c.is_ascii_alphanumeric()) {
+            return Some(rest.to_string());
+        }
+    }
+    if is_function_key(&upper) {
+        return Some(upper);
+    }
+    if let Some(n) = upper.strip_prefix("NUMPAD").or_else(|| upper.strip_prefix("NUM")) {
+        if n.len() == 1 && n.chars().all(|c| c.is_ascii_digit()) {
+            return Some(format!("Numpad{}", n));
+        }
+    }
+
+    let alias = match upper.as_str() {
+        "ESC" => "ESCAPE",
+        "RETURN" => "ENTER",
+        "DEL" => "DELETE",
+        "INS" => "INSERT",
+        "PGUP" => "PAGEUP",
+        "PGDN" => "PAGEDOWN",
+        "UP" => "ARROWUP",
+        "DOWN" => "ARROWDOWN",
+        "LEFT" => "ARROWLEFT",
+        "RIGHT" => "ARROWRIGHT",
+        "PRTSC" | "PRINT" => "PRINTSCREEN",
+        other => other,
+    };
+    NAMED_KEYS.iter().find(|k| k.eq_ignore_ascii_case(alias)).map(|k| k.to_string())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn parse(s: &str) -> Result<String, String> {
+        s.parse::<Accelerator>().map(|a| a.to_string())
+    }
+
+    #[test]
+    fn test_canonical_form() {
+        assert_eq!(parse("Alt+F10").unwrap(), "Alt+F10");
+        assert_eq!(parse(" shift + ctrl + s ").unwrap(), "Ctrl+Shift+S");
+        assert_eq!(parse("Win+Alt+Digit2").unwrap(), "Alt+Super+2");
+        assert_eq!(parse("Control+Num5").unwrap(), "Ctrl+Numpad5");
+        assert_eq!(parse("ctrl+pgup").unwrap(), "Ctrl+PageUp");
+        assert_eq!(parse("F9").unwrap(), "F9");
+        assert_eq!(parse("Pause").unwrap(), "Pause");
+    }
+
+    #[test]
+    fn test_equal_bindings_compare_equal() {
+        assert_eq!("Alt+Shift+F1".parse::<Accelerator>(), "shift+alt+f1".parse::<Accelerator>());
+    }
+
+    #[test]
+    fn test_rejects_invalid() {
+        assert!(parse("").is_err());
+        assert!(parse("Ctrl+Alt").unwrap_err().contains("no key"));
+        assert!(parse("Ctrl+A+B").unwrap_err().contains("more than one key"));
+        assert!(parse("Ctrl+Ctrl+A").unwrap_err().contains("repeats"));
+        assert!(parse("Ctrl++").is_err());
+        assert!(parse("Ctrl+Banana").unwrap_err().contains("not a supported key"));
+        assert!(parse("A").unwrap_err().contains("needs a modifier"));
+        assert!(parse("F25").is_err());
+    }
+}
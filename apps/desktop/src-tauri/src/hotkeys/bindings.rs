@@ -0,0 +1,112 @@
+//! Hotkey actions and the accelerator bound to each.
+
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+use std::str::FromStr;
+
+use super::accelerator::Accelerator;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum HotkeyAction {
+    /// Save the configured buffer duration
+    SaveReplay,
+    #[serde(rename = "save_30s")]
+    Save30s,
+    #[serde(rename = "save_60s")]
+    Save60s,
+    #[serde(rename = "save_2m")]
+    Save2m,
+    ToggleBuffer,
+    AddMarker,
+    TriggerSquadClip,
+}
+
+impl HotkeyAction {
+    /// Clip length for the save actions; `None` uses the configured buffer duration.
+    pub fn save_duration_secs(self) -> Option<u32> {
+        match self {
+            HotkeyAction::Save30s => Some(30),
+            HotkeyAction::Save60s => Some(60),
+            HotkeyAction::Save2m => Some(120),
+            _ => None,
+        }
+    }
+}
+
+/// Action -> accelerator. An empty string leaves the action unbound.
+pub type HotkeyMap = BTreeMap<HotkeyAction, String>;
+
+pub fn default_hotkeys() -> HotkeyMap {
+    BTreeMap::from([(HotkeyAction::SaveReplay, "Alt+F10".to_string())])
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct HotkeyStatus {
+    pub action: HotkeyAction,
+    pub accelerator: String,
+    pub registered: bool,
+    pub error: Option<String>,
+}
+
+/// Validates the map: every binding must parse and no two actions may share an accelerator.
+/// Returns the parsed bindings and a status entry for each problem.
+pub fn resolve(map: &HotkeyMap) -> (Vec<(HotkeyAction, Accelerator)>, Vec<HotkeyStatus>) {
+    let mut bindings: Vec<(HotkeyAction, Accelerator)> = Vec::new();
+    let mut problems = Vec::new();
+
+    for (&action, value) in map.iter().filter(|(_, v)| !v.trim().is_empty()) {
+        match Accelerator::from_str(value) {
+            Ok(accel) => match bindings.iter().find(|(_, other)| *other == accel) {
+                Some((other_action, _)) => problems.push(HotkeyStatus {
+                    action,
+                    accelerator: accel.to_string(),
+                    registered: false,
+                    error: Some(format!("{} is already bound to {:?}", accel, other_action)),
+                }),
+                None => bindings.push((action, accel)),
+            },
+            Err(e) => problems.push(HotkeyStatus { action, accelerator: value.clone(), registered: false, error: Some(e) }),
+        }
+    }
+
+    (bindings, problems)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_conflicts_and_invalid_bindings_are_reported() {
+        let map = HotkeyMap::from([
+            (HotkeyAction::SaveReplay, "Alt+F10".to_string()),
+            (HotkeyAction::Save30s, "alt+f10".to_string()),
+            (HotkeyAction::Save60s, "Ctrl+Nope".to_string()),
+            (HotkeyAction::ToggleBuffer, "Ctrl+Shift+R".to_string()),
+            (HotkeyAction::AddMarker, "".to_string()),
+        ]);
+        let (bindings, problems) = resolve(&map);
+
+        let bound: Vec<(HotkeyAction, String)> = bindings.iter().map(|(a, k)| (*a, k.to_string())).collect();
+        assert_eq!(bound, vec![(HotkeyAction::SaveReplay, "Alt+F10".to_string()), (HotkeyAction::ToggleBuffer, "Ctrl+Shift+R".to_string())]);
+
+        assert_eq!(problems.len(), 2);
+        assert_eq!(problems[0].action, HotkeyAction::Save30s);
+        assert!(problems[0].error.as_deref().unwrap().contains("SaveReplay"));
+        assert_eq!(problems[1].action, HotkeyAction::Save60s);
+    }
+
+    #[test]
+    fn test_config_table_round_trip() {
+        #[derive(Serialize, Deserialize)]
+        struct Wrapper {
+            hotkeys: HotkeyMap,
+        }
+        let parsed: Wrapper = toml::from_str("[hotkeys]\nsave_replay = \"Alt+F10\"\nsave_2m = \"Alt+F12\"\ntrigger_squad_clip = \"Ctrl+Alt+S\"\n").unwrap();
+        assert_eq!(parsed.hotkeys.get(&HotkeyAction::Save2m).map(String::as_str), Some("Alt+F12"));
+        assert_eq!(parsed.hotkeys.len(), 3);
+        assert!(toml::to_string(&parsed).unwrap().contains("trigger_squad_clip"));
+        assert_eq!(default_hotkeys().get(&HotkeyAction::SaveReplay).map(String::as_str), Some("Alt+F10"));
+    }
+}
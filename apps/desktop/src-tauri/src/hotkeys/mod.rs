@@ -0,0 +1,111 @@
+//! Global Hotkeys
+//!
+//! Maps configurable accelerators to app actions.
+//!
+//! # Architecture
+//!
+//! * `accelerator`: Parses and validates accelerator strings into a canonical form.
+//! * `bindings`: The action map from the config and its conflict checks.
+//!
+//! `apply` (re-)registers the whole `[hotkeys]` config table with the global-shortcut plugin:
+//! everything is unregistered first, bindings that fail to parse, clash with another action, or
+//! are refused by the OS are reported per action. The result is kept in `RecordingState` and
+//! sent to the UI as a `hotkeys-status` event. Actions that need the signaling connection
+//! (squad clips) are forwarded to the frontend as `hotkey-action` events.
+
+pub mod accelerator;
+pub mod bindings;
+
+use std::collections::HashMap;
+use std::str::FromStr;
+use tauri::{AppHandle, Emitter, Manager};
+use tauri_plugin_global_shortcut::{GlobalShortcutExt, Shortcut};
+
+use crate::state::RecordingState;
+pub use bindings::{default_hotkeys, resolve, HotkeyAction, HotkeyMap, HotkeyStatus};
+
+pub const HOTKEY_STATUS_EVENT: &str = "hotkeys-status";
+pub const HOTKEY_ACTION_EVENT: &str = "hotkey-action";
+
+/// Shortcuts currently registered, by plugin shortcut ID.
+#[derive(Default)]
+pub struct HotkeyRegistry {
+    bindings: HashMap<u32, HotkeyAction>,
+    pub status: Vec<HotkeyStatus>,
+}
+
+/// Replaces all registered shortcuts with `map`. Returns the status of every bound action.
+pub fn apply(app: &AppHandle, map: &HotkeyMap) -> Vec<HotkeyStatus> {
+    let shortcuts = app.global_shortcut();
+    if let Err(e) = shortcuts.unregister_all() {
+        log::warn!("Failed to unregister hotkeys: {}", e);
+    }
+
+    let (bindings, mut status) = resolve(map);
+    let mut registered = HashMap::new();
+    for (action, accel) in bindings {
+        let result = Shortcut::from_str(&accel.to_string())
+            .map_err(|e| e.to_string())
+            .and_then(|shortcut| shortcuts.register(shortcut).map(|_| shortcut).map_err(|e| e.to_string()));
+        match result {
+            Ok(shortcut) => {
+                log::info!("Registered hotkey {} for {:?}", accel, action);
+                registered.insert(shortcut.id(), action);
+                status.push(HotkeyStatus { action, accelerator: accel.to_string(), registered: true, error: None });
+            }
+            Err(e) => {
+                // Typically another application already owns the combination
+                log::error!("Failed to register hotkey {} for {:?}: {}", accel, action, e);
+                status.push(HotkeyStatus { action, accelerator: accel.to_string(), registered: false, error: Some(e) });
+            }
+        }
+    }
+    status.sort_by_key(|s| s.action);
+
+    let state = app.state::<RecordingState>();
+    match state.hotkeys.lock() {
+        Ok(mut registry) => *registry = HotkeyRegistry { bindings: registered, status: status.clone() },
+        Err(e) => log::error!("Failed to lock hotkey registry: {}", e),
+    };
+    if let Err(e) = app.emit(HOTKEY_STATUS_EVENT, &status) {
+        log::error!("Failed to emit {}: {}", HOTKEY_STATUS_EVENT, e);
+    }
+    status
+}
+
+/// Global shortcut handler: runs the action bound to `shortcut`.
+pub fn handle(app: &AppHandle, shortcut: &Shortcut) {
+    let action = app.state::<RecordingState>().hotkeys.lock().ok().and_then(|r| r.bindings.get(&shortcut.id()).copied());
+    let Some(action) = action else { return };
+    log::info!("Hotkey triggered: {:?}", action);
+
+    let app = app.clone();
+    tauri::async_runtime::spawn(async move {
+        if let Err(e) = run_action(&app, action).await {
+            log::error!("Hotkey action {:?} failed: {}", action, e);
+        }
+    });
+}
+
+async fn run_action(app: &AppHandle, action: HotkeyAction) -> Result<(), String> {
+    match action {
+        HotkeyAction::SaveReplay | HotkeyAction::Save30s | HotkeyAction::Save60s | HotkeyAction::Save2m => {
+            let saved = crate::commands::replay::save_replay_impl(app, None, action.save_duration_secs()).await?;
+            log::info!("Replay saved via hotkey: {}", saved.file_path);
+        }
+        HotkeyAction::ToggleBuffer => {
+            let active = app.state::<RecordingState>().tx.lock().map_err(|e| e.to_string())?.is_some();
+            if active {
+                crate::commands::recording::disable_replay(app.clone()).await?;
+            } else {
+                crate::commands::recording::enable_replay(app.clone()).await?;
+            }
+        }
+        HotkeyAction::AddMarker => {
+            crate::commands::markers::add_marker_impl(app, None, None)?;
+        }
+        HotkeyAction::TriggerSquadClip => {}
+    }
+    // Let the UI react (refresh state, or run actions it owns such as squad clips)
+    app.emit(HOTKEY_ACTION_EVENT, action).map_err(|e| e.to_string())
+}
@@ -9,6 +9,14 @@ pub mod audio;
 pub mod error;
 pub mod constants;
 pub mod ntp;
+pub mod time;
+pub mod hotkeys;
+pub mod markers;
+pub mod plugins;
+pub mod gsi;
+pub mod games;
+pub mod library;
+pub mod upload;
 #[cfg(target_os = "windows")]
 pub mod job_object;
 
@@ -16,6 +24,9 @@ use state::RecordingState;
 
 #[cfg_attr(mobile, tauri::mobile_entry_point)]
 pub fn run() {
+  // Anchor the time base before anything records timestamps
+  time::time_base();
+
   tauri::Builder::default()
     .plugin(tauri_plugin_shell::init())
     .plugin(tauri_plugin_fs::init())
@@ -29,16 +40,8 @@ pub fn run() {
         ])
         .build())
     .plugin(tauri_plugin_global_shortcut::Builder::new().with_handler(|app, shortcut, event| {
-        if event.state == tauri_plugin_global_shortcut::ShortcutState::Pressed
-            && shortcut.matches(tauri_plugin_global_shortcut::Modifiers::ALT, tauri_plugin_global_shortcut::Code::F10) {
-                log::info!("Global Hotkey Triggered");
-                let app_handle = app.clone();
-                tauri::async_runtime::spawn(async move {
-                    match crate::commands::replay::save_replay_impl(&app_handle, None).await {
-                        Ok(saved_replay) => log::info!("Replay saved via hotkey: {}", saved_replay.file_path),
-                        Err(e) => log::error!("Failed to save replay via hotkey: {}", e),
-                    }
-                });
+        if event.state == tauri_plugin_global_shortcut::ShortcutState::Pressed {
+            hotkeys::handle(app, shortcut);
         }
     }).build())
     .manage(RecordingState::new())
@@ -49,9 +52,14 @@ pub fn run() {
         commands::system::get_system_info,
         commands::config::get_config,
         commands::config::update_config,
+        commands::config::validate_config,
+        commands::profiles::set_active_profile,
+        commands::profiles::export_profile,
+        commands::profiles::import_profile,
+        commands::benchmark::benchmark_encoders,
         commands::devices::get_audio_devices,
         commands::devices::get_system_audio_devices,
-        commands::devices::get_system_audio_devices,
+        commands::devices::list_audio_devices,
         commands::monitors::get_monitors,
         commands::playback::get_recordings,
         commands::playback::delete_recording,
@@ -59,7 +67,21 @@ pub fn run() {
         commands::playback::show_in_folder,
         commands::playback::open_file,
         commands::playback::generate_thumbnail,
-        commands::upload::upload_clip_to_url
+        commands::upload::upload_clip_to_url,
+        commands::upload::upload_clip_multipart,
+        commands::upload::upload_clip_to_storage,
+        commands::upload::list_uploads,
+        commands::upload::refresh_upload_url,
+        commands::squad::download_clip,
+        commands::hotkeys::get_hotkey_status,
+        commands::hotkeys::validate_hotkey,
+        commands::markers::add_marker,
+        commands::plugins::get_plugin_api_status,
+        commands::plugins::regenerate_plugin_token,
+        commands::gsi::get_cs2_gsi_config,
+        commands::games::get_active_game,
+        commands::clock::get_clock_health,
+        commands::clock::submit_time_sync
     ])
     .setup(|app| {
       #[cfg(debug_assertions)]
@@ -70,7 +92,13 @@ pub fn run() {
       }
       
       // Load config
-      let config = crate::config::AppConfig::load(app.handle());
+      let mut config = crate::config::AppConfig::load(app.handle());
+      let new_plugin_token = plugins::ensure_token(&mut config.plugin_api);
+      if gsi::ensure_token(&mut config.gsi) || new_plugin_token {
+          if let Err(e) = config.save(app.handle()) {
+              log::error!("Failed to save generated tokens: {}", e);
+          }
+      }
       let state = app.state::<RecordingState>();
       match state.config.lock() {
           Ok(mut c) => *c = config.clone(),
@@ -78,7 +106,19 @@ pub fn run() {
       }
 
       // Start NTP Sync
-      state.ntp_manager.start();
+      state.ntp_manager.start(app.handle().clone());
+
+      // Restore the upload queue and resume unfinished uploads
+      let queue_path = app.path().app_data_dir().ok().map(|p| p.join(crate::constants::UPLOAD_QUEUE_FILE));
+      let queue = std::sync::Arc::new(crate::upload::queue::UploadQueue::open(queue_path, commands::upload::queue_listener(app.handle().clone())));
+      app.manage(queue.clone());
+      let app_handle = app.handle().clone();
+      tauri::async_runtime::spawn(async move {
+          let state = app_handle.state::<RecordingState>();
+          if let Err(e) = commands::upload::pump_queue(&queue, &state) {
+              log::error!("Failed to start upload queue: {}", e);
+          }
+      });
 
       // Cleanup Temp Buffer on Startup
       let temp_path_str = config.recording.temp_path.replace("%TEMP%", &std::env::temp_dir().to_string_lossy());
@@ -91,13 +131,24 @@ pub fn run() {
 
 
 
-      // Register Global Shortcut
-      use tauri_plugin_global_shortcut::GlobalShortcutExt;
-      if let Err(e) = app.handle().global_shortcut().register("Alt+F10") {
-          log::error!("Failed to register global shortcut: {}", e);
-      }
+      // Register Global Shortcuts
+      hotkeys::apply(app.handle(), &config.hotkeys);
 
+      // Auto-start the buffer for running games (checks the config on every poll)
+      games::spawn_watcher(app.handle().clone());
 
+      // Local plugin API and game state receiver (opt-in)
+      let app_handle = app.handle().clone();
+      let plugin_api = config.plugin_api.clone();
+      let gsi_config = config.gsi.clone();
+      tauri::async_runtime::spawn(async move {
+          if let Err(e) = plugins::restart(&app_handle, &plugin_api).await {
+              log::error!("Failed to start plugin API: {}", e);
+          }
+          if let Err(e) = gsi::restart(&app_handle, &gsi_config).await {
+              log::error!("Failed to start game state receiver: {}", e);
+          }
+      });
 
       Ok(())
     })
@@ -0,0 +1,63 @@
+//! Clip library metadata.
+//!
+//! Everything known about a saved clip that isn't in the video file itself (markers, the game
+//! it was recorded in) lives in a `<clip>.meta.json` sidecar, which `get_recordings` reads back
+//! and which follows the clip on rename/delete.
+
+use serde::{Deserialize, Serialize};
+use std::path::{Path, PathBuf};
+use crate::markers::ClipMarker;
+
+#[derive(Debug, Clone, PartialEq, Default, Serialize, Deserialize)]
+pub struct ClipMetadata {
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub markers: Vec<ClipMarker>,
+    /// Game detected while the clip was recorded
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub game: Option<String>,
+}
+
+impl ClipMetadata {
+    pub fn is_empty(&self) -> bool {
+        self.markers.is_empty() && self.game.is_none()
+    }
+
+    /// `<clip>.meta.json` next to the clip.
+    pub fn path(clip: &Path) -> PathBuf {
+        let mut name = clip.as_os_str().to_owned();
+        name.push(".meta.json");
+        PathBuf::from(name)
+    }
+
+    /// Metadata recorded for `clip`; empty when there is none.
+    pub fn load(clip: &Path) -> Self {
+        std::fs::read_to_string(Self::path(clip))
+            .ok()
+            .and_then(|s| serde_json::from_str(&s).ok())
+            .unwrap_or_default()
+    }
+
+    pub fn save(&self, clip: &Path) -> Result<(), String> {
+        let json = serde_json::to_string_pretty(self).map_err(|e| e.to_string())?;
+        std::fs::write(Self::path(clip), json).map_err(|e| format!("Failed to write clip metadata: {}", e))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_metadata_round_trip() {
+        let dir = std::env::temp_dir().join(format!("squadsync_library_{}", std::process::id()));
+        std::fs::create_dir_all(&dir).unwrap();
+        let clip = dir.join("Replay.mp4");
+
+        assert!(ClipMetadata::load(&clip).is_empty());
+        let meta = ClipMetadata { markers: vec![ClipMarker { label: "Kill".into(), offset_ms: 1_500 }], game: Some("Counter-Strike 2".into()) };
+        meta.save(&clip).unwrap();
+        assert_eq!(ClipMetadata::load(&clip), meta);
+        assert!(ClipMetadata::path(&clip).to_string_lossy().ends_with("Replay.mp4.meta.json"));
+        let _ = std::fs::remove_dir_all(&dir);
+    }
+}
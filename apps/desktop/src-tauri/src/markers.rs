@@ -0,0 +1,170 @@
+//! Event Markers
+//!
+//! Bookmarks (kills, deaths, callouts) dropped into the replay buffer and carried into saved clips.
+//!
+//! # Architecture
+//!
+//! * `MarkerLog`: NTP-stamped markers kept next to the segments in `markers.json`, pruned with
+//!   the buffer retention so they survive an app restart exactly as long as the video does.
+//! * `clip_markers`: The markers inside a saved clip's window, as offsets from the clip start.
+//! * `ffmetadata`: Renders those offsets as an FFMETADATA chapter file for the merge step.
+//!
+//! The clip's markers are also kept in its library metadata (`crate::library`).
+
+use serde::{Deserialize, Serialize};
+use std::path::{Path, PathBuf};
+
+pub const MARKER_ADDED_EVENT: &str = "marker-added";
+
+/// A marker in the buffer. `timestamp_ms` is NTP-corrected Unix time.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Marker {
+    pub label: String,
+    pub timestamp_ms: u64,
+}
+
+/// A marker inside a saved clip, relative to the start of the clip.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct ClipMarker {
+    pub label: String,
+    pub offset_ms: u64,
+}
+
+/// Markers of the current buffer directory.
+#[derive(Default)]
+pub struct MarkerLog {
+    dir: Option<PathBuf>,
+    markers: Vec<Marker>,
+}
+
+impl MarkerLog {
+    /// Adds a marker and persists the log to `dir`, dropping markers older than `retention_ms`.
+    /// Markers further than `retention_ms` from now (either way) are rejected: the buffer holds
+    /// no video for them.
+    pub fn add(&mut self, dir: &Path, marker: Marker, now_ms: u64, retention_ms: u64) -> Result<(), String> {
+        if marker.timestamp_ms.abs_diff(now_ms) > retention_ms {
+            return Err(format!("Marker time {} is more than {} ms away from now ({})", marker.timestamp_ms, retention_ms, now_ms));
+        }
+        self.load(dir);
+        self.markers.push(marker);
+        self.markers.retain(|m| m.timestamp_ms.abs_diff(now_ms) <= retention_ms);
+        self.markers.sort_by_key(|m| m.timestamp_ms);
+        self.save(dir)
+    }
+
+    /// Markers in `dir` whose timestamp falls in `[start_ms, end_ms]`.
+    pub fn between(&mut self, dir: &Path, start_ms: u64, end_ms: u64) -> Vec<Marker> {
+        self.load(dir);
+        self.markers.iter().filter(|m| (start_ms..=end_ms).contains(&m.timestamp_ms)).cloned().collect()
+    }
+
+    /// (Re)loads from disk when the buffer directory changed (or on first use).
+    fn load(&mut self, dir: &Path) {
+        if self.dir.as_deref() == Some(dir) {
+            return;
+        }
+        self.markers = std::fs::read_to_string(dir.join(crate::constants::MARKERS_FILE))
+            .ok()
+            .and_then(|s| serde_json::from_str(&s).ok())
+            .unwrap_or_default();
+        self.dir = Some(dir.to_path_buf());
+    }
+
+    fn save(&self, dir: &Path) -> Result<(), String> {
+        std::fs::create_dir_all(dir).map_err(|e| format!("Failed to create {}: {}", dir.display(), e))?;
+        let json = serde_json::to_string_pretty(&self.markers).map_err(|e| e.to_string())?;
+        std::fs::write(dir.join(crate::constants::MARKERS_FILE), json).map_err(|e| format!("Failed to save markers: {}", e))
+    }
+}
+
+/// Markers that fall inside a clip starting at `clip_start_ms` (NTP) and lasting `duration_ms`.
+/// The end is inclusive: a clip saved at a marker's moment ends exactly on it.
+pub fn clip_markers(markers: &[Marker], clip_start_ms: u64, duration_ms: u64) -> Vec<ClipMarker> {
+    let mut out: Vec<ClipMarker> = markers
+        .iter()
+        .filter(|m| (clip_start_ms..=clip_start_ms + duration_ms).contains(&m.timestamp_ms))
+        .map(|m| ClipMarker { label: m.label.clone(), offset_ms: m.timestamp_ms - clip_start_ms })
+        .collect();
+    out.sort_by_key(|m| m.offset_ms);
+    out
+}
+
+/// FFMETADATA1 document with one chapter per marker, each running until the next marker
+/// (or the end of the clip). Times are in milliseconds.
+pub fn ffmetadata(markers: &[ClipMarker], duration_ms: u64) -> String {
+    let mut out = String::from(";FFMETADATA1\n");
+    for (i, marker) in markers.iter().enumerate() {
+        let end = markers.get(i + 1).map(|next| next.offset_ms).unwrap_or(duration_ms).max(marker.offset_ms);
+        out.push_str("[CHAPTER]\nTIMEBASE=1/1000\n");
+        out.push_str(&format!("START={}\nEND={}\ntitle={}\n", marker.offset_ms, end, escape_ffmetadata(&marker.label)));
+    }
+    out
+}
+
+/// FFMETADATA values escape `=`, `;`, `#`, `\` and newlines with a backslash.
+fn escape_ffmetadata(value: &str) -> String {
+    let mut out = String::with_capacity(value.len());
+    for c in value.chars() {
+        if matches!(c, '=' | ';' | '#' | '\\' | '\n') {
+            out.push('\\');
+        }
+        out.push(c);
+    }
+    out
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn marker(label: &str, timestamp_ms: u64) -> Marker {
+        Marker { label: label.to_string(), timestamp_ms }
+    }
+
+    #[test]
+    fn test_clip_markers_window() {
+        let markers = [marker("before", 999), marker("kill", 1_000), marker("death", 5_500), marker("trigger", 31_000), marker("after", 31_001)];
+        let clip = clip_markers(&markers, 1_000, 30_000);
+        assert_eq!(clip, vec![
+            ClipMarker { label: "kill".into(), offset_ms: 0 },
+            ClipMarker { label: "death".into(), offset_ms: 4_500 },
+            ClipMarker { label: "trigger".into(), offset_ms: 30_000 },
+        ]);
+    }
+
+    #[test]
+    fn test_ffmetadata_chapters() {
+        let markers = [
+            ClipMarker { label: "Kill".into(), offset_ms: 2_000 },
+            ClipMarker { label: "a=b; #1\\".into(), offset_ms: 9_000 },
+        ];
+        assert_eq!(ffmetadata(&markers, 30_000), concat!(
+            ";FFMETADATA1\n",
+            "[CHAPTER]\nTIMEBASE=1/1000\nSTART=2000\nEND=9000\ntitle=Kill\n",
+            "[CHAPTER]\nTIMEBASE=1/1000\nSTART=9000\nEND=30000\ntitle=a\\=b\\; \\#1\\\\\n",
+        ));
+    }
+
+    #[test]
+    fn test_marker_log_persists_and_prunes() {
+        let dir = std::env::temp_dir().join(format!("squadsync_markers_{}", std::process::id()));
+        let _ = std::fs::remove_dir_all(&dir);
+
+        let mut log = MarkerLog::default();
+        log.add(&dir, marker("old", 1_000), 1_000, 60_000).unwrap();
+        log.add(&dir, marker("callout", 70_000), 70_000, 60_000).unwrap();
+        log.add(&dir, marker("kill", 65_000), 70_000, 60_000).unwrap();
+
+        // A fresh log (app restart) reads the same markers back, oldest pruned
+        let mut reloaded = MarkerLog::default();
+        let all = reloaded.between(&dir, 0, u64::MAX);
+        assert_eq!(all, vec![marker("kill", 65_000), marker("callout", 70_000)]);
+        assert_eq!(reloaded.between(&dir, 66_000, 80_000), vec![marker("callout", 70_000)]);
+
+        // Markers the buffer can't hold video for are refused
+        assert!(log.add(&dir, marker("future", u64::MAX), 70_000, 60_000).is_err());
+        assert!(log.add(&dir, marker("ancient", 1), 70_000, 60_000).is_err());
+        assert_eq!(MarkerLog::default().between(&dir, 0, u64::MAX).len(), 2);
+        let _ = std::fs::remove_dir_all(&dir);
+    }
+}
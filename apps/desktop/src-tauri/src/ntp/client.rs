@@ -0,0 +1,112 @@
+//! Querying SNTP servers. Blocking: run on a blocking thread.
+
+use rsntp::SntpClient;
+use std::time::Duration;
+
+use super::select::Sample;
+use super::source::ClockSource;
+use crate::constants::{NTP_QUERY_TIMEOUT_MS, NTP_SAMPLE_GAP_MS};
+
+/// Takes `samples` samples from each server, querying the servers in parallel so an
+/// unreachable one only costs one timeout per sample.
+pub fn collect_samples(servers: &[String], samples: u32) -> Vec<Sample> {
+    std::thread::scope(|scope| {
+        let handles: Vec<_> = servers
+            .iter()
+            .map(|server| scope.spawn(move || query_server(server, samples)))
+            .collect();
+        handles.into_iter().flat_map(|h| h.join().unwrap_or_default()).collect()
+    })
+}
+
+/// `server` is a host name or address, optionally with a port (default 123).
+fn query_server(server: &str, samples: u32) -> Vec<Sample> {
+    let mut client = SntpClient::new();
+    client.set_timeout(Duration::from_millis(NTP_QUERY_TIMEOUT_MS));
+    let mut collected = Vec::new();
+
+    for i in 1..=samples.max(1) {
+        if i > 1 {
+            // Small delay between samples to avoid flooding
+            std::thread::sleep(Duration::from_millis(NTP_SAMPLE_GAP_MS));
+        }
+        match client.synchronize(server) {
+            Ok(result) => {
+                let sample = Sample {
+                    server: server.to_string(),
+                    source: ClockSource::Ntp,
+                    offset_ms: result.clock_offset().as_secs_f64() * 1000.0,
+                    delay_ms: result.round_trip_delay().as_secs_f64() * 1000.0,
+                };
+                log::debug!("NTP sample {}/{} from {}: offset={:.2}ms, delay={:.2}ms", i, samples, server, sample.offset_ms, sample.delay_ms);
+                collected.push(sample);
+            }
+            Err(e) => {
+                log::warn!("NTP sample {}/{} from {} failed: {}", i, samples, server, e);
+                // Don't wait out the remaining timeouts for a server that is down
+                if collected.is_empty() && i >= 2 {
+                    break;
+                }
+            }
+        }
+    }
+    collected
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::ntp::select::select;
+    use std::net::UdpSocket;
+    use std::time::{SystemTime, UNIX_EPOCH};
+
+    const NTP_UNIX_OFFSET_SECS: u64 = 2_208_988_800;
+
+    fn ntp_timestamp(offset_ms: i64) -> [u8; 8] {
+        let now = SystemTime::now().duration_since(UNIX_EPOCH).unwrap();
+        let nanos = now.as_nanos() as i64 + offset_ms * 1_000_000;
+        let secs = (nanos / 1_000_000_000) as u64 + NTP_UNIX_OFFSET_SECS;
+        let frac = ((nanos % 1_000_000_000) as u64) * (1u64 << 32) / 1_000_000_000;
+        let mut bytes = [0u8; 8];
+        bytes[..4].copy_from_slice(&(secs as u32).to_be_bytes());
+        bytes[4..].copy_from_slice(&(frac as u32).to_be_bytes());
+        bytes
+    }
+
+    /// Answers SNTP requests with its clock `offset_ms` ahead of ours. Returns "127.0.0.1:port".
+    fn mock_server(offset_ms: i64) -> String {
+        let socket = UdpSocket::bind("127.0.0.1:0").unwrap();
+        let addr = socket.local_addr().unwrap().to_string();
+        std::thread::spawn(move || {
+            let mut request = [0u8; 48];
+            while let Ok((_, peer)) = socket.recv_from(&mut request) {
+                let mut reply = [0u8; 48];
+                reply[0] = 0x24; // no leap warning, version 4, server mode
+                reply[1] = 1; // stratum
+                reply[12..16].copy_from_slice(b"MOCK");
+                reply[16..24].copy_from_slice(&ntp_timestamp(offset_ms));
+                reply[24..32].copy_from_slice(&request[40..48]);
+                reply[32..40].copy_from_slice(&ntp_timestamp(offset_ms));
+                reply[40..48].copy_from_slice(&ntp_timestamp(offset_ms));
+                let _ = socket.send_to(&reply, peer);
+            }
+        });
+        addr
+    }
+
+    #[test]
+    fn test_mock_servers_with_falseticker() {
+        let servers = vec![mock_server(250), mock_server(-9_000), mock_server(251), mock_server(250)];
+        // Nothing listens on the unreachable one
+        let unreachable = UdpSocket::bind("127.0.0.1:0").unwrap().local_addr().unwrap().to_string();
+        let mut all = servers.clone();
+        all.push(unreachable);
+
+        let samples = collect_samples(&all, 3);
+        assert_eq!(samples.len(), 12);
+        let selection = select(&super::super::select::best_per_server(&samples)).unwrap();
+        assert_eq!(selection.rejected, vec![servers[1].clone()]);
+        assert_eq!(selection.servers.len(), 3);
+        assert!((selection.offset_ms - 250.0).abs() < 5.0, "{}", selection.offset_ms);
+    }
+}
@@ -0,0 +1,191 @@
+//! Offset model between syncs: last offset plus the measured drift of the local clock.
+//! Local times are `crate::time` microseconds; offsets are stored in microseconds too.
+
+use serde::{Deserialize, Serialize};
+use std::collections::VecDeque;
+
+use super::select::Selection;
+use super::source::ClockSource;
+use crate::constants::{
+    NTP_DRIFT_HISTORY, NTP_DRIFT_MIN_SPAN_US, NTP_MAX_DRIFT_PPM, NTP_MAX_SYNCED_UNCERTAINTY_MS,
+    NTP_STEP_THRESHOLD_US, NTP_UNCERTAINTY_GROWTH_PPM,
+};
+
+/// How far the app's clock can be trusted. Saved replays carry a copy.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct ClockHealth {
+    /// Synced recently enough that `uncertainty_ms` is within the threshold
+    pub synced: bool,
+    pub source: ClockSource,
+    /// Servers (or the signaling server) that agreed in the last successful sync
+    pub servers: Vec<String>,
+    /// NTP time of the last successful sync
+    pub last_sync_ms: Option<u64>,
+    /// Current error bound: the sync's own plus what the clock may have wandered since.
+    /// None before the first sync.
+    pub uncertainty_ms: Option<f64>,
+    pub offset_ms: f64,
+    pub drift_ppm: f64,
+}
+
+#[derive(Debug, Clone, Copy)]
+struct SyncPoint {
+    local_us: i64,
+    offset_us: i64,
+}
+
+#[derive(Debug, Default)]
+pub struct ClockModel {
+    history: VecDeque<SyncPoint>,
+    drift_ppm: f64,
+    uncertainty_ms: f64,
+    source: Option<ClockSource>,
+    servers: Vec<String>,
+    /// `crate::time` generation the history was measured in
+    generation: u64,
+}
+
+impl ClockModel {
+    /// Drops everything measured in an older time base generation. Returns whether it did.
+    pub fn rebase(&mut self, generation: u64) -> bool {
+        if generation == self.generation {
+            return false;
+        }
+        *self = Self { generation, ..Self::default() };
+        true
+    }
+
+    /// Adds a sync result measured at local time `local_us`. The selection's offset must be
+    /// relative to the local (time base) clock.
+    pub fn record(&mut self, local_us: i64, selection: Selection) {
+        let offset_us = (selection.offset_ms * 1000.0).round() as i64;
+        // The offset jumped: the old points describe a different clock
+        if let Some(last) = self.history.back() {
+            if (self.offset_us_at(local_us) - offset_us).abs() > NTP_STEP_THRESHOLD_US || local_us < last.local_us {
+                log::info!("Clock step detected, resetting drift history");
+                self.history.clear();
+                self.drift_ppm = 0.0;
+            }
+        }
+
+        self.history.push_back(SyncPoint { local_us, offset_us });
+        while self.history.len() > NTP_DRIFT_HISTORY {
+            self.history.pop_front();
+        }
+        self.uncertainty_ms = selection.uncertainty_ms;
+        self.source = Some(selection.source);
+        self.servers = selection.servers;
+
+        if let (Some(first), Some(last)) = (self.history.front(), self.history.back()) {
+            if last.local_us - first.local_us >= NTP_DRIFT_MIN_SPAN_US {
+                self.drift_ppm = (slope(&self.history) * 1e6).clamp(-NTP_MAX_DRIFT_PPM, NTP_MAX_DRIFT_PPM);
+            }
+        }
+    }
+
+    /// Offset at local time `local_us`, extrapolated from the last sync with the drift rate.
+    pub fn offset_us_at(&self, local_us: i64) -> i64 {
+        match self.history.back() {
+            Some(last) => last.offset_us + (self.drift_ppm * 1e-6 * (local_us - last.local_us) as f64).round() as i64,
+            None => 0,
+        }
+    }
+
+    pub fn health(&self, local_us: i64) -> ClockHealth {
+        let offset_ms = self.offset_us_at(local_us) as f64 / 1000.0;
+        let Some(last) = self.history.back() else {
+            return ClockHealth {
+                synced: false,
+                source: ClockSource::System,
+                servers: Vec::new(),
+                last_sync_ms: None,
+                uncertainty_ms: None,
+                offset_ms,
+                drift_ppm: 0.0,
+            };
+        };
+        let age_ms = (local_us - last.local_us).max(0) as f64 / 1000.0;
+        let uncertainty_ms = self.uncertainty_ms + age_ms * NTP_UNCERTAINTY_GROWTH_PPM * 1e-6;
+        ClockHealth {
+            synced: uncertainty_ms <= NTP_MAX_SYNCED_UNCERTAINTY_MS,
+            source: self.source.unwrap_or(ClockSource::System),
+            servers: self.servers.clone(),
+            last_sync_ms: Some((last.local_us + last.offset_us).max(0) as u64 / 1000),
+            uncertainty_ms: Some(uncertainty_ms),
+            offset_ms,
+            drift_ppm: self.drift_ppm,
+        }
+    }
+}
+
+/// Least-squares slope of offset over local time (µs per µs).
+fn slope(points: &VecDeque<SyncPoint>) -> f64 {
+    let n = points.len() as f64;
+    // Relative to the first point to keep the products small
+    let t0 = points[0].local_us;
+    let xs: Vec<f64> = points.iter().map(|p| (p.local_us - t0) as f64).collect();
+    let mean_x = xs.iter().sum::<f64>() / n;
+    let mean_y = points.iter().map(|p| p.offset_us as f64).sum::<f64>() / n;
+    let (mut num, mut den) = (0.0, 0.0);
+    for (x, p) in xs.iter().zip(points) {
+        num += (x - mean_x) * (p.offset_us as f64 - mean_y);
+        den += (x - mean_x) * (x - mean_x);
+    }
+    if den == 0.0 {
+        0.0
+    } else {
+        num / den
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn selection(offset_ms: f64) -> Selection {
+        Selection { offset_ms, source: ClockSource::Ntp, uncertainty_ms: 5.0, servers: vec!["a".into()], rejected: Vec::new() }
+    }
+
+    #[test]
+    fn test_drift_extrapolates_between_syncs() {
+        let mut model = ClockModel::default();
+        let health = model.health(1_000);
+        assert!(!health.synced);
+        assert_eq!(health.source, ClockSource::System);
+
+        // The local clock loses 20 ms every 15 minutes (~22 ppm)
+        let step = 15 * 60 * 1_000_000;
+        for i in 0..4 {
+            model.record(1_000_000_000 + i * step, selection(100.0 + i as f64 * 20.0));
+        }
+        let drift = model.health(0).drift_ppm;
+        assert!((drift - 22.2).abs() < 0.1, "{}", drift);
+        let later = 1_000_000_000 + 3 * step + step / 2;
+        assert_eq!(model.offset_us_at(later), 170_000);
+
+        let health = model.health(later);
+        assert!(health.synced);
+        assert_eq!(health.source, ClockSource::Ntp);
+        assert_eq!(health.last_sync_ms, Some(((1_000_000_000 + 3 * step) / 1000 + 160) as u64));
+        assert!(health.uncertainty_ms.unwrap() > 5.0);
+
+        // Hours without a sync
+        assert!(!model.health(later + 48 * 3_600_000_000).synced);
+
+        // A re-anchored time base invalidates the model
+        assert!(!model.rebase(0));
+        assert!(model.rebase(1));
+        assert_eq!(model.health(later).source, ClockSource::System);
+    }
+
+    #[test]
+    fn test_clock_step_resets_drift() {
+        let mut model = ClockModel::default();
+        model.record(0, selection(0.0));
+        model.record(NTP_DRIFT_MIN_SPAN_US, selection(30.0));
+        assert!(model.health(0).drift_ppm > 0.0);
+        model.record(2 * NTP_DRIFT_MIN_SPAN_US, selection(3_600_000.0));
+        assert_eq!(model.health(0).drift_ppm, 0.0);
+        assert_eq!(model.offset_us_at(3 * NTP_DRIFT_MIN_SPAN_US), 3_600_000_000);
+    }
+}
@@ -0,0 +1,195 @@
+//! Clock Sync
+//!
+//! Keeps an offset between the system clock and NTP time so clips saved by different squad
+//! members line up.
+//!
+//! # Architecture
+//!
+//! * `source`: The `TimeSource` trait: NTP servers, and the room server's `TIME_SYNC` exchange
+//!   relayed by the frontend (works where NTP is blocked and keeps a squad on one clock).
+//! * `client`: Takes SNTP samples from every configured server in parallel.
+//! * `select`: Keeps each server's lowest-delay sample and rejects falsetickers (Marzullo).
+//! * `clock`: Tracks the drift of the local clock across syncs, extrapolates the offset between
+//!   them, and reports `ClockHealth`.
+//!
+//! The sync loop re-reads `[clock]` before every sync. A failed sync retries sooner than the
+//! regular interval; until the first success the offset stays 0 and health reports "system".
+//! Signaling samples arriving while the clock is unsynced trigger a sync straight away.
+//!
+//! The model runs on `crate::time` (monotonic-anchored, microseconds), so OS clock adjustments
+//! between syncs don't move NTP time. `get_offset` is relative to the system clock, for
+//! converting system timestamps such as segment file names.
+
+pub mod client;
+pub mod clock;
+pub mod select;
+pub mod source;
+
+use std::sync::{Arc, Mutex, MutexGuard};
+use std::time::Duration;
+use tauri::{AppHandle, Emitter, Manager};
+use tokio::sync::Notify;
+
+use crate::state::RecordingState;
+use crate::time::{system_now_us, time_base};
+use clock::{ClockHealth, ClockModel};
+use source::{NtpSource, SignalingSource, TimeSource, TimeSyncExchange};
+
+pub const CLOCK_HEALTH_EVENT: &str = "clock-health";
+
+pub struct NtpManager {
+    model: Arc<Mutex<ClockModel>>,
+    signaling: Arc<SignalingSource>,
+    wake: Arc<Notify>,
+}
+
+impl Default for NtpManager {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl NtpManager {
+    pub fn new() -> Self {
+        Self {
+            model: Arc::new(Mutex::new(ClockModel::default())),
+            signaling: Arc::new(SignalingSource::default()),
+            wake: Arc::new(Notify::new()),
+        }
+    }
+
+    /// Starts the background sync loop. Runs for the lifetime of the app.
+    pub fn start(&self, app: AppHandle) {
+        let model = self.model.clone();
+        let signaling = self.signaling.clone();
+        let wake = self.wake.clone();
+
+        tauri::async_runtime::spawn(async move {
+            let mut retry_secs = crate::constants::NTP_RETRY_SECS as u64;
+            loop {
+                let settings = match app.state::<RecordingState>().config.lock() {
+                    Ok(config) => config.clock.clone(),
+                    Err(e) => {
+                        log::error!("Failed to lock config: {}", e);
+                        return;
+                    }
+                };
+                let interval_secs = settings.sync_interval_secs.max(crate::constants::NTP_RETRY_SECS) as u64;
+
+                let sources: Vec<Arc<dyn TimeSource>> = vec![
+                    Arc::new(NtpSource { servers: settings.ntp_servers, samples_per_server: settings.samples_per_server }),
+                    signaling.clone(),
+                ];
+                let wait_secs = match Self::sync(&model, &wake, sources).await {
+                    Ok(health) => {
+                        if let Err(e) = app.emit(CLOCK_HEALTH_EVENT, &health) {
+                            log::error!("Failed to emit {}: {}", CLOCK_HEALTH_EVENT, e);
+                        }
+                        retry_secs = crate::constants::NTP_RETRY_SECS as u64;
+                        interval_secs
+                    }
+                    Err(e) => {
+                        log::warn!("Clock sync failed: {}. Using previous offset, retrying in {}s.", e, retry_secs);
+                        let wait = retry_secs.min(interval_secs);
+                        retry_secs = (retry_secs * 2).min(interval_secs);
+                        wait
+                    }
+                };
+                if tokio::time::timeout(Duration::from_secs(wait_secs), wake.notified()).await.is_ok() {
+                    log::info!("New time samples while unsynced, syncing now");
+                }
+            }
+        });
+    }
+
+    async fn sync(model: &Mutex<ClockModel>, wake: &Notify, sources: Vec<Arc<dyn TimeSource>>) -> Result<ClockHealth, String> {
+        log::info!("Starting clock sync...");
+
+        let samples = tauri::async_runtime::spawn_blocking(move || {
+            // Sources are queried in parallel: NTP may sit out its timeouts
+            std::thread::scope(|scope| {
+                let handles: Vec<_> = sources.iter().map(|source| scope.spawn(|| (source.name().to_string(), source.collect()))).collect();
+                handles.into_iter().filter_map(|h| h.join().ok()).flat_map(|(name, samples)| {
+                    log::debug!("{} sample(s) from {}", samples.len(), name);
+                    samples
+                }).collect::<Vec<_>>()
+            })
+        })
+        .await
+        .map_err(|e| format!("Clock sync task panicked: {}", e))?;
+        let best = select::best_per_server(&samples);
+        let mut selection = select::select(&best).ok_or("No time source answered")?;
+        if !selection.rejected.is_empty() {
+            log::warn!("Ignoring NTP server(s) that disagree with the rest: {}", selection.rejected.join(", "));
+        }
+        log::info!(
+            "Clock sync successful. Offset: {:.2} ms (±{:.2} ms) from {}",
+            selection.offset_ms, selection.uncertainty_ms, selection.servers.join(", ")
+        );
+
+        // Samples are relative to the system clock; the model is relative to the time base
+        let mut model = lock_model(model, wake)?;
+        let (now_us, system_us) = (time_base().now_us(), system_now_us());
+        selection.offset_ms += (system_us - now_us) as f64 / 1000.0;
+        model.record(now_us, selection);
+        Ok(model.health(now_us))
+    }
+
+    /// Offset of NTP time from the system clock, in microseconds.
+    pub fn get_offset_us(&self) -> i64 {
+        let (now_us, system_us) = (time_base().now_us(), system_now_us());
+        match lock_model(&self.model, &self.wake) {
+            Ok(model) => model.offset_us_at(now_us) + now_us - system_us,
+            Err(e) => {
+                log::error!("{}", e);
+                now_us - system_us
+            }
+        }
+    }
+
+    /// Offset of NTP time from the system clock, in milliseconds.
+    pub fn get_offset(&self) -> i64 {
+        (self.get_offset_us() as f64 / 1000.0).round() as i64
+    }
+
+    /// NTP-corrected Unix time in microseconds.
+    pub fn get_ntp_time_us(&self) -> i64 {
+        let now_us = time_base().now_us();
+        match lock_model(&self.model, &self.wake) {
+            Ok(model) => now_us + model.offset_us_at(now_us),
+            Err(e) => {
+                log::error!("{}", e);
+                now_us
+            }
+        }
+    }
+
+    pub fn get_ntp_time_ms(&self) -> u64 {
+        self.get_ntp_time_us().max(0) as u64 / 1000
+    }
+
+    /// Adds a room server time sync exchange. Wakes the sync loop if the clock isn't synced.
+    pub fn add_signaling_sample(&self, exchange: &TimeSyncExchange) -> Result<(), String> {
+        self.signaling.push(exchange)?;
+        if !self.health()?.synced {
+            self.wake.notify_one();
+        }
+        Ok(())
+    }
+
+    pub fn health(&self) -> Result<ClockHealth, String> {
+        let now_us = time_base().now_us();
+        Ok(lock_model(&self.model, &self.wake)?.health(now_us))
+    }
+}
+
+/// Locks the model, dropping it if the time base re-anchored since it was measured (and waking
+/// the sync loop to measure again).
+fn lock_model<'a>(model: &'a Mutex<ClockModel>, wake: &Notify) -> Result<MutexGuard<'a, ClockModel>, String> {
+    let mut model = model.lock().map_err(|e| format!("Failed to lock clock model: {}", e))?;
+    if model.rebase(time_base().generation()) {
+        log::warn!("Time base re-anchored, clock unsynced until the next sync");
+        wake.notify_one();
+    }
+    Ok(model)
+}
@@ -0,0 +1,162 @@
+//! Combining samples from several servers into one offset.
+
+use super::source::ClockSource;
+use crate::constants::NTP_MIN_ERROR_MS;
+
+/// One exchange with a time server. `offset_ms` is server time minus local time.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Sample {
+    pub server: String,
+    pub source: ClockSource,
+    pub offset_ms: f64,
+    pub delay_ms: f64,
+}
+
+impl Sample {
+    /// The range the true offset must lie in: the offset is only known to within half the
+    /// round trip.
+    fn interval(&self) -> (f64, f64) {
+        let error = self.delay_ms.max(0.0) / 2.0 + NTP_MIN_ERROR_MS;
+        (self.offset_ms - error, self.offset_ms + error)
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct Selection {
+    pub offset_ms: f64,
+    /// NTP if any agreeing server is an NTP server
+    pub source: ClockSource,
+    /// Half-width of the range the agreeing servers leave for the offset
+    pub uncertainty_ms: f64,
+    pub servers: Vec<String>,
+    /// Servers that disagreed with the majority (falsetickers)
+    pub rejected: Vec<String>,
+}
+
+/// The lowest-delay sample of each server, in first-seen server order.
+pub fn best_per_server(samples: &[Sample]) -> Vec<Sample> {
+    let mut best: Vec<Sample> = Vec::new();
+    for sample in samples {
+        match best.iter_mut().find(|b| b.server == sample.server) {
+            Some(b) if sample.delay_ms < b.delay_ms => *b = sample.clone(),
+            Some(_) => {}
+            None => best.push(sample.clone()),
+        }
+    }
+    best
+}
+
+/// Picks the offset from one sample per server.
+///
+/// Marzullo's algorithm finds the range most servers agree on. Servers whose interval misses
+/// it are rejected, and the median of the rest is the offset. Without a majority (e.g. two
+/// servers that disagree) there's no telling who is wrong: the median of all of them is used
+/// with an uncertainty covering the spread.
+pub fn select(samples: &[Sample]) -> Option<Selection> {
+    if samples.is_empty() {
+        return None;
+    }
+
+    // Starts sort before ends at the same value so touching intervals count as overlapping
+    let mut edges: Vec<(f64, i32)> = samples
+        .iter()
+        .flat_map(|s| {
+            let (lo, hi) = s.interval();
+            [(lo, -1), (hi, 1)]
+        })
+        .collect();
+    edges.sort_by(|a, b| a.0.total_cmp(&b.0).then(a.1.cmp(&b.1)));
+
+    let (mut count, mut best_count) = (0, 0);
+    let (mut best_lo, mut best_hi) = (0.0, 0.0);
+    for (i, &(value, kind)) in edges.iter().enumerate() {
+        count -= kind;
+        if kind == -1 && count > best_count {
+            best_count = count;
+            best_lo = value;
+            best_hi = edges[i + 1].0;
+        }
+    }
+
+    if (best_count as usize) * 2 <= samples.len() && samples.len() > 1 {
+        let offsets: Vec<f64> = samples.iter().map(|s| s.offset_ms).collect();
+        let spread = offsets.iter().cloned().fold(f64::MIN, f64::max) - offsets.iter().cloned().fold(f64::MAX, f64::min);
+        let max_error = samples.iter().map(|s| s.delay_ms / 2.0 + NTP_MIN_ERROR_MS).fold(0.0, f64::max);
+        return Some(Selection {
+            offset_ms: median(offsets),
+            source: combined_source(samples.iter()),
+            uncertainty_ms: spread / 2.0 + max_error,
+            servers: samples.iter().map(|s| s.server.clone()).collect(),
+            rejected: Vec::new(),
+        });
+    }
+
+    let (truechimers, falsetickers): (Vec<&Sample>, Vec<&Sample>) = samples.iter().partition(|s| {
+        let (lo, hi) = s.interval();
+        lo <= best_hi && hi >= best_lo
+    });
+    Some(Selection {
+        offset_ms: median(truechimers.iter().map(|s| s.offset_ms).collect()),
+        source: combined_source(truechimers.iter().copied()),
+        uncertainty_ms: (best_hi - best_lo) / 2.0,
+        servers: truechimers.iter().map(|s| s.server.clone()).collect(),
+        rejected: falsetickers.iter().map(|s| s.server.clone()).collect(),
+    })
+}
+
+fn combined_source<'a>(mut samples: impl Iterator<Item = &'a Sample>) -> ClockSource {
+    if samples.any(|s| s.source == ClockSource::Ntp) {
+        ClockSource::Ntp
+    } else {
+        ClockSource::Signaling
+    }
+}
+
+fn median(mut values: Vec<f64>) -> f64 {
+    values.sort_by(f64::total_cmp);
+    let mid = values.len() / 2;
+    if values.len() % 2 == 0 {
+        (values[mid - 1] + values[mid]) / 2.0
+    } else {
+        values[mid]
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn sample(server: &str, offset_ms: f64, delay_ms: f64) -> Sample {
+        Sample { server: server.to_string(), source: ClockSource::Ntp, offset_ms, delay_ms }
+    }
+
+    #[test]
+    fn test_rejects_falseticker() {
+        let samples = best_per_server(&[
+            sample("a", 120.0, 40.0),
+            sample("a", 101.0, 10.0),
+            sample("b", 104.0, 20.0),
+            sample("c", 2_500.0, 8.0),
+            sample("d", 98.0, 30.0),
+        ]);
+        assert_eq!(samples.len(), 4);
+        let selection = select(&samples).unwrap();
+        assert_eq!(selection.rejected, vec!["c"]);
+        assert_eq!(selection.servers, vec!["a", "b", "d"]);
+        assert_eq!(selection.offset_ms, 101.0);
+        // a: 95..107, b: 93..115, d: 82..114 -> 95..107
+        assert_eq!(selection.uncertainty_ms, 6.0);
+    }
+
+    #[test]
+    fn test_no_majority_falls_back_to_median() {
+        let selection = select(&[sample("a", 0.0, 10.0), sample("b", 1_000.0, 10.0)]).unwrap();
+        assert!(selection.rejected.is_empty());
+        assert_eq!(selection.offset_ms, 500.0);
+        assert_eq!(selection.uncertainty_ms, 506.0);
+
+        let single = select(&[sample("a", 42.0, 10.0)]).unwrap();
+        assert_eq!((single.offset_ms, single.uncertainty_ms), (42.0, 6.0));
+        assert!(select(&[]).is_none());
+    }
+}
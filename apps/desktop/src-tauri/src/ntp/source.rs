@@ -0,0 +1,168 @@
+//! Where offset samples come from: public NTP servers and the room's signaling server.
+
+use serde::{Deserialize, Serialize};
+use std::collections::VecDeque;
+use std::sync::Mutex;
+
+use super::client;
+use super::select::Sample;
+use crate::constants::{SIGNALING_MAX_SAMPLES, SIGNALING_SAMPLE_MAX_AGE_MS, SIGNALING_SOURCE_NAME};
+
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum ClockSource {
+    Ntp,
+    /// Only the room server answered: consistent within the squad, as good as its clock
+    Signaling,
+    /// Never synced: plain system time
+    System,
+}
+
+/// A provider of offset samples. `collect` may block; the sync loop calls it on a
+/// blocking thread.
+pub trait TimeSource: Send + Sync {
+    fn name(&self) -> &str;
+    fn collect(&self) -> Vec<Sample>;
+}
+
+/// Queries public NTP servers.
+pub struct NtpSource {
+    pub servers: Vec<String>,
+    pub samples_per_server: u32,
+}
+
+impl TimeSource for NtpSource {
+    fn name(&self) -> &str {
+        "ntp"
+    }
+
+    fn collect(&self) -> Vec<Sample> {
+        client::collect_samples(&self.servers, self.samples_per_server)
+    }
+}
+
+/// One `TIME_SYNC_REQUEST`/`TIME_SYNC_RESPONSE` round trip, as relayed by the frontend.
+/// Client times are the webview's `Date.now()`, i.e. the same system clock NTP corrects.
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct TimeSyncExchange {
+    pub client_send: u64,
+    pub server_receive: u64,
+    pub server_send: u64,
+    pub client_receive: u64,
+}
+
+impl TimeSyncExchange {
+    /// The usual NTP four-timestamp math. Errors on timestamps that can't be from one exchange.
+    pub fn to_sample(&self) -> Result<Sample, String> {
+        let (t1, t2, t3, t4) = (
+            self.client_send as f64,
+            self.server_receive as f64,
+            self.server_send as f64,
+            self.client_receive as f64,
+        );
+        if t4 < t1 || t3 < t2 {
+            return Err("Time sync timestamps out of order".to_string());
+        }
+        Ok(Sample {
+            server: SIGNALING_SOURCE_NAME.to_string(),
+            source: ClockSource::Signaling,
+            offset_ms: ((t2 - t1) + (t3 - t4)) / 2.0,
+            delay_ms: (t4 - t1) - (t3 - t2),
+        })
+    }
+}
+
+/// Buffers exchanges pushed by the frontend until the next sync uses them.
+#[derive(Default)]
+pub struct SignalingSource {
+    samples: Mutex<VecDeque<(u64, Sample)>>,
+}
+
+impl SignalingSource {
+    pub fn push(&self, exchange: &TimeSyncExchange) -> Result<(), String> {
+        let sample = exchange.to_sample()?;
+        let mut samples = self.samples.lock().map_err(|e| e.to_string())?;
+        samples.push_back((exchange.client_receive, sample));
+        while samples.len() > SIGNALING_MAX_SAMPLES {
+            samples.pop_front();
+        }
+        Ok(())
+    }
+
+    /// Takes the buffered samples received within the max age before `now_ms`.
+    pub fn take_recent(&self, now_ms: u64) -> Vec<Sample> {
+        match self.samples.lock() {
+            Ok(mut samples) => samples
+                .drain(..)
+                .filter(|(at, _)| at + SIGNALING_SAMPLE_MAX_AGE_MS >= now_ms)
+                .map(|(_, sample)| sample)
+                .collect(),
+            Err(e) => {
+                log::error!("Failed to lock signaling samples: {}", e);
+                Vec::new()
+            }
+        }
+    }
+}
+
+impl TimeSource for SignalingSource {
+    fn name(&self) -> &str {
+        SIGNALING_SOURCE_NAME
+    }
+
+    fn collect(&self) -> Vec<Sample> {
+        let now_ms = std::time::SystemTime::now()
+            .duration_since(std::time::UNIX_EPOCH)
+            .unwrap_or_default()
+            .as_millis() as u64;
+        self.take_recent(now_ms)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::ntp::select::{best_per_server, select};
+
+    fn exchange(client_send: u64, server_receive: u64, server_send: u64, client_receive: u64) -> TimeSyncExchange {
+        TimeSyncExchange { client_send, server_receive, server_send, client_receive }
+    }
+
+    #[test]
+    fn test_four_timestamp_exchange() {
+        // Server 500 ms ahead, 40 ms each way, 2 ms processing
+        let sample = exchange(10_000, 10_540, 10_542, 10_082).to_sample().unwrap();
+        assert_eq!(sample.offset_ms, 500.0);
+        assert_eq!(sample.delay_ms, 80.0);
+        assert_eq!(sample.source, ClockSource::Signaling);
+        assert!(exchange(10_000, 10_540, 10_542, 9_000).to_sample().is_err());
+
+        let json = r#"{"clientSend":1,"serverReceive":2,"serverSend":3,"clientReceive":4}"#;
+        assert_eq!(serde_json::from_str::<TimeSyncExchange>(json).unwrap(), exchange(1, 2, 3, 4));
+    }
+
+    #[test]
+    fn test_signaling_combines_with_ntp() {
+        let source = SignalingSource::default();
+        source.push(&exchange(0, 600, 600, 200)).unwrap(); // stale by the time of the sync
+        source.push(&exchange(2_000_000, 2_000_530, 2_000_531, 2_000_061)).unwrap();
+        source.push(&exchange(2_000_100, 2_000_620, 2_000_621, 2_000_141)).unwrap();
+        let signaling = source.take_recent(2_000_200);
+        assert_eq!(signaling.len(), 2);
+        assert!(source.take_recent(2_000_200).is_empty());
+
+        // Offline from NTP: the room server alone keeps the squad on one clock
+        let alone = select(&best_per_server(&signaling)).unwrap();
+        assert_eq!(alone.source, ClockSource::Signaling);
+        assert_eq!(alone.offset_ms, 500.0);
+
+        let ntp = |server: &str, offset_ms| Sample { server: server.into(), source: ClockSource::Ntp, offset_ms, delay_ms: 20.0 };
+        let mut samples = signaling;
+        samples.extend([ntp("a", 505.0), ntp("b", 498.0)]);
+        let combined = select(&best_per_server(&samples)).unwrap();
+        assert_eq!(combined.source, ClockSource::Ntp);
+        assert_eq!(combined.servers, vec![SIGNALING_SOURCE_NAME, "a", "b"]);
+        assert_eq!(combined.offset_ms, 500.0);
+    }
+}
@@ -0,0 +1,126 @@
+//! Plugin API
+//!
+//! Opt-in loopback endpoint that lets external game detectors (any language) trigger clips
+//! and drop markers, using the `ClipTrigger` / `PluginEvent` shapes from the shared package.
+//!
+//! # Architecture
+//!
+//! * `protocol`: Message types and validation.
+//! * `rate_limit`: Sliding-window limits per plugin, shared with `crate::gsi`.
+//! * `server`: The axum server on `127.0.0.1`, token auth, HTTP and WebSocket routes.
+//!
+//! `AppPluginHandler` maps accepted messages onto the app: a trigger marks the moment and saves
+//! a replay of the requested duration, an event becomes a marker. Both are also forwarded to
+//! the UI (`plugin-clip`, `plugin-event`). `restart` applies the `[plugin_api]` config.
+
+pub mod protocol;
+pub mod rate_limit;
+pub mod server;
+
+use std::sync::Arc;
+use async_trait::async_trait;
+use rand::RngCore;
+use tauri::{AppHandle, Emitter, Manager};
+
+use crate::config::PluginApiConfig;
+use crate::state::RecordingState;
+use protocol::{ClipTrigger, PluginEvent};
+use rate_limit::RateLimits;
+use server::PluginHandler;
+
+pub const PLUGIN_CLIP_EVENT: &str = "plugin-clip";
+pub const PLUGIN_EVENT_EVENT: &str = "plugin-event";
+
+/// A random 256-bit token, hex encoded.
+pub fn generate_token() -> String {
+    let mut bytes = [0u8; 32];
+    rand::thread_rng().fill_bytes(&mut bytes);
+    hex::encode(bytes)
+}
+
+/// Gives an enabled API a token if it has none. Returns whether one was generated.
+pub fn ensure_token(config: &mut PluginApiConfig) -> bool {
+    if config.enabled && config.token.is_empty() {
+        config.token = generate_token();
+        return true;
+    }
+    false
+}
+
+/// Stops the running server (if any) and starts one for `config` when enabled.
+pub async fn restart(app: &AppHandle, config: &PluginApiConfig) -> Result<(), String> {
+    let state = app.state::<RecordingState>();
+    // The game state receiver shares these limits, so apply them even with the API off
+    state.plugin_limiter.configure(RateLimits { triggers_per_minute: config.triggers_per_minute, events_per_minute: config.events_per_minute });
+    let previous = state.plugin_server.lock().map_err(|e| e.to_string())?.take();
+    if let Some(server) = previous {
+        server.stop().await;
+    }
+    if !config.enabled {
+        return Ok(());
+    }
+
+    let handler = Arc::new(AppPluginHandler::new(app.clone()));
+    let server = server::start(config.port, config.token.clone(), state.plugin_limiter.clone(), handler).await?;
+    *state.plugin_server.lock().map_err(|e| e.to_string())? = Some(server);
+    Ok(())
+}
+
+/// Turns plugin messages into markers and replay saves. Also used by `crate::gsi`.
+pub(crate) struct AppPluginHandler {
+    app: AppHandle,
+}
+
+impl AppPluginHandler {
+    pub(crate) fn new(app: AppHandle) -> Self {
+        Self { app }
+    }
+
+    /// Plugin timestamps are local Unix ms (0 = now); markers and saves use NTP time. Saturates
+    /// instead of overflowing: out-of-range times are refused when the marker is added.
+    fn ntp_time(&self, local_ms: u64) -> u64 {
+        let ntp = &self.app.state::<RecordingState>().ntp_manager;
+        if local_ms == 0 {
+            return ntp.get_ntp_time_ms();
+        }
+        local_ms.saturating_add_signed(ntp.get_offset())
+    }
+}
+
+#[async_trait]
+impl PluginHandler for AppPluginHandler {
+    async fn clip(&self, trigger: ClipTrigger) -> Result<(), String> {
+        let state = self.app.state::<RecordingState>();
+        if state.tx.lock().map_err(|e| e.to_string())?.is_none() {
+            return Err("Replay buffer is not running".to_string());
+        }
+        let max_secs = state.config.lock().map_err(|e| e.to_string())?.recording.buffer_retention_seconds;
+        let duration_secs = trigger.duration_secs(max_secs);
+        let timestamp = self.ntp_time(trigger.timestamp);
+        let label = if trigger.label.trim().is_empty() { trigger.plugin_id.clone() } else { trigger.label.clone() };
+        log::info!("Plugin '{}' triggered a {}s clip: {}", trigger.plugin_id, duration_secs, label);
+
+        crate::commands::markers::add_marker_impl(&self.app, Some(label.clone()), Some(timestamp))?;
+
+        // Saving waits for segments to close; answer the plugin right away
+        let app = self.app.clone();
+        tauri::async_runtime::spawn(async move {
+            match crate::commands::replay::save_replay_impl(&app, Some(timestamp), Some(duration_secs)).await {
+                Ok(replay) => {
+                    let payload = serde_json::json!({ "pluginId": trigger.plugin_id, "label": label, "replay": replay });
+                    if let Err(e) = app.emit(PLUGIN_CLIP_EVENT, payload) {
+                        log::error!("Failed to emit {}: {}", PLUGIN_CLIP_EVENT, e);
+                    }
+                }
+                Err(e) => log::error!("Plugin '{}' clip failed: {}", trigger.plugin_id, e),
+            }
+        });
+        Ok(())
+    }
+
+    async fn event(&self, event: PluginEvent) -> Result<(), String> {
+        let timestamp = self.ntp_time(event.timestamp);
+        crate::commands::markers::add_marker_impl(&self.app, Some(event.marker_label()), Some(timestamp))?;
+        self.app.emit(PLUGIN_EVENT_EVENT, &event).map_err(|e| e.to_string())
+    }
+}
@@ -0,0 +1,119 @@
+//! Plugin API messages, mirroring `packages/shared/src/types/plugin.ts`.
+
+use serde::{Deserialize, Serialize};
+use crate::constants::{PLUGIN_MAX_ID_LEN, PLUGIN_MAX_LABEL_LEN};
+
+/// Asks for a replay to be saved. `duration` is in seconds, `timestamp` in Unix ms (0 = now).
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ClipTrigger {
+    pub plugin_id: String,
+    pub timestamp: u64,
+    pub duration: f64,
+    pub label: String,
+}
+
+/// A game event (kill, death, round end, ...). Becomes a marker in the buffer.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct PluginEvent {
+    pub plugin_id: String,
+    pub event_id: String,
+    pub timestamp: u64,
+    pub game: String,
+    #[serde(rename = "type")]
+    pub event_type: String,
+    #[serde(default)]
+    pub metadata: serde_json::Map<String, serde_json::Value>,
+}
+
+/// A WebSocket frame carries either message; they are told apart by their fields.
+#[derive(Debug, Clone, PartialEq, Deserialize)]
+#[serde(untagged)]
+pub enum PluginMessage {
+    Clip(ClipTrigger),
+    Event(PluginEvent),
+}
+
+impl ClipTrigger {
+    pub fn validate(&self) -> Result<(), String> {
+        check_text("pluginId", &self.plugin_id, PLUGIN_MAX_ID_LEN)?;
+        if self.label.chars().count() > PLUGIN_MAX_LABEL_LEN {
+            return Err(format!("label is longer than {} characters", PLUGIN_MAX_LABEL_LEN));
+        }
+        if !self.duration.is_finite() || self.duration <= 0.0 {
+            return Err("duration must be a positive number of seconds".to_string());
+        }
+        Ok(())
+    }
+
+    /// Requested duration in whole seconds, capped at what the buffer can hold.
+    pub fn duration_secs(&self, max_secs: u32) -> u32 {
+        (self.duration.ceil() as u32).clamp(1, max_secs.max(1))
+    }
+}
+
+impl PluginEvent {
+    pub fn validate(&self) -> Result<(), String> {
+        check_text("pluginId", &self.plugin_id, PLUGIN_MAX_ID_LEN)?;
+        check_text("game", &self.game, PLUGIN_MAX_ID_LEN)?;
+        check_text("type", &self.event_type, PLUGIN_MAX_LABEL_LEN)?;
+        Ok(())
+    }
+
+    /// Marker label: `metadata.label` when the plugin provides one, otherwise the event type.
+    pub fn marker_label(&self) -> String {
+        self.metadata
+            .get("label")
+            .and_then(|l| l.as_str())
+            .map(str::trim)
+            .filter(|l| !l.is_empty())
+            .map(|l| l.chars().take(PLUGIN_MAX_LABEL_LEN).collect())
+            .unwrap_or_else(|| self.event_type.clone())
+    }
+}
+
+fn check_text(field: &str, value: &str, max_len: usize) -> Result<(), String> {
+    if value.trim().is_empty() {
+        return Err(format!("{} is required", field));
+    }
+    if value.chars().count() > max_len {
+        return Err(format!("{} is longer than {} characters", field, max_len));
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_messages() {
+        let clip: PluginMessage = serde_json::from_str(r#"{"pluginId":"cs2-gsi","timestamp":0,"duration":29.5,"label":"Ace"}"#).unwrap();
+        let PluginMessage::Clip(clip) = clip else { panic!("expected a clip trigger") };
+        assert!(clip.validate().is_ok());
+        assert_eq!(clip.duration_secs(120), 30);
+        assert_eq!(clip.duration_secs(20), 20);
+
+        let event: PluginMessage = serde_json::from_str(
+            r#"{"pluginId":"cs2-gsi","eventId":"e1","timestamp":1700000000000,"game":"cs2","type":"kill","metadata":{"label":"Headshot"}}"#,
+        ).unwrap();
+        let PluginMessage::Event(event) = event else { panic!("expected an event") };
+        assert!(event.validate().is_ok());
+        assert_eq!(event.marker_label(), "Headshot");
+    }
+
+    #[test]
+    fn test_validation() {
+        let clip = ClipTrigger { plugin_id: " ".into(), timestamp: 0, duration: 30.0, label: String::new() };
+        assert!(clip.validate().unwrap_err().contains("pluginId"));
+        let clip = ClipTrigger { plugin_id: "p".into(), duration: f64::NAN, ..clip };
+        assert!(clip.validate().unwrap_err().contains("duration"));
+
+        let event = PluginEvent { plugin_id: "p".into(), event_id: "e".into(), timestamp: 0, game: "cs2".into(), event_type: String::new(), metadata: Default::default() };
+        assert!(event.validate().unwrap_err().contains("type"));
+        let event = PluginEvent { plugin_id: String::new(), event_type: "kill".into(), ..event };
+        assert!(event.validate().unwrap_err().contains("pluginId"));
+        assert!(serde_json::from_str::<PluginMessage>(r#"{"hello":1}"#).is_err());
+    }
+}
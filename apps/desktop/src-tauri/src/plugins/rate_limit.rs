@@ -0,0 +1,115 @@
+//! Per-plugin rate limits: a sliding window of accepted requests per key.
+//!
+//! One `PluginLimiter` is shared by the plugin API and the game state receiver, so clips and
+//! markers from every source are limited the same way.
+
+use std::collections::{HashMap, VecDeque};
+use std::sync::Mutex;
+
+use crate::constants::PLUGIN_RATE_WINDOW_MS;
+
+#[derive(Debug, Clone, Copy)]
+pub struct RateLimits {
+    pub triggers_per_minute: u32,
+    pub events_per_minute: u32,
+}
+
+/// Clip trigger and event budgets per key (`plugin:<pluginId>`, `gsi:<game>`).
+pub struct PluginLimiter {
+    triggers: Mutex<RateWindow>,
+    events: Mutex<RateWindow>,
+}
+
+impl PluginLimiter {
+    pub fn new(limits: RateLimits) -> Self {
+        Self {
+            triggers: Mutex::new(RateWindow::new(limits.triggers_per_minute, PLUGIN_RATE_WINDOW_MS)),
+            events: Mutex::new(RateWindow::new(limits.events_per_minute, PLUGIN_RATE_WINDOW_MS)),
+        }
+    }
+
+    /// Applies new limits. Requests already in the window still count.
+    pub fn configure(&self, limits: RateLimits) {
+        self.triggers.lock().unwrap_or_else(|e| e.into_inner()).limit = limits.triggers_per_minute;
+        self.events.lock().unwrap_or_else(|e| e.into_inner()).limit = limits.events_per_minute;
+    }
+
+    /// See [RateWindow::check].
+    pub fn check_trigger(&self, key: &str, now_ms: u64) -> Result<(), u64> {
+        self.triggers.lock().unwrap_or_else(|e| e.into_inner()).check(key, now_ms)
+    }
+
+    /// See [RateWindow::check].
+    pub fn check_event(&self, key: &str, now_ms: u64) -> Result<(), u64> {
+        self.events.lock().unwrap_or_else(|e| e.into_inner()).check(key, now_ms)
+    }
+}
+
+pub struct RateWindow {
+    limit: u32,
+    window_ms: u64,
+    hits: HashMap<String, VecDeque<u64>>,
+}
+
+impl RateWindow {
+    pub fn new(limit: u32, window_ms: u64) -> Self {
+        Self { limit, window_ms, hits: HashMap::new() }
+    }
+
+    /// Records a request for `key` at `now_ms`. When over the limit, returns how long
+    /// (ms) until the oldest request leaves the window; the request is not counted.
+    pub fn check(&mut self, key: &str, now_ms: u64) -> Result<(), u64> {
+        let window_ms = self.window_ms;
+        let expired = move |t: u64| t + window_ms <= now_ms;
+        let hits = self.hits.entry(key.to_string()).or_default();
+        while hits.front().is_some_and(|&t| expired(t)) {
+            hits.pop_front();
+        }
+        if hits.len() >= self.limit as usize {
+            let oldest = hits.front().copied().unwrap_or(now_ms);
+            return Err((oldest + window_ms).saturating_sub(now_ms).max(1));
+        }
+        hits.push_back(now_ms);
+
+        // Forget keys that went quiet so one-off plugin IDs don't accumulate
+        self.hits.retain(|_, h| h.back().is_some_and(|&t| !expired(t)));
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_limit_per_key() {
+        let mut window = RateWindow::new(2, 1_000);
+        assert!(window.check("a", 0).is_ok());
+        assert!(window.check("a", 100).is_ok());
+        assert_eq!(window.check("a", 400), Err(600));
+        // Other plugins have their own budget
+        assert!(window.check("b", 400).is_ok());
+        // The first request leaves the window
+        assert!(window.check("a", 1_000).is_ok());
+        assert_eq!(window.check("a", 1_050), Err(50));
+    }
+
+    #[test]
+    fn test_limiter_keeps_budgets_apart_and_reconfigures() {
+        let limiter = PluginLimiter::new(RateLimits { triggers_per_minute: 1, events_per_minute: 1 });
+        assert!(limiter.check_trigger("gsi:cs2", 0).is_ok());
+        assert!(limiter.check_trigger("gsi:cs2", 10).is_err());
+        assert!(limiter.check_event("gsi:cs2", 10).is_ok());
+        assert!(limiter.check_trigger("plugin:cs2", 10).is_ok());
+
+        limiter.configure(RateLimits { triggers_per_minute: 2, events_per_minute: 1 });
+        assert!(limiter.check_trigger("gsi:cs2", 20).is_ok());
+        assert!(limiter.check_trigger("gsi:cs2", 30).is_err());
+    }
+
+    #[test]
+    fn test_zero_limit_rejects_everything() {
+        let mut window = RateWindow::new(0, 1_000);
+        assert!(window.check("a", 5).is_err());
+    }
+}
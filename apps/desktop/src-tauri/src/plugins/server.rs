@@ -0,0 +1,348 @@
+//! Loopback HTTP/WebSocket server for plugins.
+//!
+//! Routes (all but `health` need `Authorization: Bearer <token>`; WebSocket clients that
+//! can't set headers may pass `?token=` instead):
+//!
+//! * `GET  /v1/health`: `{ "ok": true, "version": 1 }`, to find a running app.
+//! * `POST /v1/clip`: a `ClipTrigger`.
+//! * `POST /v1/event`: a `PluginEvent`.
+//! * `GET  /v1/ws`: WebSocket taking either message as a text frame, answered with one
+//!   `{ "ok": ... }` frame each.
+//!
+//! Clip triggers and events are rate limited per `pluginId` by the shared `PluginLimiter`.
+
+use std::net::SocketAddr;
+use std::sync::Arc;
+
+use async_trait::async_trait;
+use axum::body::Bytes;
+use axum::extract::ws::{Message, WebSocket, WebSocketUpgrade};
+use axum::extract::{DefaultBodyLimit, Query, State};
+use axum::http::{header, HeaderMap, HeaderValue, StatusCode};
+use axum::response::{IntoResponse, Response};
+use axum::routing::{get, post};
+use axum::{Json, Router};
+use serde::{Deserialize, Serialize};
+use tokio::sync::oneshot;
+
+use super::protocol::{ClipTrigger, PluginEvent, PluginMessage};
+use super::rate_limit::PluginLimiter;
+use crate::constants::PLUGIN_API_MAX_BODY_BYTES;
+
+pub const PLUGIN_API_VERSION: u32 = 1;
+const SERVER_STOP_TIMEOUT: std::time::Duration = std::time::Duration::from_secs(2);
+
+/// What accepted messages turn into. Implemented over the app in `plugins::AppPluginHandler`.
+#[async_trait]
+pub trait PluginHandler: Send + Sync {
+    async fn clip(&self, trigger: ClipTrigger) -> Result<(), String>;
+    async fn event(&self, event: PluginEvent) -> Result<(), String>;
+}
+
+struct ServerState {
+    token: String,
+    handler: Arc<dyn PluginHandler>,
+    limiter: Arc<PluginLimiter>,
+}
+
+/// A server on `127.0.0.1`. Stops when dropped.
+pub struct LocalServer {
+    name: &'static str,
+    addr: SocketAddr,
+    shutdown: Option<oneshot::Sender<()>>,
+    task: Option<tokio::task::JoinHandle<()>>,
+}
+
+impl LocalServer {
+    /// Binds `127.0.0.1:port` (0 = any free port) and serves `router` on the current runtime.
+    pub async fn bind(name: &'static str, port: u16, router: Router) -> Result<Self, String> {
+        let listener = tokio::net::TcpListener::bind(SocketAddr::from(([127, 0, 0, 1], port)))
+            .await
+            .map_err(|e| format!("Failed to bind {} on port {}: {}", name, port, e))?;
+        let addr = listener.local_addr().map_err(|e| e.to_string())?;
+
+        let (shutdown, stopped) = oneshot::channel::<()>();
+        let task = tokio::spawn(async move {
+            let server = axum::serve(listener, router).with_graceful_shutdown(async {
+                let _ = stopped.await;
+            });
+            if let Err(e) = server.await {
+                log::error!("{} server failed: {}", name, e);
+            }
+        });
+        log::info!("{} listening on http://{}", name, addr);
+        Ok(Self { name, addr, shutdown: Some(shutdown), task: Some(task) })
+    }
+
+    pub fn addr(&self) -> SocketAddr {
+        self.addr
+    }
+
+    /// Stops the server and waits (briefly) until the port is released, so it can be rebound.
+    pub async fn stop(mut self) {
+        if let Some(shutdown) = self.shutdown.take() {
+            let _ = shutdown.send(());
+        }
+        if let Some(mut task) = self.task.take() {
+            // Open keep-alive connections can hold up a graceful shutdown
+            if tokio::time::timeout(SERVER_STOP_TIMEOUT, &mut task).await.is_err() {
+                task.abort();
+                let _ = task.await;
+            }
+        }
+        log::info!("{} on {} stopped", self.name, self.addr);
+    }
+}
+
+impl Drop for LocalServer {
+    fn drop(&mut self) {
+        if let Some(shutdown) = self.shutdown.take() {
+            let _ = shutdown.send(());
+            log::info!("{} on {} stopped", self.name, self.addr);
+        }
+    }
+}
+
+/// Starts the plugin API.
+pub async fn start(port: u16, token: String, limiter: Arc<PluginLimiter>, handler: Arc<dyn PluginHandler>) -> Result<LocalServer, String> {
+    if token.is_empty() {
+        return Err("Plugin API token is empty".to_string());
+    }
+    let state = Arc::new(ServerState {
+        token,
+        handler,
+        limiter,
+    });
+    let router = Router::new()
+        .route("/v1/health", get(health))
+        .route("/v1/clip", post(post_clip))
+        .route("/v1/event", post(post_event))
+        .route("/v1/ws", get(websocket))
+        .layer(DefaultBodyLimit::max(PLUGIN_API_MAX_BODY_BYTES))
+        .with_state(state);
+    LocalServer::bind("Plugin API", port, router).await
+}
+
+#[derive(Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+struct Reply {
+    ok: bool,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    error: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    retry_after_ms: Option<u64>,
+}
+
+struct ApiError {
+    status: StatusCode,
+    message: String,
+    retry_after_ms: Option<u64>,
+}
+
+impl ApiError {
+    fn new(status: StatusCode, message: impl Into<String>) -> Self {
+        Self { status, message: message.into(), retry_after_ms: None }
+    }
+
+    fn reply(&self) -> Reply {
+        Reply { ok: false, error: Some(self.message.clone()), retry_after_ms: self.retry_after_ms }
+    }
+}
+
+impl IntoResponse for ApiError {
+    fn into_response(self) -> Response {
+        let mut response = (self.status, Json(self.reply())).into_response();
+        if let Some(ms) = self.retry_after_ms {
+            if let Ok(value) = HeaderValue::from_str(&ms.div_ceil(1000).to_string()) {
+                response.headers_mut().insert(header::RETRY_AFTER, value);
+            }
+        }
+        response
+    }
+}
+
+fn accepted() -> Response {
+    (StatusCode::ACCEPTED, Json(Reply { ok: true, error: None, retry_after_ms: None })).into_response()
+}
+
+/// Compares without an early exit, so response timing doesn't leak the token.
+pub(crate) fn token_matches(expected: &str, given: &str) -> bool {
+    expected.len() == given.len() && expected.bytes().zip(given.bytes()).fold(0u8, |acc, (a, b)| acc | (a ^ b)) == 0
+}
+
+fn authorize(state: &ServerState, headers: &HeaderMap, query_token: Option<&str>) -> Result<(), ApiError> {
+    let bearer = headers
+        .get(header::AUTHORIZATION)
+        .and_then(|v| v.to_str().ok())
+        .and_then(|v| v.strip_prefix("Bearer "))
+        .map(str::trim);
+    match bearer.or(query_token) {
+        Some(token) if token_matches(&state.token, token) => Ok(()),
+        Some(_) => Err(ApiError::new(StatusCode::UNAUTHORIZED, "Invalid token")),
+        None => Err(ApiError::new(StatusCode::UNAUTHORIZED, "Missing token")),
+    }
+}
+
+fn parse<T: serde::de::DeserializeOwned>(body: &[u8]) -> Result<T, ApiError> {
+    serde_json::from_slice(body).map_err(|e| ApiError::new(StatusCode::BAD_REQUEST, format!("Invalid message: {}", e)))
+}
+
+fn now_ms() -> u64 {
+    chrono::Utc::now().timestamp_millis().max(0) as u64
+}
+
+async fn dispatch(state: &ServerState, message: PluginMessage) -> Result<(), ApiError> {
+    let limited = match &message {
+        PluginMessage::Clip(trigger) => {
+            trigger.validate().map_err(|e| ApiError::new(StatusCode::BAD_REQUEST, e))?;
+            state.limiter.check_trigger(&format!("plugin:{}", trigger.plugin_id), now_ms()).map_err(|ms| (&trigger.plugin_id, ms))
+        }
+        PluginMessage::Event(event) => {
+            event.validate().map_err(|e| ApiError::new(StatusCode::BAD_REQUEST, e))?;
+            state.limiter.check_event(&format!("plugin:{}", event.plugin_id), now_ms()).map_err(|ms| (&event.plugin_id, ms))
+        }
+    };
+    if let Err((plugin_id, retry_after_ms)) = limited {
+        log::warn!("Plugin API: rate limit hit for '{}'", plugin_id);
+        return Err(ApiError { status: StatusCode::TOO_MANY_REQUESTS, message: format!("Rate limit exceeded for '{}'", plugin_id), retry_after_ms: Some(retry_after_ms) });
+    }
+
+    let result = match message {
+        PluginMessage::Clip(trigger) => state.handler.clip(trigger).await,
+        PluginMessage::Event(event) => state.handler.event(event).await,
+    };
+    result.map_err(|e| ApiError::new(StatusCode::SERVICE_UNAVAILABLE, e))
+}
+
+async fn health() -> Response {
+    Json(serde_json::json!({ "ok": true, "version": PLUGIN_API_VERSION })).into_response()
+}
+
+async fn post_clip(State(state): State<Arc<ServerState>>, headers: HeaderMap, body: Bytes) -> Result<Response, ApiError> {
+    authorize(&state, &headers, None)?;
+    dispatch(&state, PluginMessage::Clip(parse(&body)?)).await?;
+    Ok(accepted())
+}
+
+async fn post_event(State(state): State<Arc<ServerState>>, headers: HeaderMap, body: Bytes) -> Result<Response, ApiError> {
+    authorize(&state, &headers, None)?;
+    dispatch(&state, PluginMessage::Event(parse(&body)?)).await?;
+    Ok(accepted())
+}
+
+#[derive(Deserialize)]
+struct WsQuery {
+    token: Option<String>,
+}
+
+async fn websocket(State(state): State<Arc<ServerState>>, headers: HeaderMap, Query(query): Query<WsQuery>, upgrade: WebSocketUpgrade) -> Result<Response, ApiError> {
+    authorize(&state, &headers, query.token.as_deref())?;
+    Ok(upgrade.on_upgrade(move |socket| serve_socket(state, socket)))
+}
+
+async fn serve_socket(state: Arc<ServerState>, mut socket: WebSocket) {
+    while let Some(Ok(message)) = socket.recv().await {
+        let text = match message {
+            Message::Text(text) => text,
+            Message::Close(_) => break,
+            // Pings are answered by axum; binary frames aren't part of the protocol
+            _ => continue,
+        };
+        let reply = match parse::<PluginMessage>(text.as_bytes()) {
+            Ok(message) => match dispatch(&state, message).await {
+                Ok(()) => Reply { ok: true, error: None, retry_after_ms: None },
+                Err(e) => e.reply(),
+            },
+            Err(e) => e.reply(),
+        };
+        let Ok(json) = serde_json::to_string(&reply) else { continue };
+        if socket.send(Message::Text(json)).await.is_err() {
+            break;
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::plugins::rate_limit::RateLimits;
+    use std::sync::Mutex;
+
+    #[derive(Default)]
+    struct Recorder {
+        clips: Mutex<Vec<ClipTrigger>>,
+        events: Mutex<Vec<PluginEvent>>,
+    }
+
+    #[async_trait]
+    impl PluginHandler for Recorder {
+        async fn clip(&self, trigger: ClipTrigger) -> Result<(), String> {
+            self.clips.lock().unwrap().push(trigger);
+            Ok(())
+        }
+
+        async fn event(&self, event: PluginEvent) -> Result<(), String> {
+            self.events.lock().unwrap().push(event);
+            Ok(())
+        }
+    }
+
+    #[test]
+    fn test_auth_dispatch_and_rate_limit() {
+        let rt = tokio::runtime::Builder::new_current_thread().enable_all().build().unwrap();
+        rt.block_on(async {
+            let recorder = Arc::new(Recorder::default());
+            let limits = RateLimits { triggers_per_minute: 2, events_per_minute: 1 };
+            let server = start(0, "secret".into(), Arc::new(PluginLimiter::new(limits)), recorder.clone()).await.unwrap();
+            let base = format!("http://{}", server.addr());
+            let client = reqwest::Client::new();
+            let clip = serde_json::json!({ "pluginId": "cs2", "timestamp": 0, "duration": 30, "label": "Ace" });
+
+            let health = client.get(format!("{}/v1/health", base)).send().await.unwrap();
+            assert_eq!(health.status().as_u16(), 200);
+
+            let status = |r: reqwest::Response| r.status().as_u16();
+            let no_token = client.post(format!("{}/v1/clip", base)).body(clip.to_string()).send().await.unwrap();
+            assert_eq!(status(no_token), 401);
+            let wrong = client.post(format!("{}/v1/clip", base)).bearer_auth("secreT").body(clip.to_string()).send().await.unwrap();
+            assert_eq!(status(wrong), 401);
+
+            for _ in 0..2 {
+                let ok = client.post(format!("{}/v1/clip", base)).bearer_auth("secret").body(clip.to_string()).send().await.unwrap();
+                assert_eq!(status(ok), 202);
+            }
+            let limited = client.post(format!("{}/v1/clip", base)).bearer_auth("secret").body(clip.to_string()).send().await.unwrap();
+            assert_eq!(limited.status().as_u16(), 429);
+            assert!(limited.headers().contains_key("retry-after"));
+
+            let post_event = |event: serde_json::Value| client.post(format!("{}/v1/event", base)).bearer_auth("secret").body(event.to_string()).send();
+            let event = serde_json::json!({ "pluginId": "cs2", "eventId": "1", "timestamp": 5, "game": "cs2", "type": "kill", "metadata": {} });
+            assert_eq!(status(post_event(event.clone()).await.unwrap()), 202);
+            // The budget is the plugin's, whatever game it reports
+            let other_game = serde_json::json!({ "pluginId": "cs2", "eventId": "2", "timestamp": 6, "game": "valorant", "type": "kill", "metadata": {} });
+            assert_eq!(status(post_event(other_game).await.unwrap()), 429);
+            let other_plugin = serde_json::json!({ "pluginId": "overlay", "eventId": "3", "timestamp": 7, "game": "cs2", "type": "death", "metadata": {} });
+            assert_eq!(status(post_event(other_plugin).await.unwrap()), 202);
+            let bad = client.post(format!("{}/v1/event", base)).bearer_auth("secret").body("{").send().await.unwrap();
+            assert_eq!(status(bad), 400);
+
+            assert_eq!(recorder.clips.lock().unwrap().len(), 2);
+            let events = recorder.events.lock().unwrap().iter().map(|e| e.event_type.clone()).collect::<Vec<_>>();
+            assert_eq!(events, vec!["kill", "death"]);
+
+            // Stopping releases the port
+            let addr = server.addr();
+            server.stop().await;
+            assert!(tokio::net::TcpStream::connect(addr).await.is_err());
+            let again = start(addr.port(), "secret".into(), Arc::new(PluginLimiter::new(limits)), recorder.clone()).await;
+            assert!(again.is_ok());
+        });
+    }
+
+    #[test]
+    fn test_token_matches() {
+        assert!(token_matches("abc", "abc"));
+        assert!(!token_matches("abc", "abd"));
+        assert!(!token_matches("abc", "abcd"));
+    }
+}
@@ -1,13 +1,22 @@
 use std::sync::Mutex;
+use std::sync::atomic::{AtomicBool, AtomicUsize, Ordering};
 use std::sync::mpsc::Sender;
 use crate::config::AppConfig;
+use crate::ffmpeg::session::AudioSettings;
 
 pub enum RecordingMessage {
     AudioData(Vec<u8>),
+    /// Restart the audio captures and process with new settings, video keeps recording
+    RestartAudio(AudioSettings),
     Stop,
 }
 
 use crate::ntp::NtpManager;
+use crate::upload::throttle::RateLimiter;
+use crate::hotkeys::HotkeyRegistry;
+use crate::markers::MarkerLog;
+use crate::plugins::rate_limit::{PluginLimiter, RateLimits};
+use crate::plugins::server::LocalServer;
 use std::sync::Arc;
 
 pub struct RecordingState {
@@ -16,6 +25,21 @@ pub struct RecordingState {
     pub config: Mutex<AppConfig>,
     pub last_clip_timestamp: Mutex<Option<std::time::Instant>>,
     pub ntp_manager: Arc<NtpManager>,
+    /// Bandwidth limiter shared by all uploads, with the kbps it was built for.
+    pub upload_limiter: Mutex<Option<(u32, RateLimiter)>>,
+    pub hotkeys: Mutex<HotkeyRegistry>,
+    pub markers: Mutex<MarkerLog>,
+    pub plugin_server: Mutex<Option<LocalServer>>,
+    pub gsi_server: Mutex<Option<LocalServer>>,
+    /// Clip and marker budgets of plugins and the game state receiver, set from `[plugin_api]`
+    pub plugin_limiter: Arc<PluginLimiter>,
+    /// Game detected by `crate::games`, tagged into saved clips
+    pub active_game: Mutex<Option<String>>,
+    /// Set while encoders are benchmarked, the replay buffer doesn't start until it's done
+    pub benchmarking: AtomicBool,
+    /// Starts of the replay buffer in progress, while `tx` is still None. Like `benchmarking`,
+    /// only raised with `tx` locked, so a start and a benchmark can't both pass their checks.
+    pub replay_starts: AtomicUsize,
 }
 
 impl Default for RecordingState {
@@ -33,6 +57,34 @@ impl RecordingState {
             config: Mutex::new(AppConfig::default()),
             last_clip_timestamp: Mutex::new(None),
             ntp_manager: Arc::new(NtpManager::new()),
+            upload_limiter: Mutex::new(None),
+            hotkeys: Mutex::new(HotkeyRegistry::default()),
+            markers: Mutex::new(MarkerLog::default()),
+            plugin_server: Mutex::new(None),
+            gsi_server: Mutex::new(None),
+            plugin_limiter: Arc::new(PluginLimiter::new(RateLimits {
+                triggers_per_minute: crate::constants::DEFAULT_PLUGIN_TRIGGERS_PER_MINUTE,
+                events_per_minute: crate::constants::DEFAULT_PLUGIN_EVENTS_PER_MINUTE,
+            })),
+            active_game: Mutex::new(None),
+            benchmarking: AtomicBool::new(false),
+            replay_starts: AtomicUsize::new(0),
         }
     }
 }
+
+/// Counts a replay buffer start in [RecordingState::replay_starts] until dropped.
+pub struct StartGuard<'a>(&'a AtomicUsize);
+
+impl<'a> StartGuard<'a> {
+    pub fn new(starts: &'a AtomicUsize) -> Self {
+        starts.fetch_add(1, Ordering::SeqCst);
+        Self(starts)
+    }
+}
+
+impl Drop for StartGuard<'_> {
+    fn drop(&mut self) {
+        self.0.fetch_sub(1, Ordering::SeqCst);
+    }
+}
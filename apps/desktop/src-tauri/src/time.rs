@@ -0,0 +1,117 @@
+//! Time Base
+//!
+//! UTC wall-clock time in microseconds that follows the monotonic clock instead of the system
+//! clock, so OS clock adjustments don't make timestamps jump mid-session.
+//!
+//! # Architecture
+//!
+//! * `TimeBase`: Reads `SystemTime` once (at startup) and adds `Instant` elapsed time from then on.
+//! * Re-anchoring: If system and monotonic time drift apart by more than
+//!   `TIME_REANCHOR_THRESHOLD_US` (suspend on platforms whose monotonic clock stops, or a manual
+//!   clock change), the base re-anchors to the system clock and bumps its generation.
+//!   `crate::ntp` treats a new generation as unsynced and syncs again.
+//!
+//! Everything stored or compared is UTC: segment file names carry a `Z` timestamp (see
+//! `crate::ffmpeg::utils::segment_pattern`), never local time.
+
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::{Mutex, OnceLock};
+use std::time::{Instant, SystemTime, UNIX_EPOCH};
+
+use crate::constants::TIME_REANCHOR_THRESHOLD_US;
+
+static TIME_BASE: OnceLock<TimeBase> = OnceLock::new();
+
+/// The process-wide time base, anchored on first use. Called early in `run()`.
+pub fn time_base() -> &'static TimeBase {
+    TIME_BASE.get_or_init(TimeBase::new)
+}
+
+/// Current system clock as Unix microseconds (may jump).
+pub fn system_now_us() -> i64 {
+    match SystemTime::now().duration_since(UNIX_EPOCH) {
+        Ok(d) => d.as_micros() as i64,
+        Err(e) => -(e.duration().as_micros() as i64),
+    }
+}
+
+#[derive(Debug, Clone, Copy)]
+struct Anchor {
+    instant: Instant,
+    unix_us: i64,
+}
+
+pub struct TimeBase {
+    anchor: Mutex<Anchor>,
+    generation: AtomicU64,
+}
+
+impl Default for TimeBase {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl TimeBase {
+    pub fn new() -> Self {
+        Self::with_anchor(Instant::now(), system_now_us())
+    }
+
+    pub fn with_anchor(instant: Instant, unix_us: i64) -> Self {
+        Self { anchor: Mutex::new(Anchor { instant, unix_us }), generation: AtomicU64::new(0) }
+    }
+
+    /// Current UTC time in Unix microseconds.
+    pub fn now_us(&self) -> i64 {
+        self.at(Instant::now(), system_now_us())
+    }
+
+    pub fn now_ms(&self) -> u64 {
+        self.now_us().max(0) as u64 / 1000
+    }
+
+    /// Bumped on every re-anchor. Offsets measured in an older generation no longer apply.
+    pub fn generation(&self) -> u64 {
+        self.generation.load(Ordering::Acquire)
+    }
+
+    /// Time at `instant`, given the system clock read at the same moment.
+    pub fn at(&self, instant: Instant, system_us: i64) -> i64 {
+        let mut anchor = match self.anchor.lock() {
+            Ok(anchor) => anchor,
+            Err(e) => e.into_inner(),
+        };
+        let elapsed_us = instant.saturating_duration_since(anchor.instant).as_micros() as i64;
+        let now_us = anchor.unix_us + elapsed_us;
+        if (system_us - now_us).abs() > TIME_REANCHOR_THRESHOLD_US {
+            log::warn!("System clock is {} ms off the monotonic clock, re-anchoring", (system_us - now_us) / 1000);
+            *anchor = Anchor { instant, unix_us: system_us };
+            self.generation.fetch_add(1, Ordering::AcqRel);
+            return system_us;
+        }
+        now_us
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::time::Duration;
+
+    #[test]
+    fn test_follows_monotonic_clock() {
+        let start = Instant::now();
+        let base = TimeBase::with_anchor(start, 1_700_000_000_000_000);
+
+        // The OS slews the clock by 2 s: ignored
+        let later = start + Duration::from_micros(1_500);
+        assert_eq!(base.at(later, 1_700_000_002_000_000), 1_700_000_000_001_500);
+        assert_eq!(base.generation(), 0);
+
+        // An hour's suspend the monotonic clock didn't see: re-anchored
+        let resumed = start + Duration::from_secs(10);
+        assert_eq!(base.at(resumed, 1_700_003_610_000_000), 1_700_003_610_000_000);
+        assert_eq!(base.generation(), 1);
+        assert_eq!(base.at(resumed + Duration::from_millis(5), 1_700_003_610_005_000), 1_700_003_610_005_000);
+    }
+}
@@ -0,0 +1,284 @@
+//! Squadmate clip downloads.
+//!
+//! Teammates' POVs are fetched from their (short-lived) cloud URLs into a local "squad
+//! moment" bundle: `<recordings>/Squad/<clip id>/` holding one file per author plus a
+//! `moment.json` with the `View` metadata, so the multi-view survives the cloud copy expiring.
+//!
+//! Downloads go to `<file>.part` and continue with a `Range` request after a failure or restart.
+//! When the view carries a SHA-256 the finished file is checked against it.
+
+use serde::{Deserialize, Serialize};
+use std::path::{Path, PathBuf};
+use std::sync::Arc;
+use std::time::Duration;
+
+use futures_util::StreamExt;
+use tokio::io::AsyncWriteExt;
+
+use super::integrity::{self, ClipHashes};
+use super::progress::{ProgressCallback, ProgressTracker};
+use super::{classify_status, AttemptError, UploadManager};
+
+pub const DOWNLOAD_PROGRESS_EVENT: &str = "download-progress";
+pub const MOMENT_FILE: &str = "moment.json";
+
+/// A squadmate's view of a clip, as broadcast in `CLIP_UPDATED`.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct View {
+    pub author: String,
+    pub url: String,
+    pub timestamp: u64,
+    pub video_start_time_ms: u64,
+    pub duration_ms: u64,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub sha256: Option<String>,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct MomentView {
+    #[serde(flatten)]
+    pub view: View,
+    /// File name inside the bundle
+    pub file: String,
+    pub downloaded_at_ms: u64,
+}
+
+/// `moment.json`: everything needed to replay a squad moment offline.
+#[derive(Debug, Clone, PartialEq, Default, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct SquadMoment {
+    pub clip_id: String,
+    /// Our own replay of the moment
+    #[serde(default)]
+    pub own_clip: Option<String>,
+    #[serde(default)]
+    pub views: Vec<MomentView>,
+}
+
+impl SquadMoment {
+    pub fn load(dir: &Path, clip_id: &str) -> Self {
+        std::fs::read_to_string(dir.join(MOMENT_FILE))
+            .ok()
+            .and_then(|s| serde_json::from_str(&s).ok())
+            .unwrap_or_else(|| SquadMoment { clip_id: clip_id.to_string(), ..Default::default() })
+    }
+
+    pub fn save(&self, dir: &Path) -> Result<(), String> {
+        let json = serde_json::to_string_pretty(self).map_err(|e| e.to_string())?;
+        std::fs::write(dir.join(MOMENT_FILE), json).map_err(|e| format!("Failed to save {}: {}", MOMENT_FILE, e))
+    }
+
+    /// Adds or replaces the author's view.
+    pub fn upsert(&mut self, view: MomentView) {
+        self.views.retain(|v| v.view.author != view.view.author);
+        self.views.push(view);
+        self.views.sort_by_key(|v| v.view.video_start_time_ms);
+    }
+}
+
+/// Bundle directory of a clip under the recordings folder.
+pub fn moment_dir(recordings_dir: &Path, clip_id: &str) -> PathBuf {
+    recordings_dir.join("Squad").join(safe_file_name(clip_id))
+}
+
+/// File name for an author's view inside the bundle.
+pub fn view_file_name(author: &str) -> String {
+    format!("{}.mp4", safe_file_name(author))
+}
+
+/// Replaces characters that aren't safe in file names on Windows.
+fn safe_file_name(name: &str) -> String {
+    let cleaned: String = name.trim().chars()
+        .map(|c| if c.is_alphanumeric() || matches!(c, '-' | '_' | '.' | ' ') { c } else { '_' })
+        .collect();
+    let cleaned = cleaned.trim_matches('.').to_string();
+    if cleaned.is_empty() { "unknown".to_string() } else { cleaned }
+}
+
+fn part_path(dest: &Path) -> PathBuf {
+    let mut name = dest.as_os_str().to_owned();
+    name.push(".part");
+    PathBuf::from(name)
+}
+
+impl UploadManager {
+    /// Downloads `url` to `dest`, resuming a previous partial download. Verifies the SHA-256 if given.
+    pub async fn download(&self, url: &str, dest: &Path, expected_sha256: Option<&str>, on_progress: ProgressCallback) -> Result<ClipHashes, String> {
+        if let Some(parent) = dest.parent() {
+            tokio::fs::create_dir_all(parent).await.map_err(|e| format!("Failed to create {}: {}", parent.display(), e))?;
+        }
+        let part = part_path(dest);
+        let tracker = Arc::new(ProgressTracker::new(dest.to_string_lossy().to_string(), 0, 0, on_progress));
+
+        self.with_retry("download", || self.fetch_into(url, &part, &tracker)).await?;
+
+        let hashes = integrity::hash_file(&part).await?;
+        if let Some(expected) = expected_sha256 {
+            if !hashes.sha256.eq_ignore_ascii_case(expected) {
+                let _ = tokio::fs::remove_file(&part).await;
+                return Err(format!("Downloaded clip does not match its SHA-256 ({} != {})", hashes.sha256, expected));
+            }
+        }
+
+        tokio::fs::rename(&part, dest).await.map_err(|e| format!("Failed to move download into place: {}", e))?;
+        if let Err(e) = integrity::record_hashes(dest, &hashes) {
+            log::warn!("{}", e);
+        }
+        tracker.finish();
+        Ok(hashes)
+    }
+
+    /// One attempt: appends to `part` from where it ends.
+    async fn fetch_into(&self, url: &str, part: &Path, tracker: &Arc<ProgressTracker>) -> Result<(), AttemptError> {
+        let have = tokio::fs::metadata(part).await.map(|m| m.len()).unwrap_or(0);
+        let mut request = self.client.get(url).timeout(Duration::from_secs(60 * 30));
+        if have > 0 {
+            request = request.header("Range", format!("bytes={}-", have));
+        }
+        let response = request.send().await
+            .map_err(|e| AttemptError::Retryable(format!("Download request failed: {}", e)))?;
+
+        let status = response.status();
+        let resume = match status.as_u16() {
+            206 => true,
+            200 => false,
+            // The part file already holds everything
+            416 if have > 0 => return Ok(()),
+            _ => {
+                let text = response.text().await.unwrap_or_default();
+                return Err(classify_status(status, &text));
+            }
+        };
+        if have > 0 && !resume {
+            log::info!("Server ignored the range request; restarting download");
+        }
+
+        let offset = if resume { have } else { 0 };
+        let total = response.content_length().map(|len| len + offset).unwrap_or(0);
+        tracker.reset(total, offset);
+
+        let mut file = tokio::fs::OpenOptions::new()
+            .create(true)
+            .write(true)
+            .append(resume)
+            .truncate(!resume)
+            .open(part)
+            .await
+            .map_err(|e| AttemptError::Fatal(format!("Failed to open {}: {}", part.display(), e)))?;
+
+        let mut stream = response.bytes_stream();
+        while let Some(chunk) = stream.next().await {
+            let chunk = chunk.map_err(|e| AttemptError::Retryable(format!("Download interrupted: {}", e)))?;
+            file.write_all(&chunk).await
+                .map_err(|e| AttemptError::Fatal(format!("Failed to write {}: {}", part.display(), e)))?;
+            tracker.add(chunk.len() as u64);
+        }
+        file.flush().await.map_err(|e| AttemptError::Fatal(format!("Failed to write {}: {}", part.display(), e)))?;
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::sync::Mutex;
+    use tokio::io::AsyncReadExt;
+    use tokio::net::TcpListener;
+
+    /// Serves `data`, honouring `Range`. The first response is cut off after `cut` bytes.
+    async fn serve(data: Vec<u8>, cut: usize) -> (String, Arc<Mutex<Vec<Option<String>>>>) {
+        let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let url = format!("http://{}/clip.mp4", listener.local_addr().unwrap());
+        let ranges = Arc::new(Mutex::new(Vec::new()));
+        let log = ranges.clone();
+
+        tokio::spawn(async move {
+            let mut first = true;
+            loop {
+                let Ok((mut socket, _)) = listener.accept().await else { return };
+                let mut buf = vec![0u8; 4096];
+                let n = socket.read(&mut buf).await.unwrap_or(0);
+                let head = String::from_utf8_lossy(&buf[..n]).to_string();
+                let range = head.lines()
+                    .find_map(|l| l.to_ascii_lowercase().strip_prefix("range: bytes=").map(|r| r.trim_end_matches('-').to_string()));
+                log.lock().unwrap().push(range.clone());
+
+                let start: usize = range.and_then(|r| r.parse().ok()).unwrap_or(0);
+                let status = if start > 0 { "206 Partial Content" } else { "200 OK" };
+                let body = &data[start..];
+                let header = format!("HTTP/1.1 {}\r\nContent-Length: {}\r\nConnection: close\r\n\r\n", status, body.len());
+                let _ = socket.write_all(header.as_bytes()).await;
+                let send = if first { &body[..cut.min(body.len())] } else { body };
+                let _ = socket.write_all(send).await;
+                first = false;
+            }
+        });
+
+        (url, ranges)
+    }
+
+    fn manager() -> UploadManager {
+        UploadManager::new(super::super::UploadOptions { max_retries: 3, base_backoff: Duration::from_millis(5), ..Default::default() }).unwrap()
+    }
+
+    #[test]
+    fn test_download_resumes_after_cut() {
+        let rt = tokio::runtime::Builder::new_current_thread().enable_all().build().unwrap();
+        rt.block_on(async {
+            let data: Vec<u8> = (0..50_000).map(|i| (i % 241) as u8).collect();
+            let expected = {
+                let mut hasher = integrity::StreamHasher::default();
+                hasher.update(&data);
+                hasher.finish()
+            };
+            let (url, ranges) = serve(data.clone(), 20_000).await;
+            let dir = std::env::temp_dir().join(format!("squadsync_download_{}", std::process::id()));
+            let _ = std::fs::remove_dir_all(&dir);
+            let dest = dir.join(view_file_name("Player 2"));
+
+            let hashes = manager().download(&url, &dest, Some(&expected.sha256), Arc::new(|_| {})).await.unwrap();
+            assert_eq!(hashes, expected);
+            assert_eq!(std::fs::read(&dest).unwrap(), data);
+            assert!(!part_path(&dest).exists());
+            assert_eq!(*ranges.lock().unwrap(), vec![None, Some("20000".to_string())]);
+        });
+    }
+
+    #[test]
+    fn test_download_rejects_wrong_hash() {
+        let rt = tokio::runtime::Builder::new_current_thread().enable_all().build().unwrap();
+        rt.block_on(async {
+            let (url, _) = serve(b"not the clip".to_vec(), usize::MAX).await;
+            let dest = std::env::temp_dir().join(format!("squadsync_download_bad_{}.mp4", std::process::id()));
+            let err = manager().download(&url, &dest, Some(&"0".repeat(64)), Arc::new(|_| {})).await.unwrap_err();
+            assert!(err.contains("SHA-256"), "{}", err);
+            assert!(!dest.exists());
+        });
+    }
+
+    #[test]
+    fn test_moment_bundle_round_trip() {
+        let dir = std::env::temp_dir().join(format!("squadsync_moment_{}", std::process::id()));
+        std::fs::create_dir_all(&dir).unwrap();
+        let view = |author: &str, start: u64| MomentView {
+            view: View { author: author.to_string(), url: "https://clips/x.mp4".into(), timestamp: 1, video_start_time_ms: start, duration_ms: 30_000, sha256: None },
+            file: view_file_name(author),
+            downloaded_at_ms: 2,
+        };
+
+        let mut moment = SquadMoment::load(&dir, "clip/1");
+        assert_eq!(moment.clip_id, "clip/1");
+        moment.upsert(view("b", 200));
+        moment.upsert(view("a", 100));
+        moment.upsert(view("b", 150));
+        moment.save(&dir).unwrap();
+
+        let loaded = SquadMoment::load(&dir, "clip/1");
+        assert_eq!(loaded, moment);
+        assert_eq!(loaded.views.iter().map(|v| v.view.author.as_str()).collect::<Vec<_>>(), vec!["a", "b"]);
+        assert_eq!(view_file_name("../evil:name"), "_evil_name.mp4");
+        assert!(std::fs::read_to_string(dir.join(MOMENT_FILE)).unwrap().contains("videoStartTimeMs"));
+    }
+}
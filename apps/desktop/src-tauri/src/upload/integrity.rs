@@ -0,0 +1,150 @@
+//! Upload integrity checks.
+//!
+//! Each `PUT` carries a `Content-MD5` of its body so the store rejects anything corrupted in
+//! transit, the bytes are hashed again as they are streamed (catching a file that changed
+//! mid-upload), and the returned ETag is compared with the MD5 where the store exposes it.
+//! The clip's SHA-256 is written next to it as `<clip>.sha256` (`sha256sum` format) so
+//! downloads can be verified.
+
+use base64::Engine;
+use md5::Md5;
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+use std::path::{Path, PathBuf};
+use tokio::io::{AsyncReadExt, AsyncSeekExt};
+
+use crate::constants::UPLOAD_CHUNK_SIZE;
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ClipHashes {
+    /// Hex SHA-256 of the file
+    pub sha256: String,
+    /// Hex MD5 of the file (what S3 reports as the ETag of a single PUT)
+    pub md5: String,
+    pub size: u64,
+}
+
+/// SHA-256 and MD5 computed together over a stream of chunks.
+#[derive(Default, Clone)]
+pub struct StreamHasher {
+    sha256: Sha256,
+    md5: Md5,
+    size: u64,
+}
+
+impl StreamHasher {
+    pub fn update(&mut self, bytes: &[u8]) {
+        self.sha256.update(bytes);
+        self.md5.update(bytes);
+        self.size += bytes.len() as u64;
+    }
+
+    pub fn finish(self) -> ClipHashes {
+        ClipHashes {
+            sha256: hex::encode(self.sha256.finalize()),
+            md5: hex::encode(self.md5.finalize()),
+            size: self.size,
+        }
+    }
+}
+
+/// Hashes `len` bytes of the file starting at `offset`.
+pub async fn hash_range(path: &Path, offset: u64, len: u64) -> Result<ClipHashes, String> {
+    let mut file = tokio::fs::File::open(path).await
+        .map_err(|e| format!("Failed to open file: {}", e))?;
+    file.seek(std::io::SeekFrom::Start(offset)).await
+        .map_err(|e| format!("Failed to seek file: {}", e))?;
+
+    let mut reader = file.take(len);
+    let mut hasher = StreamHasher::default();
+    let mut buf = vec![0u8; UPLOAD_CHUNK_SIZE];
+    loop {
+        let n = reader.read(&mut buf).await.map_err(|e| format!("Failed to read file: {}", e))?;
+        if n == 0 {
+            break;
+        }
+        hasher.update(&buf[..n]);
+    }
+
+    if hasher.size != len {
+        return Err(format!("File ended after {} of {} bytes", hasher.size, len));
+    }
+    Ok(hasher.finish())
+}
+
+pub async fn hash_file(path: &Path) -> Result<ClipHashes, String> {
+    let size = tokio::fs::metadata(path).await
+        .map_err(|e| format!("File not found: {} ({})", path.display(), e))?
+        .len();
+    hash_range(path, 0, size).await
+}
+
+/// `Content-MD5` header value (base64 of the raw digest) for a hex MD5.
+pub fn content_md5(md5_hex: &str) -> Option<String> {
+    hex::decode(md5_hex).ok().map(|raw| base64::engine::general_purpose::STANDARD.encode(raw))
+}
+
+/// Whether an ETag agrees with the MD5 of what was sent. `None` when the ETag is not a plain
+/// MD5 (multipart ETags, encrypted objects, stores with opaque ETags) and can't be checked.
+pub fn etag_matches(etag: &str, md5_hex: &str) -> Option<bool> {
+    let etag = etag.trim().trim_start_matches("W/").trim_matches('"');
+    let is_md5 = etag.len() == 32 && etag.bytes().all(|b| b.is_ascii_hexdigit());
+    is_md5.then(|| etag.eq_ignore_ascii_case(md5_hex))
+}
+
+pub fn hash_record_path(path: &Path) -> PathBuf {
+    let mut name = path.as_os_str().to_owned();
+    name.push(".sha256");
+    PathBuf::from(name)
+}
+
+/// Writes `<clip>.sha256` next to the clip.
+pub fn record_hashes(path: &Path, hashes: &ClipHashes) -> Result<(), String> {
+    let file_name = path.file_name().map(|n| n.to_string_lossy().to_string()).unwrap_or_default();
+    std::fs::write(hash_record_path(path), format!("{}  {}\n", hashes.sha256, file_name))
+        .map_err(|e| format!("Failed to record clip hash: {}", e))
+}
+
+/// SHA-256 recorded next to the clip, if any.
+pub fn recorded_sha256(path: &Path) -> Option<String> {
+    let content = std::fs::read_to_string(hash_record_path(path)).ok()?;
+    let hash = content.split_whitespace().next()?;
+    (hash.len() == 64 && hash.bytes().all(|b| b.is_ascii_hexdigit())).then(|| hash.to_ascii_lowercase())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_known_digests() {
+        let mut hasher = StreamHasher::default();
+        hasher.update(b"hello ");
+        hasher.update(b"world");
+        let hashes = hasher.finish();
+        assert_eq!(hashes.md5, "5eb63bbbe01eeed093cb22bb8f5acdc3");
+        assert_eq!(hashes.sha256, "b94d27b9934d3e08a52e52d7da7dabfac484efe37a5380ee9088f7ace2efcde9");
+        assert_eq!(hashes.size, 11);
+        assert_eq!(content_md5(&hashes.md5).as_deref(), Some("XrY7u+Ae7tCTyyK7j1rNww=="));
+    }
+
+    #[test]
+    fn test_etag_matches() {
+        let md5 = "5eb63bbbe01eeed093cb22bb8f5acdc3";
+        assert_eq!(etag_matches("\"5eb63bbbe01eeed093cb22bb8f5acdc3\"", md5), Some(true));
+        assert_eq!(etag_matches("\"00000000000000000000000000000000\"", md5), Some(false));
+        assert_eq!(etag_matches("\"5eb63bbbe01eeed093cb22bb8f5acdc3-4\"", md5), None);
+        assert_eq!(etag_matches("opaque", md5), None);
+    }
+
+    #[test]
+    fn test_record_and_read_hash() {
+        let clip = std::env::temp_dir().join(format!("squadsync_hash_{}.mp4", std::process::id()));
+        std::fs::write(&clip, b"hello world").unwrap();
+        let rt = tokio::runtime::Builder::new_current_thread().build().unwrap();
+        let hashes = rt.block_on(hash_file(&clip)).unwrap();
+        record_hashes(&clip, &hashes).unwrap();
+        assert_eq!(recorded_sha256(&clip), Some(hashes.sha256));
+        assert!(rt.block_on(hash_range(&clip, 6, 10)).is_err());
+    }
+}
@@ -0,0 +1,608 @@
+//! Clip Upload Manager
+//!
+//! Uploads clips to presigned URLs, either as one `PUT` or as an S3-style multipart upload.
+//!
+//! # Architecture
+//!
+//! * `throttle`: Token bucket shared by all uploads (configurable bandwidth cap).
+//! * `progress`: Byte accounting and rate-limited progress reports.
+//! * `queue`: Persistent queue that runs uploads with bounded concurrency and survives restarts.
+//! * `download`: Resumable downloads of squadmates' clips into local squad moment bundles.
+//! * `integrity`: Content-MD5 / SHA-256 hashing and ETag verification.
+//! * `storage`: Where clips go: server-presigned URLs or a self-hosted S3 bucket (SigV4).
+//!
+//! Multipart uploads send each part with its own retry/backoff and record finished parts
+//! (part number + ETag) in a `<clip>.upload.json` sidecar, so a retried upload resumes
+//! instead of starting over. Only the part that failed is re-sent.
+
+pub mod download;
+pub mod integrity;
+pub mod progress;
+pub mod queue;
+pub mod storage;
+pub mod throttle;
+
+use serde::{Deserialize, Serialize};
+use std::path::{Path, PathBuf};
+use std::sync::Arc;
+use std::time::Duration;
+
+use futures_util::StreamExt;
+use tokio::io::{AsyncReadExt, AsyncSeekExt};
+use tokio_util::io::ReaderStream;
+
+use crate::constants::{UPLOAD_BASE_BACKOFF_MS, UPLOAD_CHUNK_SIZE, UPLOAD_CONNECT_TIMEOUT_SECS, UPLOAD_MAX_BACKOFF_MS, UPLOAD_MIN_RATE_BYTES};
+use integrity::{ClipHashes, StreamHasher};
+use progress::{ProgressCallback, ProgressTracker};
+use throttle::RateLimiter;
+
+pub const UPLOAD_PROGRESS_EVENT: &str = "upload-progress";
+
+#[derive(Clone)]
+pub struct UploadOptions {
+    pub max_retries: u32,
+    pub base_backoff: Duration,
+    pub limiter: Option<RateLimiter>,
+    pub content_type: String,
+}
+
+impl Default for UploadOptions {
+    fn default() -> Self {
+        Self {
+            max_retries: 5,
+            base_backoff: Duration::from_millis(UPLOAD_BASE_BACKOFF_MS),
+            limiter: None,
+            content_type: "video/mp4".to_string(),
+        }
+    }
+}
+
+/// Presigned URLs for one S3 multipart upload, created by the server.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct MultipartPlan {
+    pub upload_id: String,
+    /// Size of every part except the last (S3 minimum is 5 MiB).
+    pub part_size: u64,
+    /// One presigned `UploadPart` URL per part, in part order.
+    pub part_urls: Vec<String>,
+    /// Presigned `CompleteMultipartUpload` URL.
+    pub complete_url: String,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct CompletedPart {
+    pub part_number: u32,
+    pub etag: String,
+}
+
+/// Resume state stored next to the clip while a multipart upload is in progress.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+struct ResumeState {
+    upload_id: String,
+    part_size: u64,
+    completed: Vec<CompletedPart>,
+}
+
+/// What one successful `PUT` sent.
+struct SentRange {
+    etag: Option<String>,
+    hashes: ClipHashes,
+}
+
+/// Result of one HTTP attempt: retryable failures are backed off and tried again.
+enum AttemptError {
+    Retryable(String),
+    Fatal(String),
+}
+
+pub struct UploadManager {
+    client: reqwest::Client,
+    options: UploadOptions,
+}
+
+impl UploadManager {
+    pub fn new(options: UploadOptions) -> Result<Self, String> {
+        let client = reqwest::Client::builder()
+            .connect_timeout(Duration::from_secs(UPLOAD_CONNECT_TIMEOUT_SECS))
+            .build()
+            .map_err(|e| format!("Failed to create HTTP client: {}", e))?;
+        Ok(Self { client, options })
+    }
+
+    /// Uploads the whole file with a single `PUT`, retrying from the start on failure.
+    /// Returns the hashes of what was sent and records the SHA-256 next to the clip.
+    pub async fn upload_single(&self, path: &Path, url: &str, on_progress: ProgressCallback) -> Result<ClipHashes, String> {
+        let size = file_size(path).await?;
+        let tracker = Arc::new(ProgressTracker::new(path.to_string_lossy().to_string(), size, 0, on_progress));
+
+        let sent = self.with_retry("upload", || async {
+            let before = tracker.sent();
+            let result = self.put_range(url, path, 0, size, &tracker).await;
+            if result.is_err() {
+                tracker.rollback(tracker.sent() - before);
+            }
+            result
+        }).await?;
+
+        record_hashes(path, &sent.hashes);
+        tracker.finish();
+        Ok(sent.hashes)
+    }
+
+    /// Uploads the file as the parts described by `plan`, then completes the multipart upload.
+    pub async fn upload_multipart(&self, path: &Path, plan: &MultipartPlan, on_progress: ProgressCallback) -> Result<Vec<CompletedPart>, String> {
+        let size = file_size(path).await?;
+        let part_count = part_count(size, plan.part_size);
+        if plan.part_size == 0 || part_count != plan.part_urls.len() as u64 {
+            return Err(format!("Upload plan has {} part URLs, but {} bytes in {}-byte parts need {}",
+                plan.part_urls.len(), size, plan.part_size, part_count));
+        }
+
+        let state_path = resume_state_path(path);
+        let mut completed = load_resume_state(&state_path, plan);
+        if !completed.is_empty() {
+            log::info!("Resuming upload {}: {}/{} parts already done", plan.upload_id, completed.len(), part_count);
+        }
+
+        let already_sent = completed.iter().map(|p| part_range(size, plan.part_size, p.part_number).1).sum();
+        let tracker = Arc::new(ProgressTracker::new(path.to_string_lossy().to_string(), size, already_sent, on_progress));
+
+        for (index, url) in plan.part_urls.iter().enumerate() {
+            let part_number = index as u32 + 1;
+            if completed.iter().any(|p| p.part_number == part_number) {
+                continue;
+            }
+
+            let (offset, len) = part_range(size, plan.part_size, part_number);
+            let etag = self.with_retry(&format!("part {}", part_number), || async {
+                let before = tracker.sent();
+                let result = self.put_range(url, path, offset, len, &tracker).await;
+                match result {
+                    Ok(SentRange { etag: Some(etag), .. }) => Ok(etag),
+                    Ok(SentRange { etag: None, .. }) => Err(AttemptError::Fatal(format!("Part {} response has no ETag", part_number))),
+                    Err(e) => {
+                        tracker.rollback(tracker.sent() - before);
+                        Err(e)
+                    }
+                }
+            }).await?;
+
+            completed.push(CompletedPart { part_number, etag });
+            save_resume_state(&state_path, plan, &completed);
+        }
+
+        completed.sort_by_key(|p| p.part_number);
+        let body = complete_multipart_xml(&completed);
+        self.with_retry("complete", || async {
+            let response = self.client.post(&plan.complete_url)
+                .header("Content-Type", "application/xml")
+                .body(body.clone())
+                .timeout(Duration::from_secs(60))
+                .send()
+                .await
+                .map_err(|e| AttemptError::Retryable(format!("Complete request failed: {}", e)))?;
+            let status = response.status();
+            let text = response.text().await.unwrap_or_default();
+            // S3 can report a failed completion with 200 and an <Error> body
+            if status.is_success() && !text.contains("<Error>") {
+                Ok(())
+            } else {
+                Err(classify_status(status, &text))
+            }
+        }).await?;
+
+        let _ = std::fs::remove_file(&state_path);
+        // Parts may come from an earlier run, so hash the whole file for the record
+        match integrity::hash_file(path).await {
+            Ok(hashes) => record_hashes(path, &hashes),
+            Err(e) => log::warn!("Failed to hash uploaded clip: {}", e),
+        }
+        tracker.finish();
+        Ok(completed)
+    }
+
+    /// PUTs `len` bytes at `offset` of the file with a `Content-MD5`, checking the bytes streamed
+    /// and the returned ETag against it.
+    async fn put_range(&self, url: &str, path: &Path, offset: u64, len: u64, tracker: &Arc<ProgressTracker>) -> Result<SentRange, AttemptError> {
+        let expected = integrity::hash_range(path, offset, len).await.map_err(AttemptError::Fatal)?;
+        let content_md5 = integrity::content_md5(&expected.md5).unwrap_or_default();
+
+        let mut file = tokio::fs::File::open(path).await
+            .map_err(|e| AttemptError::Fatal(format!("Failed to open file: {}", e)))?;
+        file.seek(std::io::SeekFrom::Start(offset)).await
+            .map_err(|e| AttemptError::Fatal(format!("Failed to seek file: {}", e)))?;
+
+        let limiter = self.options.limiter.clone();
+        let counter = tracker.clone();
+        let hasher = Arc::new(std::sync::Mutex::new(StreamHasher::default()));
+        let stream_hasher = hasher.clone();
+        let stream = ReaderStream::with_capacity(file.take(len), UPLOAD_CHUNK_SIZE).then(move |chunk| {
+            let limiter = limiter.clone();
+            let counter = counter.clone();
+            let hasher = stream_hasher.clone();
+            async move {
+                if let Ok(bytes) = &chunk {
+                    if let Some(limiter) = &limiter {
+                        limiter.acquire(bytes.len()).await;
+                    }
+                    if let Ok(mut hasher) = hasher.lock() {
+                        hasher.update(bytes);
+                    }
+                    counter.add(bytes.len() as u64);
+                }
+                chunk
+            }
+        });
+
+        let response = self.client.put(url)
+            .header("Content-Type", &self.options.content_type)
+            .header("Content-Length", len)
+            .header("Content-MD5", content_md5)
+            .body(reqwest::Body::wrap_stream(stream))
+            .timeout(request_timeout(len, self.options.limiter.is_some()))
+            .send()
+            .await
+            .map_err(|e| AttemptError::Retryable(format!("Upload request failed: {}", e)))?;
+
+        let status = response.status();
+        if !status.is_success() {
+            let text = response.text().await.unwrap_or_default();
+            return Err(classify_status(status, &text));
+        }
+
+        let etag = response.headers().get("ETag").and_then(|v| v.to_str().ok()).map(|s| s.to_string());
+        let sent = hasher.lock().map(|h| h.clone().finish()).unwrap_or_else(|e| e.into_inner().clone().finish());
+        if sent != expected {
+            return Err(AttemptError::Retryable("File changed while it was being uploaded".to_string()));
+        }
+        if let Some(false) = etag.as_deref().and_then(|etag| integrity::etag_matches(etag, &sent.md5)) {
+            return Err(AttemptError::Retryable(format!("ETag {} does not match MD5 {} of the upload", etag.unwrap_or_default(), sent.md5)));
+        }
+
+        Ok(SentRange { etag, hashes: sent })
+    }
+
+    async fn with_retry<T, F, Fut>(&self, what: &str, mut attempt: F) -> Result<T, String>
+    where
+        F: FnMut() -> Fut,
+        Fut: std::future::Future<Output = Result<T, AttemptError>>,
+    {
+        let mut tries = 0;
+        loop {
+            match attempt().await {
+                Ok(value) => return Ok(value),
+                Err(AttemptError::Fatal(e)) => return Err(e),
+                Err(AttemptError::Retryable(e)) => {
+                    if tries >= self.options.max_retries {
+                        return Err(format!("{} failed after {} attempts: {}", what, tries + 1, e));
+                    }
+                    let delay = backoff_delay(self.options.base_backoff, tries);
+                    log::warn!("Upload {} failed ({}). Retrying in {:?}", what, e, delay);
+                    tokio::time::sleep(delay).await;
+                    tries += 1;
+                }
+            }
+        }
+    }
+}
+
+/// Exponential backoff: base * 2^attempt, capped.
+pub fn backoff_delay(base: Duration, attempt: u32) -> Duration {
+    let factor = 1u32.checked_shl(attempt.min(16)).unwrap_or(u32::MAX);
+    base.saturating_mul(factor).min(Duration::from_millis(UPLOAD_MAX_BACKOFF_MS))
+}
+
+/// 5xx, 408 and 429 are worth retrying; other 4xx (expired or invalid URL) are not.
+fn classify_status(status: reqwest::StatusCode, body: &str) -> AttemptError {
+    let message = format!("Upload failed with status {}: {}", status, body.chars().take(300).collect::<String>());
+    if status.is_server_error() || status.as_u16() == 408 || status.as_u16() == 429 || status.is_success() {
+        AttemptError::Retryable(message)
+    } else {
+        AttemptError::Fatal(message)
+    }
+}
+
+/// Generous per-request timeout: enough for the body at the slowest expected rate.
+fn request_timeout(len: u64, throttled: bool) -> Duration {
+    let min_rate = if throttled { UPLOAD_MIN_RATE_BYTES / 4 } else { UPLOAD_MIN_RATE_BYTES };
+    Duration::from_secs(60 + len / min_rate)
+}
+
+pub fn part_count(size: u64, part_size: u64) -> u64 {
+    if part_size == 0 {
+        return 0;
+    }
+    size.div_ceil(part_size).max(1)
+}
+
+/// (offset, length) of a 1-based part.
+fn part_range(size: u64, part_size: u64, part_number: u32) -> (u64, u64) {
+    let offset = (part_number as u64 - 1) * part_size;
+    (offset, part_size.min(size.saturating_sub(offset)))
+}
+
+pub fn complete_multipart_xml(parts: &[CompletedPart]) -> String {
+    let mut xml = String::from("<CompleteMultipartUpload>");
+    for part in parts {
+        xml.push_str(&format!("<Part><PartNumber>{}</PartNumber><ETag>{}</ETag></Part>", part.part_number, part.etag));
+    }
+    xml.push_str("</CompleteMultipartUpload>");
+    xml
+}
+
+async fn file_size(path: &Path) -> Result<u64, String> {
+    tokio::fs::metadata(path).await
+        .map(|m| m.len())
+        .map_err(|e| format!("File not found: {} ({})", path.display(), e))
+}
+
+fn record_hashes(path: &Path, hashes: &ClipHashes) {
+    if let Err(e) = integrity::record_hashes(path, hashes) {
+        log::warn!("{}", e);
+    }
+}
+
+fn resume_state_path(path: &Path) -> PathBuf {
+    let mut name = path.as_os_str().to_owned();
+    name.push(".upload.json");
+    PathBuf::from(name)
+}
+
+/// Parts finished by an earlier attempt of the same upload. A different upload ID or part size starts over.
+fn load_resume_state(state_path: &Path, plan: &MultipartPlan) -> Vec<CompletedPart> {
+    std::fs::read_to_string(state_path)
+        .ok()
+        .and_then(|s| serde_json::from_str::<ResumeState>(&s).ok())
+        .filter(|s| s.upload_id == plan.upload_id && s.part_size == plan.part_size)
+        .map(|s| s.completed)
+        .unwrap_or_default()
+}
+
+fn save_resume_state(state_path: &Path, plan: &MultipartPlan, completed: &[CompletedPart]) {
+    let state = ResumeState {
+        upload_id: plan.upload_id.clone(),
+        part_size: plan.part_size,
+        completed: completed.to_vec(),
+    };
+    if let Ok(json) = serde_json::to_string(&state) {
+        if let Err(e) = std::fs::write(state_path, json) {
+            log::warn!("Failed to save upload resume state: {}", e);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::collections::VecDeque;
+    use std::sync::Mutex;
+    use tokio::io::AsyncWriteExt;
+    use tokio::net::TcpListener;
+
+    /// (method, path, body) of a request the mock server received.
+    type Recorded = (String, String, Vec<u8>);
+
+    /// Minimal HTTP/1.1 server: answers requests with scripted (status, etag) responses and records them.
+    struct MockServer {
+        url: String,
+        requests: Arc<Mutex<Vec<Recorded>>>,
+    }
+
+    impl MockServer {
+        async fn start(responses: Vec<(u16, Option<&str>)>) -> Self {
+            let responses: VecDeque<(u16, Option<String>)> = responses.into_iter().map(|(s, e)| (s, e.map(String::from))).collect();
+            let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+            let url = format!("http://{}", listener.local_addr().unwrap());
+            let requests = Arc::new(Mutex::new(Vec::new()));
+            let log = requests.clone();
+            let responses = Arc::new(Mutex::new(responses));
+
+            tokio::spawn(async move {
+                loop {
+                    let Ok((mut socket, _)) = listener.accept().await else { return };
+                    let log = log.clone();
+                    let responses = responses.clone();
+                    tokio::spawn(async move {
+                        let mut buf = Vec::new();
+                        let mut chunk = [0u8; 8192];
+                        let header_end = loop {
+                            let n = socket.read(&mut chunk).await.unwrap_or(0);
+                            if n == 0 { return; }
+                            buf.extend_from_slice(&chunk[..n]);
+                            if let Some(pos) = buf.windows(4).position(|w| w == b"\r\n\r\n") {
+                                break pos + 4;
+                            }
+                        };
+                        let head = String::from_utf8_lossy(&buf[..header_end]).to_string();
+                        let length = head.lines()
+                            .find_map(|l| l.to_lowercase().strip_prefix("content-length:").map(|v| v.trim().parse::<usize>().unwrap()))
+                            .unwrap_or(0);
+                        while buf.len() < header_end + length {
+                            let n = socket.read(&mut chunk).await.unwrap_or(0);
+                            if n == 0 { break; }
+                            buf.extend_from_slice(&chunk[..n]);
+                        }
+
+                        let mut request_line = head.lines().next().unwrap_or_default().split_whitespace();
+                        let method = request_line.next().unwrap_or_default().to_string();
+                        let path = request_line.next().unwrap_or_default().to_string();
+                        log.lock().unwrap().push((method, path, buf[header_end..].to_vec()));
+
+                        let (status, etag) = responses.lock().unwrap().pop_front().unwrap_or((200, None));
+                        let etag_header = etag.map(|e| format!("ETag: {}\r\n", e)).unwrap_or_default();
+                        let response = format!("HTTP/1.1 {} Mock\r\n{}Content-Length: 0\r\nConnection: close\r\n\r\n", status, etag_header);
+                        let _ = socket.write_all(response.as_bytes()).await;
+                    });
+                }
+            });
+
+            Self { url, requests }
+        }
+
+        fn requests(&self) -> Vec<Recorded> {
+            self.requests.lock().unwrap().clone()
+        }
+    }
+
+    fn runtime() -> tokio::runtime::Runtime {
+        tokio::runtime::Builder::new_current_thread().enable_all().build().unwrap()
+    }
+
+    fn test_file(name: &str, len: usize) -> (PathBuf, Vec<u8>) {
+        let dir = std::env::temp_dir().join(format!("squadsync_upload_{}_{}", name, std::process::id()));
+        let _ = std::fs::remove_dir_all(&dir);
+        std::fs::create_dir_all(&dir).unwrap();
+        let data: Vec<u8> = (0..len).map(|i| (i % 251) as u8).collect();
+        let path = dir.join("clip.mp4");
+        std::fs::write(&path, &data).unwrap();
+        (path, data)
+    }
+
+    fn options() -> UploadOptions {
+        UploadOptions { max_retries: 3, base_backoff: Duration::from_millis(5), ..Default::default() }
+    }
+
+    fn plan(server: &MockServer, parts: usize, part_size: u64) -> MultipartPlan {
+        MultipartPlan {
+            upload_id: "upload-1".to_string(),
+            part_size,
+            part_urls: (1..=parts).map(|n| format!("{}/part{}", server.url, n)).collect(),
+            complete_url: format!("{}/complete", server.url),
+        }
+    }
+
+    fn collect_progress() -> (ProgressCallback, Arc<Mutex<Vec<progress::UploadProgress>>>) {
+        let events = Arc::new(Mutex::new(Vec::new()));
+        let sink = events.clone();
+        (Arc::new(move |p| sink.lock().unwrap().push(p)), events)
+    }
+
+    #[test]
+    fn test_multipart_retries_failed_part_only() {
+        runtime().block_on(async {
+            // Part 2 hits a 503 once
+            let server = MockServer::start(vec![
+                (200, Some("\"e1\"")),
+                (503, None),
+                (200, Some("\"e2\"")),
+                (200, Some("\"e3\"")),
+                (200, None),
+            ]).await;
+            let (path, data) = test_file("retry", 2500);
+            let (callback, events) = collect_progress();
+
+            let manager = UploadManager::new(options()).unwrap();
+            let parts = manager.upload_multipart(&path, &plan(&server, 3, 1000), callback).await.unwrap();
+            assert_eq!(parts.iter().map(|p| p.etag.as_str()).collect::<Vec<_>>(), vec!["\"e1\"", "\"e2\"", "\"e3\""]);
+
+            let requests = server.requests();
+            let paths: Vec<&str> = requests.iter().map(|r| r.1.as_str()).collect();
+            assert_eq!(paths, vec!["/part1", "/part2", "/part2", "/part3", "/complete"]);
+
+            // Successful part bodies reassemble the file
+            let mut uploaded = Vec::new();
+            for index in [0, 2, 3] {
+                uploaded.extend_from_slice(&requests[index].2);
+            }
+            assert_eq!(uploaded, data);
+
+            let complete = String::from_utf8(requests[4].2.clone()).unwrap();
+            assert_eq!(complete, complete_multipart_xml(&parts));
+            assert_eq!(requests[4].0, "POST");
+
+            let last = events.lock().unwrap().last().cloned().unwrap();
+            assert!(last.done);
+            assert_eq!(last.bytes_sent, 2500);
+            assert!(!resume_state_path(&path).exists(), "resume state is removed after completion");
+        });
+    }
+
+    #[test]
+    fn test_multipart_resumes_from_saved_parts() {
+        runtime().block_on(async {
+            let server = MockServer::start(vec![(403, None)]).await;
+            let (path, _) = test_file("resume", 2500);
+            let first_plan = plan(&server, 3, 1000);
+
+            // First run: part 1 is rejected outright (expired URL), nothing is retried
+            let manager = UploadManager::new(options()).unwrap();
+            let (callback, _) = collect_progress();
+            let err = manager.upload_multipart(&path, &first_plan, callback).await.unwrap_err();
+            assert!(err.contains("403"), "{}", err);
+            assert_eq!(server.requests().len(), 1);
+
+            // Pretend an earlier run finished parts 1 and 2
+            save_resume_state(&resume_state_path(&path), &first_plan, &[
+                CompletedPart { part_number: 1, etag: "\"a\"".to_string() },
+                CompletedPart { part_number: 2, etag: "\"b\"".to_string() },
+            ]);
+            let server = MockServer::start(vec![(200, Some("\"c\"")), (200, None)]).await;
+            let plan = plan(&server, 3, 1000);
+            let (callback, _) = collect_progress();
+            let parts = manager.upload_multipart(&path, &plan, callback).await.unwrap();
+
+            let paths: Vec<String> = server.requests().into_iter().map(|r| r.1).collect();
+            assert_eq!(paths, vec!["/part3", "/complete"]);
+            assert_eq!(parts.len(), 3);
+            assert_eq!(server.requests()[0].2.len(), 500);
+        });
+    }
+
+    #[test]
+    fn test_single_put_retries_then_gives_up() {
+        runtime().block_on(async {
+            let server = MockServer::start(vec![(500, None), (200, None)]).await;
+            let (path, data) = test_file("single", 4096);
+            let manager = UploadManager::new(options()).unwrap();
+            let (callback, _) = collect_progress();
+            manager.upload_single(&path, &format!("{}/clip", server.url), callback).await.unwrap();
+            let requests = server.requests();
+            assert_eq!(requests.len(), 2);
+            assert_eq!(requests[1].2, data);
+
+            let server = MockServer::start(vec![(502, None); 10]).await;
+            let (callback, _) = collect_progress();
+            let err = manager.upload_single(&path, &format!("{}/clip", server.url), callback).await.unwrap_err();
+            assert!(err.contains("after 4 attempts"), "{}", err);
+        });
+    }
+
+    #[test]
+    fn test_single_put_verifies_etag_and_records_hash() {
+        runtime().block_on(async {
+            let (path, data) = test_file("verify", 3000);
+            let hashes = integrity::hash_file(&path).await.unwrap();
+            let good = format!("\"{}\"", hashes.md5);
+            // The first response claims a different MD5, so the upload is sent again
+            let server = MockServer::start(vec![(200, Some("\"00000000000000000000000000000000\"")), (200, Some(good.as_str()))]).await;
+            let manager = UploadManager::new(options()).unwrap();
+            let (callback, _) = collect_progress();
+
+            let sent = manager.upload_single(&path, &format!("{}/clip", server.url), callback).await.unwrap();
+            assert_eq!(sent, hashes);
+            assert_eq!(sent.size, data.len() as u64);
+            assert_eq!(server.requests().len(), 2);
+            assert_eq!(integrity::recorded_sha256(&path), Some(hashes.sha256));
+        });
+    }
+
+    #[test]
+    fn test_plan_must_cover_file() {
+        runtime().block_on(async {
+            let server = MockServer::start(vec![]).await;
+            let (path, _) = test_file("plan", 2500);
+            let manager = UploadManager::new(options()).unwrap();
+            let (callback, _) = collect_progress();
+            assert!(manager.upload_multipart(&path, &plan(&server, 2, 1000), callback).await.is_err());
+            assert!(server.requests().is_empty());
+        });
+    }
+
+    #[test]
+    fn test_backoff_delay() {
+        let base = Duration::from_millis(500);
+        assert_eq!(backoff_delay(base, 0), Duration::from_millis(500));
+        assert_eq!(backoff_delay(base, 3), Duration::from_millis(4000));
+        assert_eq!(backoff_delay(base, 30), Duration::from_millis(UPLOAD_MAX_BACKOFF_MS));
+    }
+}
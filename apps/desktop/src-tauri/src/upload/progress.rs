@@ -0,0 +1,110 @@
+//! Upload progress accounting.
+//!
+//! Bytes are counted as they are handed to the HTTP body, so a part that fails mid-way is
+//! rolled back before it is retried. Reports are rate limited and carry a smoothed send rate.
+
+use serde::Serialize;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
+
+use crate::constants::UPLOAD_PROGRESS_INTERVAL_MS;
+
+/// Smoothing of the reported rate (weight of the newest sample).
+const RATE_SMOOTHING: f64 = 0.3;
+
+#[derive(Debug, Clone, Serialize)]
+pub struct UploadProgress {
+    /// Identifies the upload (the clip's file path)
+    pub upload_id: String,
+    pub bytes_sent: u64,
+    pub total_bytes: u64,
+    pub bytes_per_second: f64,
+    pub done: bool,
+}
+
+pub type ProgressCallback = Arc<dyn Fn(UploadProgress) + Send + Sync>;
+
+pub struct ProgressTracker {
+    upload_id: String,
+    total: AtomicU64,
+    sent: AtomicU64,
+    report: Mutex<ReportState>,
+    callback: ProgressCallback,
+}
+
+struct ReportState {
+    last_at: Instant,
+    last_sent: u64,
+    rate: f64,
+}
+
+impl ProgressTracker {
+    pub fn new(upload_id: String, total: u64, already_sent: u64, callback: ProgressCallback) -> Self {
+        Self {
+            upload_id,
+            total: AtomicU64::new(total),
+            sent: AtomicU64::new(already_sent),
+            report: Mutex::new(ReportState { last_at: Instant::now(), last_sent: already_sent, rate: 0.0 }),
+            callback,
+        }
+    }
+
+    pub fn add(&self, bytes: u64) {
+        self.sent.fetch_add(bytes, Ordering::Relaxed);
+        self.maybe_report();
+    }
+
+    /// Forgets bytes of an attempt that failed and will be re-sent.
+    pub fn rollback(&self, bytes: u64) {
+        let _ = self.sent.fetch_update(Ordering::Relaxed, Ordering::Relaxed, |s| Some(s.saturating_sub(bytes)));
+    }
+
+    /// Starts over with a new total, e.g. once a download learns its size.
+    pub fn reset(&self, total: u64, sent: u64) {
+        self.total.store(total, Ordering::Relaxed);
+        self.sent.store(sent, Ordering::Relaxed);
+        if let Ok(mut state) = self.report.lock() {
+            state.last_sent = sent;
+        }
+    }
+
+    pub fn sent(&self) -> u64 {
+        self.sent.load(Ordering::Relaxed)
+    }
+
+    pub fn finish(&self) {
+        let rate = self.report.lock().map(|r| r.rate).unwrap_or(0.0);
+        let total = self.total.load(Ordering::Relaxed).max(self.sent());
+        (self.callback)(UploadProgress {
+            upload_id: self.upload_id.clone(),
+            bytes_sent: total,
+            total_bytes: total,
+            bytes_per_second: rate,
+            done: true,
+        });
+    }
+
+    fn maybe_report(&self) {
+        let Ok(mut state) = self.report.try_lock() else { return };
+        let elapsed = state.last_at.elapsed();
+        if elapsed < Duration::from_millis(UPLOAD_PROGRESS_INTERVAL_MS) {
+            return;
+        }
+
+        let sent = self.sent();
+        let total = self.total.load(Ordering::Relaxed);
+        let sample = sent.saturating_sub(state.last_sent) as f64 / elapsed.as_secs_f64();
+        state.rate = if state.rate == 0.0 { sample } else { state.rate + (sample - state.rate) * RATE_SMOOTHING };
+        state.last_at = Instant::now();
+        state.last_sent = sent;
+
+        (self.callback)(UploadProgress {
+            upload_id: self.upload_id.clone(),
+            bytes_sent: if total > 0 { sent.min(total) } else { sent },
+            total_bytes: total,
+            bytes_per_second: state.rate,
+            done: false,
+        });
+    }
+}
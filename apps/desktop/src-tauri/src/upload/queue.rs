@@ -0,0 +1,647 @@
+//! Persistent upload queue.
+//!
+//! Every clip upload is recorded in `uploads.json` (app data directory) before it starts, so
+//! uploads cut short by a crash or restart are picked up again on the next launch. At most
+//! `max_concurrent` uploads run at once; the rest wait as `Pending`.
+//!
+//! Presigned URLs expire. A job whose URL has expired (or is about to) is parked as `NeedsUrl`
+//! and the listener is told, so the frontend can ask the signaling server for a fresh URL and
+//! hand it back with `refresh_url`. Jobs for the direct S3 backend carry their object key and
+//! are simply re-signed locally.
+
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicU64, AtomicUsize, Ordering};
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+use tokio::sync::oneshot;
+
+use crate::constants::{UPLOAD_QUEUE_MAX_ATTEMPTS, UPLOAD_QUEUE_RETENTION_MS, UPLOAD_QUEUE_RETRY_MS, UPLOAD_URL_EXPIRY_MARGIN_MS};
+use super::integrity::ClipHashes;
+use super::progress::{ProgressCallback, UploadProgress};
+use super::storage::{SignedUrl, Storage};
+use super::{backoff_delay, UploadManager, UploadOptions};
+
+pub const UPLOAD_STATUS_EVENT: &str = "upload-status";
+pub const UPLOAD_URL_EXPIRED_EVENT: &str = "upload-url-expired";
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum UploadJobState {
+    Pending,
+    Uploading,
+    /// The presigned URL expired; waiting for `refresh_url`.
+    NeedsUrl,
+    Done,
+    Failed,
+}
+
+impl UploadJobState {
+    fn is_finished(self) -> bool {
+        matches!(self, UploadJobState::Done | UploadJobState::Failed)
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct UploadJob {
+    pub id: String,
+    pub clip_path: String,
+    pub url: String,
+    /// Signaling clip ID, used to request a new URL when this one expires.
+    #[serde(default)]
+    pub clip_id: Option<String>,
+    /// Object key when the URL was signed locally (direct S3 backend).
+    #[serde(default)]
+    pub storage_key: Option<String>,
+    /// When the presigned URL stops working (Unix ms), if known.
+    #[serde(default)]
+    pub expires_at_ms: Option<u64>,
+    #[serde(default)]
+    pub attempts: u32,
+    pub state: UploadJobState,
+    #[serde(default)]
+    pub last_error: Option<String>,
+    /// Hashes of what was uploaded, once done.
+    #[serde(default)]
+    pub hashes: Option<ClipHashes>,
+    /// Earliest time a failed attempt is retried (Unix ms).
+    #[serde(default)]
+    pub retry_at_ms: Option<u64>,
+    pub created_at_ms: u64,
+    pub updated_at_ms: u64,
+}
+
+impl UploadJob {
+    fn url_expired(&self, now_ms: u64) -> bool {
+        self.expires_at_ms.is_some_and(|expires| now_ms + UPLOAD_URL_EXPIRY_MARGIN_MS >= expires)
+    }
+}
+
+pub enum QueueEvent {
+    Status(UploadJob),
+    UrlExpired(UploadJob),
+    Progress(UploadProgress),
+}
+
+pub type QueueListener = Arc<dyn Fn(QueueEvent) + Send + Sync>;
+
+type Waiter = oneshot::Sender<Result<(), String>>;
+
+pub struct UploadQueue {
+    store: Option<PathBuf>,
+    jobs: Mutex<Vec<UploadJob>>,
+    waiters: Mutex<HashMap<String, Vec<Waiter>>>,
+    options: Mutex<UploadOptions>,
+    /// Re-signs URLs of jobs with a `storage_key`.
+    signer: Mutex<Option<Arc<dyn Storage>>>,
+    max_concurrent: AtomicUsize,
+    next_id: AtomicU64,
+    listener: QueueListener,
+}
+
+impl UploadQueue {
+    /// Loads the queue from `store` (if any). Uploads that were running when the app stopped go
+    /// back to `Pending`; finished jobs past the retention window are dropped.
+    pub fn open(store: Option<PathBuf>, listener: QueueListener) -> Self {
+        let now = now_ms();
+        let mut jobs = store.as_deref().map(load_jobs).unwrap_or_default();
+        jobs.retain(|job| !job.state.is_finished() || now.saturating_sub(job.updated_at_ms) < UPLOAD_QUEUE_RETENTION_MS);
+
+        for job in jobs.iter_mut() {
+            if job.state.is_finished() {
+                continue;
+            }
+            if !Path::new(&job.clip_path).exists() {
+                job.state = UploadJobState::Failed;
+                job.last_error = Some("Clip file no longer exists".to_string());
+                job.updated_at_ms = now;
+            } else if job.state == UploadJobState::Uploading {
+                job.state = UploadJobState::Pending;
+                job.retry_at_ms = None;
+                job.updated_at_ms = now;
+            }
+        }
+
+        let unfinished = jobs.iter().filter(|j| !j.state.is_finished()).count();
+        if unfinished > 0 {
+            log::info!("Upload queue restored with {} unfinished upload(s)", unfinished);
+        }
+
+        let queue = Self {
+            store,
+            jobs: Mutex::new(jobs),
+            waiters: Mutex::new(HashMap::new()),
+            options: Mutex::new(UploadOptions::default()),
+            signer: Mutex::new(None),
+            max_concurrent: AtomicUsize::new(1),
+            next_id: AtomicU64::new(0),
+            listener,
+        };
+        if let Ok(jobs) = queue.jobs.lock() {
+            queue.save(&jobs);
+        }
+        queue
+    }
+
+    /// Options for uploads started from now on.
+    pub fn configure(&self, options: UploadOptions, max_concurrent: usize) {
+        if let Ok(mut current) = self.options.lock() {
+            *current = options;
+        }
+        self.max_concurrent.store(max_concurrent.max(1), Ordering::Relaxed);
+    }
+
+    /// Storage used to re-sign expired URLs of locally signed jobs.
+    pub fn set_signer(&self, signer: Option<Arc<dyn Storage>>) {
+        if let Ok(mut current) = self.signer.lock() {
+            *current = signer;
+        }
+    }
+
+    /// Queues an upload to an object signed by `storage`; the URL is renewed locally when it expires.
+    pub fn enqueue_signed(&self, clip_path: &str, key: &str, target: SignedUrl) -> String {
+        self.insert(clip_path, &target.url, None, Some(key.to_string()), target.expires_at_ms)
+    }
+
+    /// Queues an upload and returns its job ID. Queuing a clip that is already waiting replaces
+    /// its URL instead of adding a second upload of the same file.
+    pub fn enqueue(&self, clip_path: &str, url: &str, clip_id: Option<String>, expires_at_ms: Option<u64>) -> String {
+        self.insert(clip_path, url, clip_id, None, expires_at_ms)
+    }
+
+    fn insert(&self, clip_path: &str, url: &str, clip_id: Option<String>, storage_key: Option<String>, expires_at_ms: Option<u64>) -> String {
+        let now = now_ms();
+        let expires_at_ms = expires_at_ms.or_else(|| presigned_expiry_ms(url));
+        let mut jobs = self.jobs.lock().unwrap_or_else(|e| e.into_inner());
+
+        let job = match jobs.iter_mut().find(|j| j.clip_path == clip_path && matches!(j.state, UploadJobState::Pending | UploadJobState::NeedsUrl)) {
+            Some(job) => {
+                job.url = url.to_string();
+                job.clip_id = clip_id.or(job.clip_id.take());
+                job.storage_key = storage_key;
+                job.expires_at_ms = expires_at_ms;
+                job.state = UploadJobState::Pending;
+                job.updated_at_ms = now;
+                job.clone()
+            }
+            None => {
+                let job = UploadJob {
+                    id: format!("upload-{}-{}", now, self.next_id.fetch_add(1, Ordering::Relaxed)),
+                    clip_path: clip_path.to_string(),
+                    url: url.to_string(),
+                    clip_id,
+                    storage_key,
+                    expires_at_ms,
+                    attempts: 0,
+                    state: UploadJobState::Pending,
+                    last_error: None,
+                    hashes: None,
+                    retry_at_ms: None,
+                    created_at_ms: now,
+                    updated_at_ms: now,
+                };
+                jobs.push(job.clone());
+                job
+            }
+        };
+
+        self.save(&jobs);
+        drop(jobs);
+        (self.listener)(QueueEvent::Status(job.clone()));
+        job.id
+    }
+
+    /// Replaces the URL of a job that is waiting for one (or failed) and queues it again.
+    pub fn refresh_url(&self, id: &str, url: &str, expires_at_ms: Option<u64>) -> Result<(), String> {
+        let mut jobs = self.jobs.lock().map_err(|e| e.to_string())?;
+        let job = jobs.iter_mut().find(|j| j.id == id).ok_or_else(|| format!("Unknown upload {}", id))?;
+        match job.state {
+            UploadJobState::Uploading => return Err(format!("Upload {} is already running", id)),
+            UploadJobState::Done => return Err(format!("Upload {} is already complete", id)),
+            UploadJobState::Failed => job.attempts = 0,
+            UploadJobState::Pending | UploadJobState::NeedsUrl => {}
+        }
+
+        job.url = url.to_string();
+        job.expires_at_ms = expires_at_ms.or_else(|| presigned_expiry_ms(url));
+        job.state = UploadJobState::Pending;
+        job.retry_at_ms = None;
+        job.last_error = None;
+        job.updated_at_ms = now_ms();
+        let job = job.clone();
+
+        self.save(&jobs);
+        drop(jobs);
+        (self.listener)(QueueEvent::Status(job));
+        Ok(())
+    }
+
+    pub fn list(&self) -> Vec<UploadJob> {
+        self.jobs.lock().map(|jobs| jobs.clone()).unwrap_or_default()
+    }
+
+    /// Resolves when the job finishes, fails, or needs a new URL.
+    pub async fn wait(&self, id: &str) -> Result<UploadJob, String> {
+        let rx = {
+            let jobs = self.jobs.lock().map_err(|e| e.to_string())?;
+            let job = jobs.iter().find(|j| j.id == id).ok_or_else(|| format!("Unknown upload {}", id))?;
+            if let Some(result) = settled_result(job) {
+                return result.map(|_| job.clone());
+            }
+            let (tx, rx) = oneshot::channel();
+            self.waiters.lock().map_err(|e| e.to_string())?.entry(id.to_string()).or_default().push(tx);
+            rx
+        };
+        rx.await.map_err(|_| "Upload queue stopped".to_string())??;
+        self.list().into_iter().find(|j| j.id == id).ok_or_else(|| format!("Unknown upload {}", id))
+    }
+
+    /// Starts as many pending uploads as the concurrency limit allows.
+    pub fn pump(self: &Arc<Self>) {
+        for job in self.take_runnable(now_ms()) {
+            let queue = self.clone();
+            tauri::async_runtime::spawn(async move { queue.run(job).await });
+        }
+    }
+
+    async fn run(self: Arc<Self>, job: UploadJob) {
+        log::info!("Uploading {} ({}, attempt {})", job.clip_path, job.id, job.attempts + 1);
+        let options = self.options.lock().map(|o| o.clone()).unwrap_or_default();
+        let listener = self.listener.clone();
+        let on_progress: ProgressCallback = Arc::new(move |progress| listener(QueueEvent::Progress(progress)));
+
+        let result = match UploadManager::new(options) {
+            Ok(manager) => manager.upload_single(Path::new(&job.clip_path), &job.url, on_progress).await,
+            Err(e) => Err(e),
+        };
+
+        if let Some(retry_at) = self.finish(&job.id, result, now_ms()) {
+            let queue = self.clone();
+            tauri::async_runtime::spawn(async move {
+                tokio::time::sleep(Duration::from_millis(retry_at.saturating_sub(now_ms()))).await;
+                queue.pump();
+            });
+        }
+        self.pump();
+    }
+
+    /// Marks the first pending jobs (up to the concurrency limit) as uploading and returns them.
+    /// Jobs whose URL has expired are moved to `NeedsUrl` instead.
+    fn take_runnable(&self, now: u64) -> Vec<UploadJob> {
+        let signer = self.signer.lock().ok().and_then(|s| s.clone());
+        let mut jobs = self.jobs.lock().unwrap_or_else(|e| e.into_inner());
+        let mut running = jobs.iter().filter(|j| j.state == UploadJobState::Uploading).count();
+        let max_concurrent = self.max_concurrent.load(Ordering::Relaxed);
+        let mut started = Vec::new();
+        let mut expired = Vec::new();
+
+        for job in jobs.iter_mut() {
+            if job.state != UploadJobState::Pending || job.retry_at_ms.is_some_and(|at| at > now) {
+                continue;
+            }
+            if job.url_expired(now) && !resign(job, signer.as_deref()) {
+                job.state = UploadJobState::NeedsUrl;
+                job.last_error = Some("Upload URL expired".to_string());
+                job.updated_at_ms = now;
+                expired.push(job.clone());
+            } else if running < max_concurrent {
+                job.state = UploadJobState::Uploading;
+                job.retry_at_ms = None;
+                job.updated_at_ms = now;
+                running += 1;
+                started.push(job.clone());
+            }
+        }
+
+        if !started.is_empty() || !expired.is_empty() {
+            self.save(&jobs);
+        }
+        drop(jobs);
+
+        for job in &started {
+            (self.listener)(QueueEvent::Status(job.clone()));
+        }
+        for job in expired {
+            self.settle(&job);
+            (self.listener)(QueueEvent::UrlExpired(job));
+        }
+        started
+    }
+
+    /// Records the outcome of an upload. Returns when to try again if the job was re-queued.
+    fn finish(&self, id: &str, result: Result<ClipHashes, String>, now: u64) -> Option<u64> {
+        let signer = self.signer.lock().ok().and_then(|s| s.clone());
+        let mut jobs = self.jobs.lock().unwrap_or_else(|e| e.into_inner());
+        let job = jobs.iter_mut().find(|j| j.id == id)?;
+        job.updated_at_ms = now;
+
+        match result {
+            Ok(hashes) => {
+                log::info!("Upload {} complete (sha256 {})", id, hashes.sha256);
+                job.state = UploadJobState::Done;
+                job.last_error = None;
+                job.hashes = Some(hashes);
+            }
+            Err(e) => {
+                job.attempts += 1;
+                if (job.url_expired(now) || is_expired_error(&e)) && resign(job, signer.as_deref()) {
+                    log::warn!("Upload {} URL expired, re-signed: {}", id, e);
+                    job.state = UploadJobState::Pending;
+                    job.retry_at_ms = None;
+                } else if job.url_expired(now) || is_expired_error(&e) {
+                    log::warn!("Upload {} needs a new URL: {}", id, e);
+                    job.state = UploadJobState::NeedsUrl;
+                } else if job.attempts >= UPLOAD_QUEUE_MAX_ATTEMPTS {
+                    log::error!("Upload {} failed after {} attempts: {}", id, job.attempts, e);
+                    job.state = UploadJobState::Failed;
+                } else {
+                    let delay = backoff_delay(Duration::from_millis(UPLOAD_QUEUE_RETRY_MS), job.attempts - 1);
+                    log::warn!("Upload {} failed ({}). Re-queued for {:?}", id, e, delay);
+                    job.state = UploadJobState::Pending;
+                    job.retry_at_ms = Some(now + delay.as_millis() as u64);
+                }
+                job.last_error = Some(e);
+            }
+        }
+
+        let job = job.clone();
+        self.save(&jobs);
+        drop(jobs);
+
+        self.settle(&job);
+        let event = if job.state == UploadJobState::NeedsUrl { QueueEvent::UrlExpired(job.clone()) } else { QueueEvent::Status(job.clone()) };
+        (self.listener)(event);
+        job.retry_at_ms.filter(|_| job.state == UploadJobState::Pending)
+    }
+
+    /// Wakes anyone waiting on the job if it has reached a state they care about.
+    fn settle(&self, job: &UploadJob) {
+        let Some(result) = settled_result(job) else { return };
+        let waiters = self.waiters.lock().ok().and_then(|mut w| w.remove(&job.id)).unwrap_or_default();
+        for waiter in waiters {
+            let _ = waiter.send(result.clone());
+        }
+    }
+
+    fn save(&self, jobs: &[UploadJob]) {
+        let Some(path) = &self.store else { return };
+        if let Err(e) = save_jobs(path, jobs) {
+            log::error!("Failed to save upload queue: {}", e);
+        }
+    }
+}
+
+fn settled_result(job: &UploadJob) -> Option<Result<(), String>> {
+    let error = || job.last_error.clone().unwrap_or_else(|| "Upload failed".to_string());
+    match job.state {
+        UploadJobState::Done => Some(Ok(())),
+        UploadJobState::Failed => Some(Err(error())),
+        UploadJobState::NeedsUrl => Some(Err(format!("Upload URL expired, waiting for a new one ({})", error()))),
+        UploadJobState::Pending | UploadJobState::Uploading => None,
+    }
+}
+
+/// Signs a new URL for a job with a storage key. False if the job can't be re-signed here.
+fn resign(job: &mut UploadJob, signer: Option<&dyn Storage>) -> bool {
+    let (Some(key), Some(signer)) = (&job.storage_key, signer) else { return false };
+    match signer.put_url(key) {
+        Ok(target) => {
+            job.url = target.url;
+            job.expires_at_ms = target.expires_at_ms;
+            true
+        }
+        Err(e) => {
+            log::error!("Failed to re-sign upload {}: {}", job.id, e);
+            false
+        }
+    }
+}
+
+/// S3 answers an expired presigned URL with 403 "Request has expired".
+fn is_expired_error(error: &str) -> bool {
+    error.to_ascii_lowercase().contains("expired")
+}
+
+/// Expiry of a SigV4 presigned URL (`X-Amz-Date` + `X-Amz-Expires`) in Unix ms.
+pub fn presigned_expiry_ms(url: &str) -> Option<u64> {
+    let url = reqwest::Url::parse(url).ok()?;
+    let mut date = None;
+    let mut expires = None;
+    for (key, value) in url.query_pairs() {
+        if key.eq_ignore_ascii_case("X-Amz-Date") {
+            date = Some(value.into_owned());
+        } else if key.eq_ignore_ascii_case("X-Amz-Expires") {
+            expires = value.parse::<u64>().ok();
+        }
+    }
+
+    let signed_at = chrono::NaiveDateTime::parse_from_str(&date?, "%Y%m%dT%H%M%SZ").ok()?.and_utc().timestamp_millis();
+    let expires = expires?;
+    u64::try_from(signed_at).ok().map(|ms| ms + expires * 1000)
+}
+
+fn load_jobs(path: &Path) -> Vec<UploadJob> {
+    match std::fs::read_to_string(path) {
+        Ok(content) => serde_json::from_str(&content).unwrap_or_else(|e| {
+            log::error!("Failed to parse upload queue {:?}: {}", path, e);
+            Vec::new()
+        }),
+        Err(_) => Vec::new(),
+    }
+}
+
+/// Writes through a temporary file so a crash mid-write can't corrupt the queue.
+fn save_jobs(path: &Path, jobs: &[UploadJob]) -> Result<(), String> {
+    if let Some(parent) = path.parent() {
+        std::fs::create_dir_all(parent).map_err(|e| e.to_string())?;
+    }
+    let json = serde_json::to_string_pretty(jobs).map_err(|e| e.to_string())?;
+    let tmp = path.with_extension("json.tmp");
+    std::fs::write(&tmp, json).map_err(|e| e.to_string())?;
+    std::fs::rename(&tmp, path).map_err(|e| e.to_string())
+}
+
+fn now_ms() -> u64 {
+    std::time::SystemTime::now()
+        .duration_since(std::time::UNIX_EPOCH)
+        .map(|d| d.as_millis() as u64)
+        .unwrap_or(0)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn temp_dir(name: &str) -> PathBuf {
+        let dir = std::env::temp_dir().join(format!("squadsync_queue_{}_{}", name, std::process::id()));
+        let _ = std::fs::remove_dir_all(&dir);
+        std::fs::create_dir_all(&dir).unwrap();
+        dir
+    }
+
+    fn clip(dir: &Path, name: &str) -> String {
+        let path = dir.join(name);
+        std::fs::write(&path, b"clip").unwrap();
+        path.to_string_lossy().to_string()
+    }
+
+    /// (job id, state, was a URL-expired event)
+    type Recorded = (String, UploadJobState, bool);
+
+    fn collect_events() -> (QueueListener, Arc<Mutex<Vec<Recorded>>>) {
+        let events = Arc::new(Mutex::new(Vec::new()));
+        let sink = events.clone();
+        let listener: QueueListener = Arc::new(move |event| {
+            let entry = match event {
+                QueueEvent::Status(job) => (job.id, job.state, false),
+                QueueEvent::UrlExpired(job) => (job.id, job.state, true),
+                QueueEvent::Progress(_) => return,
+            };
+            sink.lock().unwrap().push(entry);
+        });
+        (listener, events)
+    }
+
+    fn noop() -> QueueListener {
+        Arc::new(|_| {})
+    }
+
+    #[test]
+    fn test_restart_resumes_unfinished_uploads() {
+        let dir = temp_dir("restart");
+        let store = dir.join("uploads.json");
+        let a = clip(&dir, "a.mp4");
+        let b = clip(&dir, "b.mp4");
+
+        let queue = UploadQueue::open(Some(store.clone()), noop());
+        let id_a = queue.enqueue(&a, "https://r2.example/a", Some("clip-1".into()), None);
+        let id_b = queue.enqueue(&b, "https://r2.example/b", None, None);
+        assert_eq!(queue.take_runnable(now_ms()).len(), 1);
+        drop(queue); // app quits while `a` is uploading
+
+        std::fs::remove_file(&b).unwrap();
+        let queue = UploadQueue::open(Some(store), noop());
+        let jobs = queue.list();
+        let job_a = jobs.iter().find(|j| j.id == id_a).unwrap();
+        assert_eq!(job_a.state, UploadJobState::Pending);
+        assert_eq!(job_a.clip_id.as_deref(), Some("clip-1"));
+        let job_b = jobs.iter().find(|j| j.id == id_b).unwrap();
+        assert_eq!(job_b.state, UploadJobState::Failed);
+    }
+
+    #[test]
+    fn test_concurrency_limit_and_dedup() {
+        let dir = temp_dir("concurrency");
+        let queue = UploadQueue::open(None, noop());
+        queue.configure(UploadOptions::default(), 2);
+
+        let clips: Vec<String> = (0..3).map(|i| clip(&dir, &format!("{}.mp4", i))).collect();
+        let ids: Vec<String> = clips.iter().map(|c| queue.enqueue(c, "https://r2.example/x", None, None)).collect();
+        // Queuing the same waiting clip again reuses the job
+        assert_eq!(queue.enqueue(&clips[2], "https://r2.example/y", None, None), ids[2]);
+        assert_eq!(queue.list().len(), 3);
+
+        let now = now_ms();
+        let started: Vec<String> = queue.take_runnable(now).into_iter().map(|j| j.id).collect();
+        assert_eq!(started, ids[..2]);
+        assert!(queue.take_runnable(now).is_empty());
+
+        queue.finish(&ids[0], Ok(ClipHashes { sha256: String::new(), md5: String::new(), size: 4 }), now);
+        let started = queue.take_runnable(now);
+        assert_eq!(started.len(), 1);
+        assert_eq!(started[0].url, "https://r2.example/y");
+    }
+
+    #[test]
+    fn test_expired_url_waits_for_refresh() {
+        let dir = temp_dir("expiry");
+        let (listener, events) = collect_events();
+        let queue = UploadQueue::open(None, listener);
+        let now = now_ms();
+
+        let id = queue.enqueue(&clip(&dir, "a.mp4"), "https://r2.example/a", Some("clip-1".into()), Some(now + 10_000));
+        assert!(queue.take_runnable(now).is_empty());
+        assert_eq!(queue.list()[0].state, UploadJobState::NeedsUrl);
+        assert!(events.lock().unwrap().contains(&(id.clone(), UploadJobState::NeedsUrl, true)));
+
+        let rt = tokio::runtime::Builder::new_current_thread().build().unwrap();
+        assert!(rt.block_on(queue.wait(&id)).unwrap_err().contains("expired"));
+
+        queue.refresh_url(&id, "https://r2.example/a2", Some(now + 900_000)).unwrap();
+        let started = queue.take_runnable(now);
+        assert_eq!(started.len(), 1);
+        assert_eq!(started[0].url, "https://r2.example/a2");
+
+        // S3 rejecting the URL as expired also asks for a new one
+        queue.finish(&id, Err("Upload failed with status 403 Forbidden: Request has expired".into()), now);
+        assert_eq!(queue.list()[0].state, UploadJobState::NeedsUrl);
+    }
+
+    #[test]
+    fn test_pump_outside_a_runtime() {
+        // Sync commands call `pump` from a plain thread
+        let dir = temp_dir("pump");
+        let queue = Arc::new(UploadQueue::open(None, noop()));
+        queue.enqueue(&clip(&dir, "a.mp4"), "http://127.0.0.1:9/a", None, None);
+        queue.pump();
+        assert!(queue.take_runnable(now_ms()).is_empty());
+    }
+
+    struct FakeSigner;
+
+    impl Storage for FakeSigner {
+        fn name(&self) -> &'static str {
+            "fake"
+        }
+
+        fn put_url(&self, key: &str) -> Result<SignedUrl, String> {
+            Ok(SignedUrl { url: format!("https://s3.local/{}?fresh", key), expires_at_ms: Some(now_ms() + 3_600_000) })
+        }
+    }
+
+    #[test]
+    fn test_locally_signed_job_is_resigned() {
+        let dir = temp_dir("resign");
+        let (listener, events) = collect_events();
+        let queue = UploadQueue::open(None, listener);
+        queue.set_signer(Some(Arc::new(FakeSigner)));
+        let now = now_ms();
+
+        let stale = SignedUrl { url: "https://s3.local/user/a.mp4?old".into(), expires_at_ms: Some(now) };
+        queue.enqueue_signed(&clip(&dir, "a.mp4"), "user/a.mp4", stale);
+        let started = queue.take_runnable(now);
+        assert_eq!(started.len(), 1);
+        assert_eq!(started[0].url, "https://s3.local/user/a.mp4?fresh");
+        assert!(!events.lock().unwrap().iter().any(|(_, _, expired)| *expired));
+    }
+
+    #[test]
+    fn test_failed_upload_is_retried_then_given_up() {
+        let dir = temp_dir("retry");
+        let queue = UploadQueue::open(None, noop());
+        let id = queue.enqueue(&clip(&dir, "a.mp4"), "https://r2.example/a", None, None);
+
+        let mut now = now_ms();
+        for attempt in 1..UPLOAD_QUEUE_MAX_ATTEMPTS {
+            assert_eq!(queue.take_runnable(now).len(), 1);
+            let retry_at = queue.finish(&id, Err("connection reset".into()), now).expect("re-queued");
+            assert!(queue.take_runnable(now).is_empty(), "retried before backoff on attempt {}", attempt);
+            now = retry_at;
+        }
+
+        assert_eq!(queue.take_runnable(now).len(), 1);
+        assert_eq!(queue.finish(&id, Err("connection reset".into()), now), None);
+        let job = &queue.list()[0];
+        assert_eq!(job.state, UploadJobState::Failed);
+        assert_eq!(job.attempts, UPLOAD_QUEUE_MAX_ATTEMPTS);
+    }
+
+    #[test]
+    fn test_presigned_expiry() {
+        let url = "https://acc.r2.cloudflarestorage.com/bucket/key.mp4?X-Amz-Algorithm=AWS4-HMAC-SHA256&X-Amz-Date=20250101T120000Z&X-Amz-Expires=900&X-Amz-Signature=abc";
+        // 2025-01-01T12:00:00Z + 15 minutes
+        assert_eq!(presigned_expiry_ms(url), Some(1_735_732_800_000 + 900_000));
+        assert_eq!(presigned_expiry_ms("https://example.com/upload"), None);
+    }
+}
@@ -0,0 +1,98 @@
+//! Upload bandwidth limiting.
+//!
+//! A token bucket shared by every upload body, so the cap applies to the total upload rate
+//! rather than per request. Chunks reserve their size and sleep off any deficit before being sent.
+
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
+
+/// Minimum burst so small caps still allow a full read chunk through at once.
+const MIN_BURST_BYTES: f64 = 64.0 * 1024.0;
+/// Burst size as a fraction of one second of the rate.
+const BURST_SECONDS: f64 = 0.25;
+
+#[derive(Clone)]
+pub struct RateLimiter {
+    bytes_per_second: f64,
+    burst: f64,
+    state: Arc<Mutex<Bucket>>,
+}
+
+struct Bucket {
+    tokens: f64,
+    last: Instant,
+}
+
+impl RateLimiter {
+    pub fn new(bytes_per_second: u64) -> Self {
+        let rate = bytes_per_second.max(1) as f64;
+        let burst = (rate * BURST_SECONDS).max(MIN_BURST_BYTES);
+        Self {
+            bytes_per_second: rate,
+            burst,
+            state: Arc::new(Mutex::new(Bucket { tokens: burst, last: Instant::now() })),
+        }
+    }
+
+    /// Limiter for a cap given in kilobits per second (the unit shown in settings).
+    pub fn from_kbps(kbps: u32) -> Self {
+        Self::new(kbps as u64 * 1000 / 8)
+    }
+
+    /// Takes `bytes` from the bucket and returns how long the caller must wait before sending them.
+    pub fn reserve_at(&self, bytes: usize, now: Instant) -> Duration {
+        let mut bucket = self.state.lock().unwrap_or_else(|e| e.into_inner());
+        let elapsed = now.saturating_duration_since(bucket.last).as_secs_f64();
+        bucket.tokens = (bucket.tokens + elapsed * self.bytes_per_second).min(self.burst);
+        bucket.last = now.max(bucket.last);
+        bucket.tokens -= bytes as f64;
+
+        if bucket.tokens >= 0.0 {
+            Duration::ZERO
+        } else {
+            Duration::from_secs_f64(-bucket.tokens / self.bytes_per_second)
+        }
+    }
+
+    pub async fn acquire(&self, bytes: usize) {
+        let wait = self.reserve_at(bytes, Instant::now());
+        if !wait.is_zero() {
+            tokio::time::sleep(wait).await;
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_burst_then_paced() {
+        // 1 MB/s: burst of 256 KB goes through, the rest is paced
+        let limiter = RateLimiter::new(1_000_000);
+        let start = Instant::now();
+        assert_eq!(limiter.reserve_at(200_000, start), Duration::ZERO);
+        let wait = limiter.reserve_at(150_000, start);
+        assert!((wait.as_secs_f64() - 0.1).abs() < 0.001, "wait was {:?}", wait);
+
+        // After waiting it off, a further 100 KB costs another 100 ms
+        let later = start + wait;
+        let wait = limiter.reserve_at(100_000, later);
+        assert!((wait.as_secs_f64() - 0.1).abs() < 0.001, "wait was {:?}", wait);
+    }
+
+    #[test]
+    fn test_sustained_rate_matches_cap() {
+        let limiter = RateLimiter::from_kbps(8_000); // 1 MB/s
+        let mut now = Instant::now();
+        let mut waited = Duration::ZERO;
+        for _ in 0..100 {
+            let wait = limiter.reserve_at(64 * 1024, now);
+            now += wait;
+            waited += wait;
+        }
+        // 6.5 MB at 1 MB/s minus the initial burst
+        let expected = (100.0 * 65536.0 - 250_000.0) / 1_000_000.0;
+        assert!((waited.as_secs_f64() - expected).abs() < 0.01, "waited {:?}, expected {}", waited, expected);
+    }
+}
@@ -1,4 +1,6 @@
+import { useState } from 'react';
 import { useSettings } from '../hooks/useSettings';
+import { AudioDeviceInfo } from '../types/config';
 import {
   DEFAULT_BUFFER_SECONDS,
   DEFAULT_SEGMENT_TIME,
@@ -18,6 +20,10 @@ import {
   Video,
   Volume2,
   Clock,
+  SlidersHorizontal,
+  Upload,
+  Download,
+  Gauge,
 } from 'lucide-react';
 
 const Section = ({
@@ -45,6 +51,7 @@ const Select = ({
   options,
   icon: Icon,
   tooltip,
+  error,
 }: {
   label: string;
   value: string | number;
@@ -52,6 +59,7 @@ const Select = ({
   options: { label: string; value: string | number }[];
   icon?: React.ElementType;
   tooltip?: string;
+  error?: string;
 }) => (
   <div className="space-y-2">
     <div className="flex items-center gap-2">
@@ -85,9 +93,19 @@ const Select = ({
         </svg>
       </div>
     </div>
+    {error && <p className="text-xs text-red-400">{error}</p>}
   </div>
 );
 
+const deviceOption = (d: AudioDeviceInfo) => ({
+  label: `${d.name}${d.is_default ? ' (Default)' : ''}`,
+  value: d.id,
+});
+
+/** The saved device's ID, or the ID of the device with its name for configs saved without one. */
+const deviceValue = (devices: AudioDeviceInfo[], id?: string | null, name?: string) =>
+  id || devices.find((d) => d.name === name)?.id || '';
+
 export function Settings() {
   const {
     config,
@@ -96,11 +114,27 @@ export function Settings() {
     monitors,
     loading,
     saving,
+    fieldErrors,
     saveSettings,
+    selectAudioDevice,
+    setActiveProfile,
+    importProfile,
+    exportProfile,
+    runBenchmark,
     updateRecordingConfig,
     updateUserConfig,
   } = useSettings();
 
+  const [benchmarking, setBenchmarking] = useState(false);
+
+  async function handleBenchmark() {
+    setBenchmarking(true);
+    await runBenchmark(true);
+    setBenchmarking(false);
+  }
+
+  const errorFor = (field: string) => fieldErrors.find((e) => e.field === field)?.message;
+
   async function handleSave(e: React.FormEvent) {
     e.preventDefault();
     await saveSettings();
@@ -144,22 +178,16 @@ export function Settings() {
             <Select
               label="Microphone"
               icon={Mic}
-              value={config.recording.audio_source || ''}
-              onChange={(val) => updateRecordingConfig('audio_source', val || null)}
-              options={[
-                { label: 'None', value: '' },
-                ...audioDevices.map((d) => ({ label: d, value: d })),
-              ]}
+              value={deviceValue(audioDevices, config.recording.audio_source_id, config.recording.audio_source)}
+              onChange={(id) => selectAudioDevice('audio_source', audioDevices.find((d) => d.id === id))}
+              options={[{ label: 'None', value: '' }, ...audioDevices.map(deviceOption)]}
             />
             <Select
               label="System Audio"
               icon={Speaker}
-              value={config.recording.system_audio_device || ''}
-              onChange={(val) => updateRecordingConfig('system_audio_device', val || null)}
-              options={[
-                { label: 'None', value: '' },
-                ...systemAudioDevices.map((d) => ({ label: d, value: d })),
-              ]}
+              value={deviceValue(systemAudioDevices, config.recording.system_audio_device_id, config.recording.system_audio_device)}
+              onChange={(id) => selectAudioDevice('system_audio_device', systemAudioDevices.find((d) => d.id === id))}
+              options={[{ label: 'None', value: '' }, ...systemAudioDevices.map(deviceOption)]}
             />
           </div>
         </Section>
@@ -167,6 +195,48 @@ export function Settings() {
         {/* Video */}
         <Section title="Video" icon={Video}>
           <div className="space-y-6">
+            <div className="space-y-2">
+              <Select
+                label="Recording Profile"
+                icon={SlidersHorizontal}
+                value={config.active_profile || ''}
+                error={errorFor('active_profile')}
+                onChange={(val) => setActiveProfile(val || null)}
+                tooltip="Overrides the settings below; applied immediately. Games can pick their own."
+                options={[
+                  { label: 'None (settings below)', value: '' },
+                  ...(config.profiles ?? []).map((p) => ({ label: p.name, value: p.name })),
+                ]}
+              />
+              <div className="flex gap-4 text-xs">
+                <button
+                  type="button"
+                  onClick={importProfile}
+                  className="flex items-center gap-1 text-slate-400 hover:text-slate-200 transition-colors"
+                >
+                  <Upload size={12} /> Import
+                </button>
+                {config.active_profile && (
+                  <button
+                    type="button"
+                    onClick={() => config.active_profile && exportProfile(config.active_profile)}
+                    className="flex items-center gap-1 text-slate-400 hover:text-slate-200 transition-colors"
+                  >
+                    <Download size={12} /> Export
+                  </button>
+                )}
+                <Tooltip content="Stop the replay buffer first. Encodes test footage with each encoder for a minute or two, then switches to the best profile this machine keeps up with.">
+                  <button
+                    type="button"
+                    onClick={handleBenchmark}
+                    disabled={benchmarking}
+                    className="flex items-center gap-1 text-slate-400 hover:text-slate-200 transition-colors disabled:opacity-50"
+                  >
+                    <Gauge size={12} /> {benchmarking ? 'Benchmarking...' : 'Benchmark'}
+                  </button>
+                </Tooltip>
+              </div>
+            </div>
             <Select
               label="Monitor"
               icon={Monitor}
@@ -182,6 +252,7 @@ export function Settings() {
                 label="Resolution"
                 icon={LayoutTemplate}
                 value={config.recording.resolution || DEFAULT_RESOLUTION}
+                error={errorFor('recording.resolution')}
                 onChange={(val) => updateRecordingConfig('resolution', val)}
                 options={[
                   { label: 'Native', value: 'native' },
@@ -193,6 +264,7 @@ export function Settings() {
                 label="Framerate"
                 icon={Film}
                 value={config.recording.framerate}
+                error={errorFor('recording.framerate')}
                 onChange={(val) => updateRecordingConfig('framerate', parseInt(val))}
                 options={[
                   { label: '60 FPS', value: 60 },
@@ -209,6 +281,7 @@ export function Settings() {
             <Select
               label="Duration"
               value={config.recording.buffer_duration || DEFAULT_BUFFER_SECONDS}
+              error={errorFor('recording.buffer_duration')}
               onChange={(val) => updateRecordingConfig('buffer_duration', parseInt(val))}
               tooltip="How far back to record"
               options={[
@@ -223,6 +296,7 @@ export function Settings() {
             <Select
               label="Segment Size"
               value={config.recording.segment_time || DEFAULT_SEGMENT_TIME}
+              error={errorFor('recording.segment_time')}
               onChange={(val) => updateRecordingConfig('segment_time', parseInt(val))}
               tooltip="Smaller is safer (20s recommended)"
               options={[
@@ -13,6 +13,8 @@ interface Recording {
   size: number;
   created_at: number;
   duration?: number;
+  markers?: { label: string; offset_ms: number }[];
+  game?: string | null;
 }
 
 interface ClipCardProps {
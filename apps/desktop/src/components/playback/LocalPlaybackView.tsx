@@ -11,6 +11,8 @@ interface Recording {
   size: number;
   created_at: number;
   duration?: number;
+  markers?: { label: string; offset_ms: number }[];
+  game?: string | null;
 }
 
 export function LocalPlaybackView() {
@@ -1,4 +1,5 @@
 import React, { useState, useEffect } from 'react';
+import { listen } from '@tauri-apps/api/event';
 import { useRoom } from '../../hooks/useRoom';
 import { useSettings } from '../../hooks/useSettings';
 import { logger } from '../../lib/logger';
@@ -11,7 +12,11 @@ interface RoomManagerProps {
     timestamp: number,
     uploadUrl?: string,
     clipId?: string
-  ) => Promise<{ startTime: number | null; duration: number } | null>;
+  ) => Promise<{
+    startTime: number | null;
+    duration: number;
+    hashes?: { sha256: string; md5: string; size: number } | null;
+  } | null>;
 }
 
 export const RoomManager: React.FC<RoomManagerProps> = ({ onClipStart }) => {
@@ -52,12 +57,28 @@ export const RoomManager: React.FC<RoomManagerProps> = ({ onClipStart }) => {
             clipId,
             videoStartTimeMs: result.startTime,
             durationMs: result.duration,
+            sha256: result.hashes?.sha256,
+            md5: result.hashes?.md5,
+            sizeBytes: result.hashes?.size,
           });
         }
       }
     }
   );
 
+  // The "trigger squad clip" hotkey needs the room connection, so it is handled here
+  useEffect(() => {
+    if (!isJoined) return;
+    const unlisten = listen<string>('hotkey-action', (event) => {
+      if (event.payload === 'trigger_squad_clip') {
+        triggerClip();
+      }
+    });
+    return () => {
+      unlisten.then((fn) => fn());
+    };
+  }, [isJoined, triggerClip]);
+
   const handleJoin = (id: string, name: string) => {
     setRoomId(id);
     setDisplayName(name);
@@ -1,10 +1,12 @@
 import { useEffect, useCallback } from 'react';
 import { invoke } from '@tauri-apps/api/core';
+import { listen } from '@tauri-apps/api/event';
 
 import { useRecordingStore } from '../stores/recordingStore';
 import { useToastStore } from '../stores/toastStore';
 import { REPLAY_BUFFER_DELAY, CLIP_SAVE_DELAY } from '@squadsync/shared';
 import { logger } from '../lib/logger';
+import type { ClockHealth } from '../types/config';
 
 export function useRecorder() {
   const { status, isReplayActive, isBuffering, setStatus, setReplayActive, setBuffering } =
@@ -12,19 +14,19 @@ export function useRecorder() {
 
   const { showToast } = useToastStore();
 
+  // Global hotkeys are handled natively; keep the UI in step with what they did
   useEffect(() => {
-    const handleKeyDown = (e: KeyboardEvent) => {
-      // Alt+F10 to save clip
-      if (e.altKey && e.key === 'F10') {
-        if (isReplayActive) {
-          saveReplay();
-        }
+    const unlisten = listen<string>('hotkey-action', (event) => {
+      if (event.payload === 'toggle_buffer') {
+        const active = !useRecordingStore.getState().isReplayActive;
+        setReplayActive(active);
+        setStatus(active ? 'Replay Buffer Active' : 'Idle');
       }
+    });
+    return () => {
+      unlisten.then((fn) => fn());
     };
-
-    window.addEventListener('keydown', handleKeyDown);
-    return () => window.removeEventListener('keydown', handleKeyDown);
-  }, [isReplayActive]);
+  }, [setReplayActive, setStatus]);
 
   const enableReplay = useCallback(async () => {
     try {
@@ -59,7 +61,7 @@ export function useRecorder() {
   }, [setReplayActive, setStatus, showToast]);
 
   const saveReplay = useCallback(
-    async (timestamp?: number, uploadUrl?: string) => {
+    async (timestamp?: number, uploadUrl?: string, clipId?: string) => {
       try {
         setStatus('Saving Clip...');
         // Pass timestamp to backend (maps to trigger_timestamp in Rust)
@@ -69,12 +71,28 @@ export function useRecorder() {
           duration_ms: number;
           start_time_utc_ms: number | null;
           version: number;
+          markers: { label: string; offset_ms: number }[];
+          game: string | null;
+          clock: ClockHealth;
+          av_skew_ms: number | null;
         }
 
+        interface ClipHashes {
+          sha256: string;
+          md5: string;
+          size: number;
+        }
+        let hashes: ClipHashes | null = null;
+
         const savedReplay = await invoke<SavedReplay>('save_replay', {
           trigger_timestamp: timestamp,
         });
         const filePath = savedReplay.file_path;
+        if (!savedReplay.clock.synced) {
+          logger.warn(
+            `⚠️ Clip saved with an unsynced clock (uncertainty: ${savedReplay.clock.uncertainty_ms ?? 'unknown'} ms)`
+          );
+        }
 
         setStatus(`Clip Saved!`);
         showToast('Clip Saved Successfully!', 'success');
@@ -91,9 +109,10 @@ export function useRecorder() {
               logger.info(`📤 Uploading ${filePath} to ${uploadUrl}`);
 
               // Use Rust backend for upload (Streaming)
-              await invoke('upload_clip_to_url', {
+              hashes = await invoke<ClipHashes | null>('upload_clip_to_url', {
                 filePath,
                 uploadUrl,
+                clipId,
               });
 
               logger.info('✅ Upload Successful');
@@ -118,6 +137,7 @@ export function useRecorder() {
         return {
           startTime: savedReplay.start_time_utc_ms,
           duration: savedReplay.duration_ms,
+          hashes,
         };
       } catch (e) {
         setStatus(`Error saving: ${e}`);
@@ -1,7 +1,15 @@
 import { useEffect, useState, useRef } from 'react';
+import { invoke } from '@tauri-apps/api/core';
+import { listen } from '@tauri-apps/api/event';
 import { PartyKitClient } from '../lib/partykit';
 import { logger } from '../lib/logger';
-import { RoomState, RoomMember } from '@squadsync/shared';
+import {
+  RoomState,
+  RoomMember,
+  TIME_SYNC_BURST,
+  TIME_SYNC_BURST_GAP_MS,
+  TIME_SYNC_INTERVAL_MS,
+} from '@squadsync/shared';
 import { PARTYKIT_HOST } from '../lib/constants';
 import { useToastStore } from '../stores/toastStore';
 
@@ -19,6 +27,8 @@ export function useRoom(
   const clientRef = useRef<PartyKitClient | null>(null);
   const onClipStartRef = useRef(onClipStart);
   const pendingClipRef = useRef<{ clipId: string; referenceTime: number } | null>(null);
+  // Queued uploads waiting for a fresh presigned URL, by clip ID
+  const expiredUploadsRef = useRef<Map<string, string>>(new Map());
 
   useEffect(() => {
     onClipStartRef.current = onClipStart;
@@ -95,6 +105,19 @@ export function useRoom(
             };
           });
           break;
+        case 'TIME_SYNC_RESPONSE': {
+          // Four-timestamp exchange; the backend combines it with NTP
+          const exchange = {
+            clientSend: msg.clientTime,
+            serverReceive: msg.serverReceive,
+            serverSend: msg.serverSend,
+            clientReceive: Date.now(),
+          };
+          invoke('submit_time_sync', { exchange }).catch((e) =>
+            logger.warn('Time sync sample rejected:', e)
+          );
+          break;
+        }
         case 'START_CLIP':
           logger.info('🎥 START_CLIP received:', msg);
           // Store reference time and request upload URL
@@ -110,7 +133,13 @@ export function useRoom(
           break;
         case 'UPLOAD_URL_GRANTED':
           logger.info('✅ UPLOAD_URL_GRANTED received:', msg);
-          if (pendingClipRef.current && pendingClipRef.current.clipId === msg.clipId) {
+          if (expiredUploadsRef.current.has(msg.clipId)) {
+            const uploadId = expiredUploadsRef.current.get(msg.clipId);
+            expiredUploadsRef.current.delete(msg.clipId);
+            invoke('refresh_upload_url', { id: uploadId, uploadUrl: msg.uploadUrl }).catch((e) =>
+              logger.error('Failed to refresh upload URL:', e)
+            );
+          } else if (pendingClipRef.current && pendingClipRef.current.clipId === msg.clipId) {
             // Now trigger the actual recording/upload
             onClipStartRef.current?.(
               pendingClipRef.current.referenceTime,
@@ -146,6 +175,36 @@ export function useRoom(
     };
   }, [roomId, userId]); // Only reconnect if room or user ID changes
 
+  // Queued uploads whose presigned URL expired ask for a new one
+  useEffect(() => {
+    if (connectionState !== 'connected') return;
+    const unlisten = listen<{ id: string; clip_id?: string }>('upload-url-expired', (event) => {
+      const { id, clip_id: clipId } = event.payload;
+      if (!clipId || !clientRef.current) return;
+      logger.info('📤 Upload URL expired, requesting a new one for clip:', clipId);
+      expiredUploadsRef.current.set(clipId, id);
+      clientRef.current.send({ type: 'REQUEST_UPLOAD_URL', clipId });
+    });
+    return () => {
+      unlisten.then((fn) => fn());
+    };
+  }, [connectionState]);
+
+  // Clock sync against the room server, which keeps the squad on one clock without NTP
+  useEffect(() => {
+    if (connectionState !== 'connected') return;
+    const requestTimeSync = () =>
+      clientRef.current?.send({ type: 'TIME_SYNC_REQUEST', clientTime: Date.now() });
+    const burst = Array.from({ length: TIME_SYNC_BURST }, (_, i) =>
+      setTimeout(requestTimeSync, i * TIME_SYNC_BURST_GAP_MS)
+    );
+    const interval = setInterval(requestTimeSync, TIME_SYNC_INTERVAL_MS);
+    return () => {
+      burst.forEach(clearTimeout);
+      clearInterval(interval);
+    };
+  }, [connectionState]);
+
   // Separate effect for joining/updating user info
   useEffect(() => {
     if (connectionState === 'connected' && clientRef.current) {
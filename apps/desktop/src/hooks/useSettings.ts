@@ -1,6 +1,7 @@
 import { useEffect } from 'react';
 import { invoke } from '@tauri-apps/api/core';
-import { AppConfig } from '../types/config';
+import { open, save } from '@tauri-apps/plugin-dialog';
+import { AppConfig, AudioDeviceInfo, BenchmarkReport, FieldError, MonitorIdentity, RecordingProfile } from '../types/config';
 import { useToastStore } from '../stores/toastStore';
 import { useSettingsStore } from '../stores/settingsStore';
 import { logger } from '../lib/logger';
@@ -11,6 +12,7 @@ export interface MonitorInfo {
   width: number;
   height: number;
   is_primary: boolean;
+  identity: MonitorIdentity;
 }
 
 export function useSettings() {
@@ -27,16 +29,15 @@ export function useSettings() {
   async function loadData() {
     try {
       store.setLoading(true);
-      const [loadedConfig, devices, systemDevices, monitorList] = await Promise.all([
+      const [loadedConfig, devices, monitorList] = await Promise.all([
         invoke<AppConfig>('get_config'),
-        invoke<string[]>('get_audio_devices'),
-        invoke<string[]>('get_system_audio_devices'),
+        invoke<AudioDeviceInfo[]>('list_audio_devices'),
         invoke<MonitorInfo[]>('get_monitors'),
       ]);
 
       store.setConfig(loadedConfig);
-      store.setAudioDevices(devices);
-      store.setSystemAudioDevices(systemDevices);
+      store.setAudioDevices(devices.filter((d) => d.kind === 'input'));
+      store.setSystemAudioDevices(devices.filter((d) => d.kind === 'output'));
       store.setMonitors(monitorList);
     } catch (e) {
       logger.error('Failed to load settings:', e);
@@ -50,6 +51,12 @@ export function useSettings() {
 
     store.setSaving(true);
     try {
+      const fieldErrors = await invoke<FieldError[]>('validate_config', { config: store.config });
+      store.setFieldErrors(fieldErrors);
+      if (fieldErrors.length > 0) {
+        showToast(`Please fix ${fieldErrors.length} invalid setting(s)`, 'error');
+        return;
+      }
       await invoke('update_config', { newConfig: store.config });
       showToast('Settings saved successfully!', 'success');
     } catch (e) {
@@ -60,6 +67,73 @@ export function useSettings() {
     }
   }
 
+  /** Stores the device's ID, which tells identical headsets apart, and its name as a fallback. */
+  function selectAudioDevice(field: 'audio_source' | 'system_audio_device', device?: AudioDeviceInfo) {
+    store.updateRecordingConfig(field, device?.name ?? null);
+    store.updateRecordingConfig(`${field}_id`, device?.id ?? null);
+  }
+
+  /** Applied right away, restarting the buffer if it's running. */
+  async function setActiveProfile(name: string | null) {
+    try {
+      await invoke('set_active_profile', { name });
+      store.setConfig(await invoke<AppConfig>('get_config'));
+      showToast(name ? `Switched to ${name}` : 'Recording with your own settings', 'success');
+    } catch (e) {
+      logger.error('Failed to switch profile:', e);
+      showToast(`Error switching profile: ${e}`, 'error');
+    }
+  }
+
+  async function importProfile() {
+    const path = await open({ filters: [{ name: 'Recording profile', extensions: ['toml'] }] });
+    if (typeof path !== 'string') return;
+    try {
+      const profile = await invoke<RecordingProfile>('import_profile', { path });
+      store.setConfig(await invoke<AppConfig>('get_config'));
+      showToast(`Imported ${profile.name}`, 'success');
+    } catch (e) {
+      logger.error('Failed to import profile:', e);
+      showToast(`Error importing profile: ${e}`, 'error');
+    }
+  }
+
+  async function exportProfile(name: string) {
+    const path = await save({
+      defaultPath: `${name}.toml`,
+      filters: [{ name: 'Recording profile', extensions: ['toml'] }],
+    });
+    if (!path) return;
+    try {
+      await invoke('export_profile', { name, path });
+      showToast(`Exported ${name}`, 'success');
+    } catch (e) {
+      logger.error('Failed to export profile:', e);
+      showToast(`Error exporting profile: ${e}`, 'error');
+    }
+  }
+
+  /** Takes a while: each encoder runs a few seconds per quality tier. */
+  async function runBenchmark(apply: boolean) {
+    try {
+      const report = await invoke<BenchmarkReport>('benchmark_encoders', { apply });
+      if (!report.recommended) {
+        showToast('No encoder kept up, even at 720p30', 'error');
+      } else if (report.applied) {
+        store.setConfig(await invoke<AppConfig>('get_config'));
+        showToast(`Switched to the ${report.recommended.name} profile`, 'success');
+      } else {
+        const { resolution, framerate } = report.recommended;
+        showToast(`This machine sustains ${resolution} at ${framerate} fps`, 'success');
+      }
+      return report;
+    } catch (e) {
+      logger.error('Failed to benchmark encoders:', e);
+      showToast(`Error benchmarking encoders: ${e}`, 'error');
+      return null;
+    }
+  }
+
   return {
     config: store.config,
     audioDevices: store.audioDevices,
@@ -67,7 +141,13 @@ export function useSettings() {
     monitors: store.monitors,
     loading: store.loading,
     saving: store.saving,
+    fieldErrors: store.fieldErrors,
     saveSettings,
+    selectAudioDevice,
+    setActiveProfile,
+    importProfile,
+    exportProfile,
+    runBenchmark,
     updateRecordingConfig: store.updateRecordingConfig,
     updateUserConfig: store.updateUserConfig,
   };
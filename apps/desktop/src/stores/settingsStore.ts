@@ -1,21 +1,23 @@
 import { create } from 'zustand';
-import { AppConfig } from '../types/config';
+import { AppConfig, AudioDeviceInfo, FieldError } from '../types/config';
 import { MonitorInfo } from '../hooks/useSettings';
 
 interface SettingsState {
   config: AppConfig | null;
-  audioDevices: string[];
-  systemAudioDevices: string[];
+  audioDevices: AudioDeviceInfo[];
+  systemAudioDevices: AudioDeviceInfo[];
   monitors: MonitorInfo[];
   loading: boolean;
   saving: boolean;
+  fieldErrors: FieldError[];
 
   setConfig: (config: AppConfig | null) => void;
-  setAudioDevices: (devices: string[]) => void;
-  setSystemAudioDevices: (devices: string[]) => void;
+  setAudioDevices: (devices: AudioDeviceInfo[]) => void;
+  setSystemAudioDevices: (devices: AudioDeviceInfo[]) => void;
   setMonitors: (monitors: MonitorInfo[]) => void;
   setLoading: (loading: boolean) => void;
   setSaving: (saving: boolean) => void;
+  setFieldErrors: (fieldErrors: FieldError[]) => void;
 
   // Actions to update specific parts of config
   updateUserConfig: (key: string, value: unknown) => void;
@@ -29,6 +31,7 @@ export const useSettingsStore = create<SettingsState>((set) => ({
   monitors: [],
   loading: true,
   saving: false,
+  fieldErrors: [],
 
   setConfig: (config) => set({ config }),
   setAudioDevices: (audioDevices) => set({ audioDevices }),
@@ -36,6 +39,7 @@ export const useSettingsStore = create<SettingsState>((set) => ({
   setMonitors: (monitors) => set({ monitors }),
   setLoading: (loading) => set({ loading }),
   setSaving: (saving) => set({ saving }),
+  setFieldErrors: (fieldErrors) => set({ fieldErrors }),
 
   updateUserConfig: (key, value) =>
     set((state) => {
@@ -13,7 +13,133 @@ export interface RecordingConfig {
   segment_wrap: number;
 }
 
+export interface MonitorIdentity {
+  name: string;
+  x: number;
+  y: number;
+  width: number;
+  height: number;
+}
+
+export type CaptureTarget =
+  | { type: 'monitor'; monitor?: MonitorIdentity | null }
+  | { type: 'window'; title?: string | null; process?: string | null }
+  | { type: 'region'; monitor?: MonitorIdentity | null; x: number; y: number; width: number; height: number };
+
+export type HotkeyAction =
+  | 'save_replay'
+  | 'save_30s'
+  | 'save_60s'
+  | 'save_2m'
+  | 'toggle_buffer'
+  | 'add_marker'
+  | 'trigger_squad_clip';
+
+export interface PluginApiConfig {
+  enabled: boolean;
+  port: number;
+  token: string;
+  triggers_per_minute: number;
+  events_per_minute: number;
+}
+
+export interface GsiRule {
+  event: string;
+  count?: number;
+  within_secs?: number;
+  save_secs: number;
+  label?: string;
+}
+
+export interface GsiConfig {
+  enabled: boolean;
+  port: number;
+  token: string;
+  rules: GsiRule[];
+}
+
+export interface GameDetectionConfig {
+  enabled: boolean;
+  grace_secs: number;
+  /** `profile` names a recording profile to switch to while the game runs. */
+  games: { name: string; executable: string; profile?: string }[];
+}
+
+export interface AudioDeviceInfo {
+  id: string;
+  name: string;
+  kind: 'input' | 'output';
+  is_default: boolean;
+  host_api: string;
+}
+
+/** Named overrides of `recording`; unset fields keep the recording value. */
+export interface RecordingProfile {
+  name: string;
+  resolution?: string;
+  framerate?: number;
+  bitrate?: string;
+  encoder?: string;
+  video_codec?: 'h264' | 'hevc' | 'av1';
+  video_preset?: string;
+  video_tune?: string;
+  video_profile?: string;
+  audio_bitrate?: string;
+}
+
+export interface BenchmarkResult {
+  encoder: string;
+  hardware: boolean;
+  tier: number;
+  width: number;
+  height: number;
+  framerate: number;
+  preset: string;
+  realtime_factor: number;
+  cpu_seconds: number;
+  cpu_share: number;
+  sustained: boolean;
+  error: string | null;
+}
+
+export interface BenchmarkReport {
+  results: BenchmarkResult[];
+  recommended: RecordingProfile | null;
+  applied: boolean;
+}
+
+export interface ClockConfig {
+  ntp_servers: string[];
+  samples_per_server: number;
+  sync_interval_secs: number;
+}
+
+/** Returned by `get_clock_health`, emitted as `clock-health` and attached to saved replays. */
+export interface ClockHealth {
+  synced: boolean;
+  source: 'ntp' | 'signaling' | 'system';
+  servers: string[];
+  last_sync_ms: number | null;
+  uncertainty_ms: number | null;
+  offset_ms: number;
+  drift_ppm: number;
+}
+
+/** A setting `validate_config` rejected; `field` is a dotted path like "recording.framerate". */
+export interface FieldError {
+  field: string;
+  message: string;
+}
+
 export interface AppConfig {
+  version?: number;
+  profiles?: RecordingProfile[];
+  active_profile?: string | null;
+  hotkeys?: Partial<Record<HotkeyAction, string>>;
+  plugin_api?: PluginApiConfig;
+  gsi?: GsiConfig;
+  game_detection?: GameDetectionConfig;
+  clock?: ClockConfig;
   user: {
     display_name: string | null;
     user_id: string | null;
@@ -24,12 +150,16 @@ export interface AppConfig {
     framerate: number;
     bitrate: string;
     monitor_index: number;
+    capture_target?: CaptureTarget;
     video_profile?: string;
     audio_bitrate?: string;
     mic_audio_delay?: number;
     encoder: string;
+    video_codec?: 'h264' | 'hevc' | 'av1';
     audio_source?: string;
+    audio_source_id?: string | null;
     system_audio_device?: string;
+    system_audio_device_id?: string | null;
     audio_codec?: string;
     buffer_duration?: number;
     segment_time?: number;
@@ -251,12 +251,32 @@ export default class Server implements Party.Server {
             const playbackUrl = `https://clips.fluxreplay.com/${keyVerify}`;
 
             try {
-              await s3Verify.send(
+              const head = await s3Verify.send(
                 new HeadObjectCommand({
                   Bucket: bucketVerify,
                   Key: keyVerify,
                 })
               );
+
+              // Check the stored object against what the client says it sent
+              const etag = head.ETag?.replace(/"/g, '').toLowerCase();
+              const etagIsMd5 = !!etag && /^[0-9a-f]{32}$/.test(etag);
+              const sizeMismatch =
+                msg.sizeBytes !== undefined && head.ContentLength !== undefined && head.ContentLength !== msg.sizeBytes;
+              const md5Mismatch = !!msg.md5 && etagIsMd5 && etag !== msg.md5.toLowerCase();
+              if (sizeMismatch || md5Mismatch) {
+                this.logger.error(
+                  `❌ Upload integrity mismatch for ${keyVerify}: size ${head.ContentLength}/${msg.sizeBytes}, etag ${etag}/${msg.md5}`
+                );
+                sender.send(
+                  JSON.stringify({
+                    type: 'ERROR',
+                    code: 'UPLOAD_VERIFICATION_FAILED',
+                    message: `Uploaded file does not match: ${keyVerify}`,
+                  })
+                );
+                return;
+              }
               this.logger.info(`✅ File verified in R2: ${keyVerify}`);
 
               // Update clip metadata
@@ -274,6 +294,7 @@ export default class Server implements Party.Server {
                 timestamp: Date.now(),
                 videoStartTimeMs: msg.videoStartTimeMs,
                 durationMs: msg.durationMs,
+                sha256: msg.sha256,
               };
 
               // Remove existing view from same author if any
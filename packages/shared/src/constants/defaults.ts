@@ -2,3 +2,7 @@ export const DEFAULT_BUFFER_SECONDS = 60;
 export const DEFAULT_SEGMENT_TIME = 15;
 export const DEFAULT_FRAMERATE = 60;
 export const DEFAULT_RESOLUTION = '1920x1080';
+// Room server time sync: a burst on connect, then one exchange per interval (rate limit: 10/min)
+export const TIME_SYNC_BURST = 4;
+export const TIME_SYNC_BURST_GAP_MS = 2000;
+export const TIME_SYNC_INTERVAL_MS = 60000;
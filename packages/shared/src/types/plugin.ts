@@ -1,14 +1,20 @@
+/** A game event. The desktop app turns it into a marker (label: `metadata.label` or `type`). */
 export interface PluginEvent {
+  pluginId: string;
   eventId: string;
+  /** Unix ms on the local clock; 0 = now */
   timestamp: number;
   game: string;
   type: string;
   metadata: Record<string, unknown>;
 }
 
+/** Asks the desktop app to save a replay ending at `timestamp`. */
 export interface ClipTrigger {
   pluginId: string;
+  /** Unix ms on the local clock; 0 = now */
   timestamp: number;
+  /** Seconds, capped at the buffer length */
   duration: number;
   label: string;
 }
@@ -35,6 +35,11 @@ export interface UploadCompleteMessage {
   key?: string;
   videoStartTimeMs: number;
   durationMs: number;
+  /** Hex SHA-256 of the uploaded file, for verifying downloads */
+  sha256?: string;
+  /** Hex MD5 of the uploaded file, compared with the stored object's ETag */
+  md5?: string;
+  sizeBytes?: number;
 }
 
 export type ClientMessage =
@@ -90,6 +95,7 @@ export interface View {
   timestamp: number;
   videoStartTimeMs: number;
   durationMs: number;
+  sha256?: string;
 }
 
 export interface ClipUpdatedMessage {
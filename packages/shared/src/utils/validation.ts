@@ -27,6 +27,9 @@ export const UploadCompleteSchema = z.object({
   key: z.string().optional(),
   videoStartTimeMs: z.number(),
   durationMs: z.number(),
+  sha256: z.string().optional(),
+  md5: z.string().optional(),
+  sizeBytes: z.number().optional(),
 });
 
 export const RequestUploadUrlSchema = z.object({